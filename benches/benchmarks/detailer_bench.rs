@@ -1,5 +1,7 @@
+use std::sync::Arc;
+
 use criterion::{criterion_group, Criterion};
-use detailer::{detail, new_detailer, scope};
+use detailer::{detail, detail_static, new_detailer, scope, NullSink};
 
 #[allow(clippy::expect_used)] // this is a benchmark, lints like this don't matter.
 fn detailing(c: &mut Criterion) {
@@ -36,6 +38,16 @@ fn detailing(c: &mut Criterion) {
         })
     });
 
+    group.bench_function("enabled with time static", |bencher| {
+        bencher.iter(|| {
+            let mut detailer = new_detailer!(Info, WithTiming);
+            detail_static!(detailer, "it does something");
+            let _guard = scope!(detailer, "suspended");
+            detail_static!(detailer, "it does something else");
+            detail_static!(detailer, "it does something else again");
+        })
+    });
+
     group.bench_function("cached enabled with time", |bencher| {
         let mut detailer = new_detailer!(Info, WithTiming);
         bencher.iter(|| {
@@ -47,6 +59,19 @@ fn detailing(c: &mut Criterion) {
             detailer.flush();
         })
     });
+
+    group.bench_function("cached enabled with time null sink", |bencher| {
+        let mut detailer = new_detailer!(Info, WithTiming);
+        detailer.sink(Arc::new(NullSink));
+        bencher.iter(|| {
+            detailer.reset();
+            detail!(detailer, "it does something");
+            let _guard = scope!(detailer, "suspended");
+            detail!(detailer, "it does something else");
+            detail!(detailer, "it does something else again");
+            detailer.flush();
+        })
+    });
 }
 
 criterion_group!(benches, detailing);
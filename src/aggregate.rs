@@ -0,0 +1,126 @@
+use alloc::{
+    collections::BTreeMap,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::{fmt::Display, time::Duration};
+
+use crate::{diff::with_durations, parse};
+
+/// Summarizes where time is spent across many finished traces.
+///
+/// Each line of each trace is a sample for its message: scopes sample how
+/// long the whole scope took, and checkpoints sample how long it was until
+/// the next line. Use this to summarize a load test instead of reading
+/// thousands of traces one by one.
+///
+/// ```rust
+/// use std::time::Duration;
+///
+/// use detailer::Aggregator;
+///
+/// let mut aggregator = Aggregator::default();
+/// for query_micros in 1..=100 {
+///     aggregator.ingest(&format!(
+///         "0      authenticating\n10     query\n{}    done",
+///         10 + query_micros
+///     ));
+/// }
+///
+/// let query = aggregator
+///     .summaries()
+///     .into_iter()
+///     .find(|summary| summary.name == "query");
+/// assert_eq!(Some(Duration::from_micros(95)), query.map(|query| query.p95));
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Aggregator {
+    names: BTreeMap<String, usize>,
+    samples: Vec<(String, Vec<Duration>)>,
+}
+
+impl Aggregator {
+    /// Add the lines of a finished trace, as it was flushed.
+    ///
+    /// Traces recorded without timing have nothing to sample.
+    pub fn ingest(&mut self, trace: &str) {
+        for (event, duration) in with_durations(parse::events(trace)) {
+            let Some(duration) = duration else {
+                continue;
+            };
+            let index = match self.names.get(event.message.as_str()) {
+                Some(index) => *index,
+                None => {
+                    self.names
+                        .insert(event.message.to_string(), self.samples.len());
+                    self.samples.push((event.message.into(), Vec::new()));
+                    self.samples.len() - 1
+                }
+            };
+            self.samples[index].1.push(duration);
+        }
+    }
+
+    /// Percentiles for every line seen so far, in the order they were first
+    /// seen.
+    pub fn summaries(&self) -> Vec<Summary> {
+        self.samples
+            .iter()
+            .map(|(name, samples)| {
+                let mut sorted = samples.clone();
+                sorted.sort_unstable();
+                Summary {
+                    name: name.clone(),
+                    count: sorted.len(),
+                    p50: percentile(&sorted, 50),
+                    p95: percentile(&sorted, 95),
+                    p99: percentile(&sorted, 99),
+                }
+            })
+            .collect()
+    }
+}
+
+/// Nearest-rank percentile of a sorted, non-empty list.
+fn percentile(sorted: &[Duration], percent: usize) -> Duration {
+    let rank = (sorted.len() * percent).div_ceil(100).max(1);
+    sorted[rank - 1]
+}
+
+/// How long one line took across all the ingested traces.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Summary {
+    /// The line's message
+    pub name: String,
+    /// How many times the line was seen
+    pub count: usize,
+    /// The median duration
+    pub p50: Duration,
+    /// The 95th percentile duration
+    pub p95: Duration,
+    /// The 99th percentile duration
+    pub p99: Duration,
+}
+
+/// Renders a table with one row per line.
+impl Display for Aggregator {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        writeln!(
+            f,
+            "{:<8} {:<8} {:<8} {:<8} name",
+            "count", "p50µs", "p95µs", "p99µs"
+        )?;
+        for summary in self.summaries() {
+            writeln!(
+                f,
+                "{:<8} {:<8} {:<8} {:<8} {}",
+                summary.count,
+                summary.p50.as_micros(),
+                summary.p95.as_micros(),
+                summary.p99.as_micros(),
+                summary.name,
+            )?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,232 @@
+//! A compact binary form of traces, for sending them between processes or
+//! storing them more cheaply than their text.
+//!
+//! The encoding starts with the magic bytes `DTLR` and a version byte, so
+//! stored traces can be recognized and newer encodings told apart. Messages
+//! are interned, so a message recorded many times is stored once, and times
+//! are varints of the difference from the line before.
+//!
+//! ```rust
+//! use std::time::Duration;
+//!
+//! use detailer::{binary, detail, new_detailer, scope};
+//!
+//! let mut detailer = new_detailer!(Info, WithoutTiming);
+//! for _ in 0..3 {
+//!     let _scope = scope!(detailer, "polling");
+//!     detail!(detailer, "nothing new");
+//! }
+//! let bytes = detailer.to_bytes();
+//! assert!(bytes.len() < detailer.peek().len());
+//!
+//! let lines = binary::from_bytes(&bytes).unwrap();
+//! assert_eq!(6, lines.len());
+//! assert_eq!(
+//!     binary::Line {
+//!         level: log::Level::Info,
+//!         elapsed: None,
+//!         indent: 1,
+//!         message: "nothing new".to_string(),
+//!     },
+//!     lines[1],
+//! );
+//! ```
+
+use alloc::{collections::BTreeMap, string::String, vec::Vec};
+use core::{fmt::Display, time::Duration};
+
+use crate::Detailer;
+
+const MAGIC: &[u8] = b"DTLR";
+const VERSION: u8 = 1;
+/// Set in a line's level byte when the line has a time.
+const TIMED: u8 = 0x8;
+
+/// One line of a decoded trace.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Line {
+    /// The level the line was logged at
+    pub level: log::Level,
+    /// Time since the trace started, if the trace was recorded with timing
+    pub elapsed: Option<Duration>,
+    /// How many scopes deep the line was recorded
+    pub indent: usize,
+    /// The line's message
+    pub message: String,
+}
+
+/// Why bytes could not be decoded as a trace.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum DecodeError {
+    /// The bytes don't start with the magic bytes
+    NotATrace,
+    /// The trace was encoded with a version this doesn't read
+    UnsupportedVersion(u8),
+    /// The bytes end in the middle of the trace, or have something that
+    /// isn't part of one
+    Malformed,
+}
+
+impl Display for DecodeError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::NotATrace => f.write_str("not an encoded trace"),
+            Self::UnsupportedVersion(version) => {
+                write!(f, "unsupported trace encoding version {version}")
+            }
+            Self::Malformed => f.write_str("malformed encoded trace"),
+        }
+    }
+}
+
+impl core::error::Error for DecodeError {}
+
+impl Detailer {
+    /// Encode the lines recorded so far in the compact [binary](crate::binary)
+    /// form. The text's layout, like its line format, isn't kept; the lines
+    /// can be read back with [`binary::from_bytes()`](crate::binary::from_bytes).
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut messages: Vec<&str> = Vec::new();
+        let mut interned = BTreeMap::new();
+        let mut lines = Vec::new();
+        let mut count = 0;
+        let mut previous = 0;
+        for line in self.recorded_lines() {
+            count += 1;
+            let index = *interned.entry(line.message).or_insert_with(|| {
+                messages.push(line.message);
+                messages.len() - 1
+            });
+            let level = line.level as u8;
+            match line.elapsed {
+                Some(elapsed) => {
+                    let elapsed = elapsed.as_micros() as i64;
+                    lines.push(level | TIMED);
+                    write_varint(&mut lines, zigzag(elapsed - previous));
+                    previous = elapsed;
+                }
+                None => lines.push(level),
+            }
+            write_varint(&mut lines, line.indent as u64);
+            write_varint(&mut lines, index as u64);
+        }
+
+        let mut bytes = Vec::with_capacity(lines.len() + self.peek().len() / 2);
+        bytes.extend_from_slice(MAGIC);
+        bytes.push(VERSION);
+        write_varint(&mut bytes, messages.len() as u64);
+        for message in &messages {
+            write_varint(&mut bytes, message.len() as u64);
+            bytes.extend_from_slice(message.as_bytes());
+        }
+        write_varint(&mut bytes, count);
+        bytes.extend_from_slice(&lines);
+        bytes
+    }
+}
+
+/// Decode a trace encoded by [`Detailer::to_bytes()`].
+///
+/// Bytes that only look like a trace are an error, not a panic:
+///
+/// ```rust
+/// use detailer::binary::{self, DecodeError};
+///
+/// // Two lines that are each as late as an i64 goes after the one before.
+/// let line = [3 | 0x8, 0xfe, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x01, 0, 0];
+/// let mut bytes = b"DTLR\x01\x01\x00\x02".to_vec();
+/// bytes.extend_from_slice(&line);
+/// bytes.extend_from_slice(&line);
+/// assert_eq!(Err(DecodeError::Malformed), binary::from_bytes(&bytes));
+/// ```
+pub fn from_bytes(bytes: &[u8]) -> Result<Vec<Line>, DecodeError> {
+    let rest = bytes.strip_prefix(MAGIC).ok_or(DecodeError::NotATrace)?;
+    let (&version, mut rest) = rest.split_first().ok_or(DecodeError::Malformed)?;
+    if version != VERSION {
+        return Err(DecodeError::UnsupportedVersion(version));
+    }
+
+    let count = read_varint(&mut rest)?;
+    let mut messages = Vec::new();
+    for _ in 0..count {
+        let length = read_varint(&mut rest)? as usize;
+        if rest.len() < length {
+            return Err(DecodeError::Malformed);
+        }
+        let (message, after) = rest.split_at(length);
+        messages.push(core::str::from_utf8(message).map_err(|_| DecodeError::Malformed)?);
+        rest = after;
+    }
+
+    let count = read_varint(&mut rest)?;
+    let mut lines = Vec::new();
+    let mut previous: i64 = 0;
+    for _ in 0..count {
+        let (&flags, after) = rest.split_first().ok_or(DecodeError::Malformed)?;
+        rest = after;
+        let level = match flags & !TIMED {
+            1 => log::Level::Error,
+            2 => log::Level::Warn,
+            3 => log::Level::Info,
+            4 => log::Level::Debug,
+            5 => log::Level::Trace,
+            _ => return Err(DecodeError::Malformed),
+        };
+        let elapsed = if flags & TIMED == 0 {
+            None
+        } else {
+            previous = previous
+                .checked_add(unzigzag(read_varint(&mut rest)?))
+                .ok_or(DecodeError::Malformed)?;
+            Some(Duration::from_micros(
+                u64::try_from(previous).map_err(|_| DecodeError::Malformed)?,
+            ))
+        };
+        let indent = read_varint(&mut rest)? as usize;
+        let message = messages
+            .get(read_varint(&mut rest)? as usize)
+            .ok_or(DecodeError::Malformed)?;
+        lines.push(Line {
+            level,
+            elapsed,
+            indent,
+            message: String::from(*message),
+        });
+    }
+    if !rest.is_empty() {
+        return Err(DecodeError::Malformed);
+    }
+    Ok(lines)
+}
+
+fn write_varint(out: &mut Vec<u8>, mut value: u64) {
+    while 0x80 <= value {
+        out.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+fn read_varint(bytes: &mut &[u8]) -> Result<u64, DecodeError> {
+    let mut value = 0;
+    for shift in (0..64).step_by(7) {
+        let (&byte, rest) = bytes.split_first().ok_or(DecodeError::Malformed)?;
+        *bytes = rest;
+        value |= u64::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(DecodeError::Malformed)
+}
+
+/// Times usually go forward, but lines appended from another detailer can
+/// step back, so differences are signed.
+fn zigzag(value: i64) -> u64 {
+    ((value << 1) ^ (value >> 63)) as u64
+}
+
+fn unzigzag(value: u64) -> i64 {
+    (value >> 1) as i64 ^ -((value & 1) as i64)
+}
@@ -0,0 +1,108 @@
+/// A source of timestamps for detail lines, in microseconds since any fixed
+/// point in time.
+///
+/// Detailers only subtract timestamps from each other, so the clock doesn't
+/// have to know the time of day. Firmware might read a hardware timer:
+///
+/// ```rust
+/// use std::sync::atomic::{AtomicU64, Ordering};
+///
+/// use detailer::{detail, Detailer, TimingSetting};
+///
+/// static TICKS: AtomicU64 = AtomicU64::new(0);
+///
+/// fn timer_micros() -> u64 {
+///     TICKS.load(Ordering::Relaxed)
+/// }
+///
+/// let mut detailer = Detailer::new(
+///     log::LevelFilter::Info,
+///     TimingSetting::WithClock(timer_micros),
+///     1024,
+/// );
+/// TICKS.store(125, Ordering::Relaxed);
+/// detail!(detailer, "sensor read");
+/// assert_eq!("125    sensor read\n", detailer.peek());
+/// ```
+///
+/// In the browser, where there is no platform clock, `performance.now()`
+/// works through whichever bindings the application already uses:
+///
+/// ```rust,ignore
+/// fn performance_micros() -> u64 {
+///     web_sys::window()
+///         .and_then(|window| window.performance())
+///         .map(|performance| (performance.now() * 1000.0) as u64)
+///         .unwrap_or_default()
+/// }
+/// ```
+pub type Clock = fn() -> u64;
+
+/// The clock `TimingSetting::WithTiming` uses, if this platform has one.
+///
+/// `std::time::Instant` panics on `wasm32-unknown-unknown`, so browser builds
+/// fall back to no timing. WASI has a working `Instant`.
+pub(crate) fn platform() -> Option<Clock> {
+    #[cfg(all(
+        feature = "std",
+        not(all(target_arch = "wasm32", target_os = "unknown"))
+    ))]
+    {
+        Some(monotonic_micros)
+    }
+    #[cfg(not(all(
+        feature = "std",
+        not(all(target_arch = "wasm32", target_os = "unknown"))
+    )))]
+    {
+        None
+    }
+}
+
+#[cfg(all(
+    feature = "std",
+    not(all(target_arch = "wasm32", target_os = "unknown"))
+))]
+fn monotonic_micros() -> u64 {
+    static EPOCH: std::sync::OnceLock<std::time::Instant> = std::sync::OnceLock::new();
+    EPOCH
+        .get_or_init(std::time::Instant::now)
+        .elapsed()
+        .as_micros() as u64
+}
+
+/// Write a time since the unix epoch as an RFC 3339 UTC timestamp with
+/// microseconds, like `2024-04-24T20:31:28.767641Z`.
+#[cfg(feature = "std")]
+pub(crate) fn write_rfc3339(
+    out: &mut impl core::fmt::Write,
+    since_epoch: core::time::Duration,
+) -> core::fmt::Result {
+    let seconds = since_epoch.as_secs();
+    let (days, second_of_day) = (seconds / 86_400, seconds % 86_400);
+
+    // Days to a civil date, from Howard Hinnant's chrono-compatible algorithms.
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let day_of_era = z % 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + u64::from(month <= 2);
+
+    write!(
+        out,
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}.{:06}Z",
+        second_of_day / 3_600,
+        second_of_day / 60 % 60,
+        second_of_day % 60,
+        since_epoch.subsec_micros(),
+    )
+}
@@ -0,0 +1,218 @@
+//! Macros named like `tracing`'s, for moving code that uses them to detail
+//! traces a find-and-replace at a time.
+//!
+//! Each macro takes the detailer first, and otherwise what the `tracing`
+//! macro of the same name does: an optional `target:`, then `key = value`
+//! fields, with `%` for `Display` and `?` for `Debug`, then the message.
+//! Fields on events are written after the message, the way `tracing`'s
+//! formatter lays them out, and fields on spans become
+//! [scope attributes](crate::scope!).
+//!
+//! ```rust
+//! use detailer::{compat::{info, info_span, warn}, new_detailer};
+//!
+//! let mut detailer = new_detailer!(Info, WithoutTiming);
+//! let user = "ada";
+//! {
+//!     let _span = info_span!(detailer, "checkout", user = %user);
+//!     info!(detailer, items = 3, "charged card");
+//!     warn!(detailer, target: "checkout::email", "receipt delayed {}s", 20);
+//! }
+//! assert_eq!(
+//!     "checkout user=ada\n  charged card items=3\n  receipt delayed 20s\n",
+//!     detailer.peek(),
+//! );
+//! ```
+//!
+//! Spans are [scopes](crate::Detailer::scope), so they are entered when they
+//! are made and shown whatever their level. Drop the `.entered()` calls
+//! after them, and keep the guards as long as the span should last.
+
+pub use crate::{
+    __compat_debug as debug, __compat_debug_span as debug_span, __compat_error as error,
+    __compat_error_span as error_span, __compat_info as info, __compat_info_span as info_span,
+    __compat_span as span, __compat_trace as trace, __compat_trace_span as trace_span,
+    __compat_warn as warn, __compat_warn_span as warn_span,
+};
+
+/// The fields of an event, written after its message.
+#[doc(hidden)]
+pub struct Fields<'a>(pub &'a [(&'static str, core::fmt::Arguments<'a>)]);
+
+impl core::fmt::Display for Fields<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        for (key, value) in self.0 {
+            write!(f, " {key}={value}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Records an event from the `compat` macros, collecting its fields first.
+#[doc(hidden)]
+#[macro_export(local_inner_macros)]
+macro_rules! __compat_event {
+    ($detail_tracker:expr, $log_level:ident, $target:tt, [$($done:tt)*] $key:ident = % $value:expr, $($rest:tt)+) => {
+        __compat_event!(
+            $detail_tracker, $log_level, $target,
+            [$($done)* (core::stringify!($key), core::format_args!("{}", $value)),]
+            $($rest)+
+        )
+    };
+    ($detail_tracker:expr, $log_level:ident, $target:tt, [$($done:tt)*] $key:ident = ? $value:expr, $($rest:tt)+) => {
+        __compat_event!(
+            $detail_tracker, $log_level, $target,
+            [$($done)* (core::stringify!($key), core::format_args!("{:?}", $value)),]
+            $($rest)+
+        )
+    };
+    ($detail_tracker:expr, $log_level:ident, $target:tt, [$($done:tt)*] $key:ident = $value:expr, $($rest:tt)+) => {
+        __compat_event!(
+            $detail_tracker, $log_level, $target,
+            [$($done)* (core::stringify!($key), core::format_args!("{}", $value)),]
+            $($rest)+
+        )
+    };
+    ($detail_tracker:expr, $log_level:ident, (), [$($done:tt)*] $($arg:tt)+) => {
+        ($detail_tracker.log(
+            log::Level::$log_level,
+            core::format_args!(
+                "{}{}",
+                core::format_args!($($arg)+),
+                $crate::compat::Fields(&[$($done)*]),
+            ))
+        )
+    };
+    ($detail_tracker:expr, $log_level:ident, ($target:expr), [$($done:tt)*] $($arg:tt)+) => {
+        ($detail_tracker.log_target(
+            log::Level::$log_level,
+            $target,
+            core::format_args!(
+                "{}{}",
+                core::format_args!($($arg)+),
+                $crate::compat::Fields(&[$($done)*]),
+            ))
+        )
+    };
+}
+
+/// Add a detail line at trace, like `tracing::trace!()`
+#[doc(hidden)]
+#[macro_export(local_inner_macros)]
+macro_rules! __compat_trace {
+    ($detail_tracker:expr, target: $target:expr, $($rest:tt)+) => {
+        __compat_event!($detail_tracker, Trace, ($target), [] $($rest)+)
+    };
+    ($detail_tracker:expr, $($rest:tt)+) => {
+        __compat_event!($detail_tracker, Trace, (), [] $($rest)+)
+    };
+}
+
+/// Add a detail line at debug, like `tracing::debug!()`
+#[doc(hidden)]
+#[macro_export(local_inner_macros)]
+macro_rules! __compat_debug {
+    ($detail_tracker:expr, target: $target:expr, $($rest:tt)+) => {
+        __compat_event!($detail_tracker, Debug, ($target), [] $($rest)+)
+    };
+    ($detail_tracker:expr, $($rest:tt)+) => {
+        __compat_event!($detail_tracker, Debug, (), [] $($rest)+)
+    };
+}
+
+/// Add a detail line at info, like `tracing::info!()`
+#[doc(hidden)]
+#[macro_export(local_inner_macros)]
+macro_rules! __compat_info {
+    ($detail_tracker:expr, target: $target:expr, $($rest:tt)+) => {
+        __compat_event!($detail_tracker, Info, ($target), [] $($rest)+)
+    };
+    ($detail_tracker:expr, $($rest:tt)+) => {
+        __compat_event!($detail_tracker, Info, (), [] $($rest)+)
+    };
+}
+
+/// Add a detail line at warn, like `tracing::warn!()`
+#[doc(hidden)]
+#[macro_export(local_inner_macros)]
+macro_rules! __compat_warn {
+    ($detail_tracker:expr, target: $target:expr, $($rest:tt)+) => {
+        __compat_event!($detail_tracker, Warn, ($target), [] $($rest)+)
+    };
+    ($detail_tracker:expr, $($rest:tt)+) => {
+        __compat_event!($detail_tracker, Warn, (), [] $($rest)+)
+    };
+}
+
+/// Add a detail line at error, like `tracing::error!()`
+#[doc(hidden)]
+#[macro_export(local_inner_macros)]
+macro_rules! __compat_error {
+    ($detail_tracker:expr, target: $target:expr, $($rest:tt)+) => {
+        __compat_event!($detail_tracker, Error, ($target), [] $($rest)+)
+    };
+    ($detail_tracker:expr, $($rest:tt)+) => {
+        __compat_event!($detail_tracker, Error, (), [] $($rest)+)
+    };
+}
+
+/// Begin a scope, like `tracing::span!()`. The level is accepted for the
+/// find-and-replace's sake, but scopes are shown at every level.
+#[doc(hidden)]
+#[macro_export(local_inner_macros)]
+macro_rules! __compat_span {
+    ($detail_tracker:expr, $log_level:expr, $name:expr, $($fields:tt)+) => {
+        ($detail_tracker.scope_with_attributes(
+            core::format_args!("{}", $name),
+            &__scope_attributes!([] $($fields)+))
+        )
+    };
+    ($detail_tracker:expr, $log_level:expr, $name:expr $(,)?) => {
+        ($detail_tracker.scope(core::format_args!("{}", $name)))
+    };
+}
+
+/// Begin a scope, like `tracing::trace_span!()`
+#[doc(hidden)]
+#[macro_export(local_inner_macros)]
+macro_rules! __compat_trace_span {
+    ($detail_tracker:expr, $($rest:tt)+) => {
+        __compat_span!($detail_tracker, (), $($rest)+)
+    };
+}
+
+/// Begin a scope, like `tracing::debug_span!()`
+#[doc(hidden)]
+#[macro_export(local_inner_macros)]
+macro_rules! __compat_debug_span {
+    ($detail_tracker:expr, $($rest:tt)+) => {
+        __compat_span!($detail_tracker, (), $($rest)+)
+    };
+}
+
+/// Begin a scope, like `tracing::info_span!()`
+#[doc(hidden)]
+#[macro_export(local_inner_macros)]
+macro_rules! __compat_info_span {
+    ($detail_tracker:expr, $($rest:tt)+) => {
+        __compat_span!($detail_tracker, (), $($rest)+)
+    };
+}
+
+/// Begin a scope, like `tracing::warn_span!()`
+#[doc(hidden)]
+#[macro_export(local_inner_macros)]
+macro_rules! __compat_warn_span {
+    ($detail_tracker:expr, $($rest:tt)+) => {
+        __compat_span!($detail_tracker, (), $($rest)+)
+    };
+}
+
+/// Begin a scope, like `tracing::error_span!()`
+#[doc(hidden)]
+#[macro_export(local_inner_macros)]
+macro_rules! __compat_error_span {
+    ($detail_tracker:expr, $($rest:tt)+) => {
+        __compat_span!($detail_tracker, (), $($rest)+)
+    };
+}
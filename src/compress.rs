@@ -0,0 +1,50 @@
+use std::io::{self, Read, Write};
+
+use flate2::{bufread::GzDecoder, write::GzEncoder, Compression};
+
+/// What starts a compressed trace, ahead of the length of the gzip stream
+/// that follows it.
+pub(crate) const MAGIC: &[u8] = b"DTLZ";
+
+/// The gzip header, which nothing a text trace starts with looks like.
+const GZIP_MAGIC: &[u8] = b"\x1f\x8b";
+
+/// The framed, compressed form of `text`, if it is longer than `threshold`
+/// bytes.
+pub(crate) fn frame_over(threshold: Option<usize>, text: &str) -> io::Result<Option<Vec<u8>>> {
+    match threshold {
+        Some(threshold) if text.len() > threshold => {
+            let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(text.as_bytes())?;
+            let compressed = encoder.finish()?;
+            let length = u32::try_from(compressed.len())
+                .map_err(|_| io::Error::from(io::ErrorKind::InvalidInput))?;
+
+            let mut frame = Vec::with_capacity(MAGIC.len() + 4 + compressed.len());
+            frame.extend_from_slice(MAGIC);
+            frame.extend_from_slice(&length.to_le_bytes());
+            frame.extend_from_slice(&compressed);
+            Ok(Some(frame))
+        }
+        _ => Ok(None),
+    }
+}
+
+/// If `bytes` starts with a compressed trace, decompress it onto `text` and
+/// return what comes after it.
+pub(crate) fn unframe<'a>(bytes: &'a [u8], text: &mut String) -> Option<io::Result<&'a [u8]>> {
+    let rest = bytes.strip_prefix(MAGIC)?;
+    let (length, rest) = rest.split_first_chunk::<4>()?;
+    let length = u32::from_le_bytes(*length) as usize;
+    if !rest.starts_with(GZIP_MAGIC) {
+        return None;
+    }
+    let Some(compressed) = rest.get(..length) else {
+        return Some(Err(io::ErrorKind::UnexpectedEof.into()));
+    };
+    Some(
+        GzDecoder::new(compressed)
+            .read_to_string(text)
+            .map(|_| &rest[length..]),
+    )
+}
@@ -0,0 +1,209 @@
+//! Render in-flight and recently finished traces for a debug endpoint.
+//!
+//! The helpers here return plain strings, so they can back a route like
+//! `/debug/detail` in any web framework: list the registered detailers that
+//! are still running, and the last few traces a [`FlightRecorder`] kept.
+//!
+//! ```rust
+//! use std::sync::Arc;
+//!
+//! use detailer::{debug::{self, FlightRecorder}, detail, new_detailer};
+//!
+//! let recorder = Arc::new(FlightRecorder::new(100));
+//!
+//! let mut finished = new_detailer!(Info, WithoutTiming);
+//! finished.sink(recorder.clone());
+//! detail!(finished, "request complete");
+//! drop(finished);
+//!
+//! let mut running = new_detailer!(Info, WithoutTiming);
+//! running.register("nightly compaction");
+//! detail!(running, "compacting shard 1");
+//!
+//! let page = debug::render(Some(&recorder));
+//! assert!(page.contains("nightly compaction"));
+//! assert!(page.contains("request complete"));
+//!
+//! let json = debug::render_json(Some(&recorder));
+//! assert!(json.contains(r#""name":"nightly compaction""#));
+//! ```
+
+use std::{
+    collections::VecDeque,
+    fmt::Write,
+    sync::{Arc, Mutex, PoisonError},
+    time::Duration,
+};
+
+use crate::{json::push_string, registry, FlushError, FlushedTrace, LogSink, Sink};
+
+/// A sink that remembers the most recent traces, for looking at after the
+/// fact.
+///
+/// Each trace is also passed on to another sink, [`LogSink`] unless it is
+/// changed with [`forward()`](Self::forward), so recording them doesn't take
+/// them out of the logs.
+pub struct FlightRecorder {
+    capacity: usize,
+    traces: Mutex<VecDeque<RecentTrace>>,
+    forward: Option<Arc<dyn Sink>>,
+}
+
+/// A trace a [`FlightRecorder`] kept.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct RecentTrace {
+    /// The level the trace was flushed at
+    pub level: log::Level,
+    /// The rendered trace
+    pub text: String,
+    /// The detailer's trace ID, if it had one
+    pub trace_id: Option<String>,
+    /// The detailer's context, as keys and values
+    pub context: Vec<(&'static str, String)>,
+    /// How long the trace ran for, if it was recorded with timing
+    pub elapsed: Option<Duration>,
+    /// How many lines the trace recorded
+    pub lines: usize,
+}
+
+impl FlightRecorder {
+    /// Remember the last `capacity` traces.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            traces: Mutex::new(VecDeque::with_capacity(capacity.min(1024))),
+            forward: Some(Arc::new(LogSink)),
+        }
+    }
+
+    /// Pass each trace on to `sink` instead of logging it, or to nothing.
+    pub fn forward(mut self, sink: Option<Arc<dyn Sink>>) -> Self {
+        self.forward = sink;
+        self
+    }
+
+    /// The remembered traces, oldest first
+    pub fn traces(&self) -> Vec<RecentTrace> {
+        self.traces
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .iter()
+            .cloned()
+            .collect()
+    }
+}
+
+impl Sink for FlightRecorder {
+    fn flush(&self, trace: &FlushedTrace) {
+        if let Err(e) = self.try_flush(trace) {
+            log::warn!("could not forward trace: {e}");
+        }
+    }
+
+    /// The trace is always recorded; this fails when forwarding it fails.
+    fn try_flush(&self, trace: &FlushedTrace) -> Result<(), FlushError> {
+        if 0 < self.capacity {
+            let mut traces = self.traces.lock().unwrap_or_else(PoisonError::into_inner);
+            if self.capacity <= traces.len() {
+                traces.pop_front();
+            }
+            traces.push_back(RecentTrace {
+                level: trace.level,
+                text: trace.text.to_string(),
+                trace_id: trace.trace_id.map(str::to_string),
+                context: trace.context.to_vec(),
+                elapsed: trace.elapsed,
+                lines: trace.lines,
+            });
+        }
+        match &self.forward {
+            Some(forward) => forward.try_flush(trace),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Render the registered detailers that are still running and, if there is
+/// one, the flight recorder's traces, as plain text.
+pub fn render(recorder: Option<&FlightRecorder>) -> String {
+    let mut page = String::new();
+    let live = registry::live();
+    let _ = writeln!(page, "in flight: {}", live.len());
+    for detailer in &live {
+        let _ = write!(page, "  {}", detailer.name());
+        if let Some(elapsed) = detailer.elapsed() {
+            let _ = write!(page, "  {}µs", elapsed.as_micros());
+        }
+        let _ = writeln!(page, "  {} lines", detailer.lines());
+    }
+    if let Some(recorder) = recorder {
+        let traces = recorder.traces();
+        let _ = writeln!(page, "\nrecent: {}", traces.len());
+        for trace in traces.iter().rev() {
+            let _ = write!(page, "\n--- {}", trace.level);
+            if let Some(trace_id) = &trace.trace_id {
+                let _ = write!(page, "  trace_id={trace_id}");
+            }
+            if let Some(elapsed) = trace.elapsed {
+                let _ = write!(page, "  {}µs", elapsed.as_micros());
+            }
+            let _ = writeln!(page, "  {} lines", trace.lines);
+            let _ = writeln!(page, "{}", trace.text.trim_end());
+        }
+    }
+    page
+}
+
+/// Render the same as [`render()`], as a JSON object with `in_flight` and
+/// `recent` arrays. Recent traces are newest first.
+pub fn render_json(recorder: Option<&FlightRecorder>) -> String {
+    let mut json = String::from(r#"{"in_flight":["#);
+    for (index, detailer) in registry::live().iter().enumerate() {
+        if 0 < index {
+            json.push(',');
+        }
+        json.push_str(r#"{"name":"#);
+        push_string(&mut json, detailer.name());
+        json.push_str(r#","elapsed_us":"#);
+        push_json_micros(&mut json, detailer.elapsed());
+        let _ = write!(json, r#","lines":{}}}"#, detailer.lines());
+    }
+    json.push_str(r#"],"recent":["#);
+    let traces = recorder.map(FlightRecorder::traces).unwrap_or_default();
+    for (index, trace) in traces.iter().rev().enumerate() {
+        if 0 < index {
+            json.push(',');
+        }
+        let _ = write!(json, r#"{{"level":"{}","trace_id":"#, trace.level);
+        match &trace.trace_id {
+            Some(trace_id) => push_string(&mut json, trace_id),
+            None => json.push_str("null"),
+        }
+        json.push_str(r#","context":{"#);
+        for (index, (key, value)) in trace.context.iter().enumerate() {
+            if 0 < index {
+                json.push(',');
+            }
+            push_string(&mut json, key);
+            json.push(':');
+            push_string(&mut json, value);
+        }
+        json.push_str(r#"},"elapsed_us":"#);
+        push_json_micros(&mut json, trace.elapsed);
+        let _ = write!(json, r#","lines":{},"text":"#, trace.lines);
+        push_string(&mut json, &trace.text);
+        json.push('}');
+    }
+    json.push_str("]}");
+    json
+}
+
+fn push_json_micros(json: &mut String, duration: Option<Duration>) {
+    match duration {
+        Some(duration) => {
+            let _ = write!(json, "{}", duration.as_micros());
+        }
+        None => json.push_str("null"),
+    }
+}
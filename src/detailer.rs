@@ -1,7 +1,33 @@
-use std::{
-    fmt::{Arguments, Write},
-    sync::{atomic::AtomicUsize, Arc},
-    time::Instant,
+use crate::{
+    clock,
+    event::{EventFields, EventText},
+    format::PrefixWith,
+    parse,
+    sink::split,
+    values::AttributeValues,
+    Clock, DetailEvent, FlushError, FlushErrorPolicy, FlushedTrace, LineFormatter, LineStart,
+    LogSink, RecordLimit, RecordedLine, Sink, SmallString, Snapshot,
+};
+#[cfg(feature = "std")]
+use crate::{
+    registry::Registration,
+    subscribe::{self, Publisher},
+    Subscription,
+};
+use alloc::{
+    boxed::Box,
+    collections::{BTreeMap, BTreeSet},
+    format,
+    string::{String, ToString},
+    sync::Arc,
+    vec::Vec,
+};
+use core::{
+    fmt::{Arguments, Display, Write},
+    ops::Range,
+    panic::Location,
+    sync::atomic::{AtomicU64, AtomicUsize},
+    time::Duration,
 };
 
 /// An event or workflow detail logger.
@@ -11,16 +37,236 @@ pub struct Detailer {
     level: log::LevelFilter,
     accumulated: String,
     current_indentation: Arc<AtomicUsize>,
-    start: Option<Instant>,
+    scope_level: Arc<AtomicUsize>,
+    clock: Option<Clock>,
+    start: u64,
     soft_limit: usize,
+    line_format: LineFormat,
+    line_formatter: Option<Arc<dyn LineFormatter>>,
+    elapsed_format: ElapsedFormat,
+    ansi: bool,
+    severity_markers: Option<SeverityMarkers>,
+    continuation_lines: ContinuationLines,
+    elapsed_width: usize,
+    deduplicate: bool,
+    flush_at_highest_level: bool,
+    #[cfg(feature = "std")]
+    start_time_header: bool,
+    #[cfg(feature = "std")]
+    wall_clock: bool,
+    #[cfg(feature = "std")]
+    thread_labels: bool,
+    #[cfg(all(feature = "cpu-time", unix))]
+    cpu_time: bool,
+    coarse_timing: Option<CoarseTiming>,
+    granularity_micros: u64,
+    records: Vec<Record>,
+    last_line: Option<RepeatedLine>,
+    throttles: Vec<Throttle>,
+    /// Call sites of `log_once()` that already recorded their line
+    logged_once: Vec<&'static Location<'static>>,
+    resumable_scopes: Vec<ResumableScope>,
+    max_message_length: Option<usize>,
+    start_line: Option<String>,
+    end_line: Option<String>,
+    drop_line: Option<String>,
+    flush_on_drop: bool,
+    shrink_after_flush: Option<usize>,
+    sink: Option<Arc<dyn Sink>>,
+    tag_filter: Option<Vec<String>>,
+    target_filter: Option<Vec<String>>,
+    lane_layout: LaneLayout,
+    /// The lanes lines were recorded in, in the order they were first used
+    lanes: Vec<&'static str>,
+    collapse_below: Option<log::Level>,
+    prune_empty_scopes: bool,
+    summary_only: bool,
+    scope_bars: Option<usize>,
+    sparkline: Option<usize>,
+    report_suppressed: bool,
+    watermark: Option<usize>,
+    watermark_crossed: bool,
+    chunk_size: Option<usize>,
+    suppressed: Suppressed,
+    on_flush_error: FlushErrorPolicy,
+    on_flush: Option<Box<FlushCallback>>,
+    on_line: Option<Box<LineCallback>>,
+    trace_id: Option<String>,
+    context: Vec<(&'static str, String)>,
+    pushed_context: Vec<(&'static str, String)>,
+    pushed_depth: Arc<AtomicUsize>,
+    attributes: AttributeValues,
+    scratch: String,
+    #[cfg(feature = "std")]
+    registration: Option<Arc<Registration>>,
+    #[cfg(feature = "std")]
+    subscribers: Vec<Publisher>,
+}
+
+/// Called with each trace as it is flushed.
+type FlushCallback = dyn FnMut(&FlushedTrace) + Send + Sync;
+
+/// Called with each line as it is recorded.
+type LineCallback = dyn FnMut(&RecordedLine) + Send + Sync;
+
+/// Where a recorded line is in the accumulated text.
+struct Record {
+    start: usize,
+    message_start: usize,
+    level: log::Level,
+    labels: Labels,
+    attributes: Range<usize>,
+    scope: bool,
+    /// The id of the scope this line begins, if it was begun with
+    /// [`Detailer::enter_scope()`]
+    opens: Option<u64>,
+    indentation: usize,
+    elapsed: Option<u64>,
+    since_epoch: Option<Duration>,
+    /// The recording thread's CPU time, in microseconds, if it is recorded
+    cpu: Option<u64>,
+}
+
+/// What a line was logged with besides its message.
+#[derive(Clone, Copy, Default, PartialEq)]
+struct Labels {
+    tags: &'static [&'static str],
+    target: Option<&'static str>,
+    lane: Option<&'static str>,
+    /// The id of the scope the line was recorded into with
+    /// [`Detailer::log_in()`], if it was
+    scope: Option<u64>,
+}
+
+/// The id of the next scope begun with [`Detailer::enter_scope()`], unique
+/// across detailers so that appended lines keep their scopes.
+static NEXT_SCOPE_ID: AtomicU64 = AtomicU64::new(0);
+
+/// The most recent line, tracked while deduplicating.
+struct RepeatedLine {
+    message: Range<usize>,
+    level: log::Level,
+    labels: Labels,
+    indentation: usize,
+    count: usize,
+    first: Option<u64>,
+    last: Option<u64>,
+}
+
+/// Rate limiting state for a throttled line of code.
+struct Throttle {
+    site: &'static Location<'static>,
+    label: String,
+    skip: u64,
+    suppressed: u64,
+    total: u64,
+}
+
+/// A named scope's totals across every time it was entered.
+struct ResumableScope {
+    name: String,
+    entries: usize,
+    micros: Arc<AtomicU64>,
+}
+
+/// How many lines were not recorded since the last flush, by why.
+#[derive(Default)]
+struct Suppressed {
+    below_level: usize,
+    over_limit: usize,
+}
+
+/// Reads the clock for only some lines, while coarse timing is on.
+struct CoarseTiming {
+    every: usize,
+    lines: usize,
+    elapsed: Option<u64>,
 }
 
 /// Configure the time logging prefix of detail lines
 pub enum TimingSetting {
-    /// Include timing info in line prefixes
+    /// Include timing info in line prefixes, from the platform's clock.
+    ///
+    /// Without the `std` feature, or on `wasm32-unknown-unknown`, there is no
+    /// platform clock, so this records no timing. Use `WithClock` to provide
+    /// one.
     WithTiming,
     /// Do not include timing info in line prefixes
     WithoutTiming,
+    /// Include timing info in line prefixes, from the given clock
+    WithClock(Clock),
+}
+
+/// How each line of a trace starts
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum LineFormat {
+    /// The microseconds since the trace started, when it records timing.
+    /// This is the default, and the format [`parse`](crate::parse) reads.
+    Elapsed,
+    /// The wall clock time, level and `target`, the way env_logger lays out
+    /// its lines, like `[2024-04-24T20:31:28.767641Z INFO  checkout] `
+    #[cfg(feature = "std")]
+    EnvLogger {
+        /// The target to show on every line
+        target: &'static str,
+    },
+}
+
+/// How the elapsed time at the start of each [`LineFormat::Elapsed`] line is
+/// written. Whichever it is, it is padded to the
+/// [elapsed width](Detailer::elapsed_width).
+#[derive(Clone, Copy, Debug)]
+#[non_exhaustive]
+pub enum ElapsedFormat {
+    /// Whole microseconds, like `1250`. This is the default, and the format
+    /// [`parse`](crate::parse) reads.
+    Micros,
+    /// Milliseconds with three decimals, like `1.250`
+    Millis,
+    /// In the unit that suits its magnitude, with at most three digits, like
+    /// `842µs`, `1.3ms`, `27ms` or `2.4s`, so long traces stay readable and
+    /// the column keeps its width until traces run past 1000 seconds.
+    Scaled,
+    /// Written by a function of your own
+    Custom(fn(Duration, &mut core::fmt::Formatter<'_>) -> core::fmt::Result),
+}
+
+/// How the second and later lines of a multi-line message are laid out
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ContinuationLines {
+    /// Only indented for their scope, starting where the elapsed column
+    /// would. This is the default.
+    Unaligned,
+    /// Padded to line up under the first line's message
+    Aligned,
+    /// Started with the same prefix as the first line, like its elapsed
+    /// time, and a `… ` marker. Every line stays associated with its record
+    /// in log pipelines that split records by line, and
+    /// [`parse`](crate::parse) still joins them back up.
+    Prefixed,
+}
+
+/// The markers that start warning and error lines, when a detailer marks
+/// them with [`Detailer::severity_markers()`]. Other lines are padded to
+/// keep them aligned.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SeverityMarkers {
+    /// The marker for warning lines
+    pub warn: &'static str,
+    /// The marker for error lines
+    pub error: &'static str,
+}
+
+/// `!` for warnings and `✗` for errors
+impl Default for SeverityMarkers {
+    fn default() -> Self {
+        Self {
+            warn: "!",
+            error: "✗",
+        }
+    }
 }
 
 /// Create a new root detailer. It will log as 1 expression upon
@@ -97,6 +343,293 @@ macro_rules! detail_at {
     };
 }
 
+/// Add a constant detail line, at info or a specified level
+///
+/// This is cheaper than `detail!()` because there is nothing to format.
+/// ```rust
+/// use detailer::{Detailer, detail_static, new_detailer};
+/// let mut detailer = new_detailer!(Info, WithoutTiming);
+///
+/// detail_static!(detailer, "authenticating");
+/// detail_static!(detailer, Error, "authentication failed");
+/// assert_eq!("authenticating\nauthentication failed\n", detailer.peek());
+/// ```
+#[macro_export(local_inner_macros)]
+macro_rules! detail_static {
+    // detail_static!(detailer, "authenticating")
+    ($detail_tracker:expr, $message:literal) => {
+        ($detail_tracker.log_static(log::Level::Info, $message));
+    };
+    // detail_static!(detailer, Debug, "authenticating")
+    ($detail_tracker:expr, $log_level:tt, $message:literal) => {
+        ($detail_tracker.log_static(log::Level::$log_level, $message));
+    };
+}
+
+/// Add a detail line that is recorded only once every `every` times this line
+/// of code runs. The lines in between are suppressed, and the next recorded
+/// line notes how many were skipped.
+///
+/// Use this to instrument tight loops without flooding the trace.
+/// ```rust
+/// use detailer::{Detailer, detail_throttled, new_detailer};
+/// let mut detailer = new_detailer!(Info, WithoutTiming);
+///
+/// for n in 0..5 {
+///     detail_throttled!(detailer, every = 2, "poll tick {n}");
+/// }
+/// for n in 0..3 {
+///     detail_throttled!(detailer, Debug, every = 2, "not logged at Info {n}");
+/// }
+/// assert_eq!(
+///     "poll tick 0\npoll tick 2 (suppressed 1 similar line)\npoll tick 4 (suppressed 1 similar line)\n",
+///     detailer.peek(),
+/// );
+/// ```
+#[macro_export(local_inner_macros)]
+macro_rules! detail_throttled {
+    // detail_throttled!(detailer, every = 100, "poll tick {}", n)
+    ($detail_tracker:expr, every = $every:expr, $($arg:tt)+) => {
+        ($detail_tracker.log_throttled(
+            log::Level::Info,
+            $every,
+            core::format_args!($($arg)+))
+        );
+    };
+    // detail_throttled!(detailer, Debug, every = 100, "poll tick {}", n)
+    ($detail_tracker:expr, $log_level:tt, every = $every:expr, $($arg:tt)+) => {
+        ($detail_tracker.log_throttled(
+            log::Level::$log_level,
+            $every,
+            core::format_args!($($arg)+))
+        );
+    };
+}
+
+/// Add a detail line the first time this line of code runs, at info or a
+/// specified level. Later runs record nothing for as long as the detailer
+/// lives.
+///
+/// Use this for conditions inside loops where one occurrence is all the
+/// signal needed.
+/// ```rust
+/// use detailer::{Detailer, detail_once, new_detailer};
+/// let mut detailer = new_detailer!(Info, WithoutTiming);
+///
+/// for attempt in 0..3 {
+///     detail_once!(detailer, "fallback path taken on attempt {attempt}");
+///     detail_once!(detailer, Warn, "cache unavailable");
+/// }
+/// assert_eq!(
+///     "fallback path taken on attempt 0\ncache unavailable\n",
+///     detailer.peek(),
+/// );
+/// ```
+#[macro_export(local_inner_macros)]
+macro_rules! detail_once {
+    // detail_once!(detailer, Warn, "fallback path taken")
+    ($detail_tracker:expr, $log_level:ident, $($arg:tt)+) => {
+        ($detail_tracker.log_once(
+            log::Level::$log_level,
+            core::format_args!($($arg)+))
+        );
+    };
+    // detail_once!(detailer, "fallback path taken")
+    ($detail_tracker:expr, $($arg:tt)+) => {
+        ($detail_tracker.log_once(
+            log::Level::Info,
+            core::format_args!($($arg)+))
+        );
+    };
+}
+
+/// End a scope with a closing line about how it went
+///
+/// ```rust
+/// use detailer::{close_scope, detail, new_detailer, scope};
+/// let mut detailer = new_detailer!(Info, WithoutTiming);
+///
+/// let loading = scope!(detailer, "loading");
+/// let rows = 12;
+/// close_scope!(detailer, loading, "loaded {} rows", rows);
+/// detail!(detailer, "done");
+/// assert_eq!("loading\n  loaded 12 rows\ndone\n", detailer.peek());
+/// ```
+#[macro_export(local_inner_macros)]
+macro_rules! close_scope {
+    // close_scope!(detailer, guard, "loaded {} rows", n)
+    ($detail_tracker:expr, $scope_guard:expr, $($arg:tt)+) => {
+        ($scope_guard.close_with(
+            &mut $detail_tracker,
+            core::format_args!($($arg)+))
+        );
+    };
+}
+
+/// Add a detail line under a scope from `enter_scope()`, at info or a
+/// specified level
+/// ```rust
+/// use detailer::{Detailer, detail_in, new_detailer};
+/// let mut detailer = new_detailer!(Info, WithoutTiming);
+///
+/// let request = detailer.enter_scope(format_args!("request"));
+/// detail_in!(detailer, request, "authenticated");
+/// detail_in!(detailer, request, Warn, "slow backend");
+/// assert_eq!("request\n  authenticated\n  slow backend\n", detailer.peek());
+/// ```
+#[macro_export(local_inner_macros)]
+macro_rules! detail_in {
+    // detail_in!(detailer, scope, Warn, "a {} event", "log")
+    ($detail_tracker:expr, $scope:expr, $log_level:ident, $($arg:tt)+) => {
+        ($detail_tracker.log_in(
+            $scope,
+            log::Level::$log_level,
+            core::format_args!($($arg)+))
+        );
+    };
+    // detail_in!(detailer, scope, "a {} event", "log")
+    ($detail_tracker:expr, $scope:expr, $($arg:tt)+) => {
+        ($detail_tracker.log_in(
+            $scope,
+            log::Level::Info,
+            core::format_args!($($arg)+))
+        );
+    };
+}
+
+/// Add a detail line with tags, at info or a specified level
+/// ```rust
+/// use detailer::{Detailer, detail_tagged, new_detailer};
+/// let mut detailer = new_detailer!(Info, WithoutTiming);
+///
+/// detail_tagged!(detailer, ["db", "retry"], "retrying after {}ms", 20);
+/// detail_tagged!(detailer, Warn, ["db"], "pool exhausted");
+/// assert_eq!("retrying after 20ms\npool exhausted\n", detailer.peek());
+/// ```
+#[macro_export(local_inner_macros)]
+macro_rules! detail_tagged {
+    // detail_tagged!(detailer, ["db", "retry"], "a {} event", "log")
+    ($detail_tracker:expr, [$($tag:expr),* $(,)?], $($arg:tt)+) => {
+        ($detail_tracker.log_tagged(
+            log::Level::Info,
+            &[$($tag),*],
+            core::format_args!($($arg)+))
+        );
+    };
+    // detail_tagged!(detailer, Warn, ["db"], "a {} event", "log")
+    ($detail_tracker:expr, $log_level:ident, [$($tag:expr),* $(,)?], $($arg:tt)+) => {
+        ($detail_tracker.log_tagged(
+            log::Level::$log_level,
+            &[$($tag),*],
+            core::format_args!($($arg)+))
+        );
+    };
+}
+
+/// Add a detail line for its own target, at info or a specified level
+/// ```rust
+/// use detailer::{Detailer, detail_target, new_detailer};
+/// let mut detailer = new_detailer!(Info, WithoutTiming);
+///
+/// detail_target!(detailer, "my_app::db", "connected to {}", "primary");
+/// detail_target!(detailer, "my_app::db", Warn, "pool exhausted");
+/// assert_eq!("connected to primary\npool exhausted\n", detailer.peek());
+/// ```
+#[macro_export(local_inner_macros)]
+macro_rules! detail_target {
+    // detail_target!(detailer, "my_app::db", Debug, "a {} event", "log")
+    ($detail_tracker:expr, $target:expr, $log_level:ident, $($arg:tt)+) => {
+        ($detail_tracker.log_target(
+            log::Level::$log_level,
+            $target,
+            core::format_args!($($arg)+))
+        );
+    };
+    // detail_target!(detailer, "my_app::db", "a {} event", "log")
+    ($detail_tracker:expr, $target:expr, $($arg:tt)+) => {
+        ($detail_tracker.log_target(
+            log::Level::Info,
+            $target,
+            core::format_args!($($arg)+))
+        );
+    };
+}
+
+/// Add a detail line only when `condition` is true
+///
+/// The condition is evaluated first, and nothing is formatted when it is
+/// false.
+/// ```rust
+/// use detailer::{Detailer, detail_if, new_detailer};
+/// let mut detailer = new_detailer!(Info, WithoutTiming);
+///
+/// for rows in [12, 0, 7] {
+///     detail_if!(detailer, rows == 0, "empty batch");
+/// }
+/// assert_eq!("empty batch\n", detailer.peek());
+/// ```
+#[macro_export(local_inner_macros)]
+macro_rules! detail_if {
+    // detail_if!(detailer, retries > 0, "retried {} times", retries)
+    ($detail_tracker:expr, $condition:expr, $($arg:tt)+) => {
+        if $condition {
+            $detail_tracker.log(
+                log::Level::Info,
+                core::format_args!($($arg)+),
+            );
+        }
+    };
+}
+
+/// Add a detail line with the value of an expression and where it was
+/// looked at, and evaluate to the value, the way `std::dbg!()` does but into
+/// the trace instead of stderr.
+///
+/// Lines are at debug unless a level is given, so they cost nothing in
+/// detailers that don't record debug lines, and the value is only formatted
+/// when the line is recorded.
+/// ```rust
+/// use detailer::{detail_dbg, new_detailer};
+/// let mut detailer = new_detailer!(Debug, WithoutTiming);
+///
+/// let retries = 2;
+/// let backoff = detail_dbg!(detailer, retries * 50);
+/// let _ = detail_dbg!(detailer, Warn, Some(backoff));
+/// assert_eq!(100, backoff);
+///
+/// let trace = detailer.peek();
+/// let lines: Vec<&str> = trace.lines().collect();
+/// assert!(lines[0].starts_with("["));
+/// assert!(lines[0].ends_with("] retries * 50 = 100"));
+/// assert!(lines[1].ends_with("] Some(backoff) = Some(100)"));
+/// ```
+#[macro_export(local_inner_macros)]
+macro_rules! detail_dbg {
+    // detail_dbg!(detailer, Warn, response.status())
+    ($detail_tracker:expr, $log_level:ident, $value:expr $(,)?) => {
+        match $value {
+            value => {
+                $detail_tracker.log(
+                    log::Level::$log_level,
+                    core::format_args!(
+                        "[{}:{}:{}] {} = {:?}",
+                        core::file!(),
+                        core::line!(),
+                        core::column!(),
+                        core::stringify!($value),
+                        &value,
+                    ),
+                );
+                value
+            }
+        }
+    };
+    // detail_dbg!(detailer, response.status())
+    ($detail_tracker:expr, $value:expr $(,)?) => {
+        detail_dbg!($detail_tracker, Debug, $value)
+    };
+}
+
 /// Add a lexical scope indentation to the detail
 ///
 /// You can go in multiple levels, but be aware that scopes
@@ -115,8 +648,48 @@ macro_rules! detail_at {
 /// }
 /// detail!(detailer, "not indented");
 /// ```
+///
+/// A scope can record its contents at a different level than the rest of the
+/// detailer, to silence or amplify a verbose section:
+/// ```rust
+/// use detailer::{detail_at, new_detailer, scope};
+/// let mut detailer = new_detailer!(Info, WithoutTiming);
+///
+/// {
+///     let _scope = scope!(detailer, min_level = Debug, "retrying");
+///     detail_at!(detailer, Debug, "backing off");
+/// }
+/// detail_at!(detailer, Debug, "not logged");
+/// assert_eq!("retrying\n  backing off\n", detailer.peek());
+/// ```
+///
+/// Attributes follow the name after a `;`, since after a `,` they would be
+/// named format arguments. `%` formats a value with `Display`, as does no
+/// sigil, and `?` with `Debug`:
+/// ```rust
+/// use detailer::{new_detailer, scope};
+/// let mut detailer = new_detailer!(Info, WithoutTiming);
+///
+/// let name = "users";
+/// let _scope = scope!(detailer, "query"; table = %name, rows_hint = 10);
+/// assert_eq!("query table=users rows_hint=10\n", detailer.peek());
+/// ```
 #[macro_export(local_inner_macros)]
 macro_rules! scope {
+    // scope!(detailer, "scope {}", "log"; table = %name, rows = n, plan = ?plan)
+    ($detail_tracker:expr, $format:literal $(, $format_arg:expr)* ; $($attributes:tt)+) => {
+        ($detail_tracker.scope_with_attributes(
+            core::format_args!($format $(, $format_arg)*),
+            &__scope_attributes!([] $($attributes)+))
+        )
+    };
+    // scope!(detailer, min_level = Debug, "scope {}", "log")
+    ($detail_tracker:expr, min_level = $log_level:tt, $($arg:tt)+) => {
+        ($detail_tracker.scope_at_level(
+            log::LevelFilter::$log_level,
+            core::format_args!($($arg)+))
+        )
+    };
     // scope!(detailer, "scope {}", "log")
     ($detail_tracker:expr, $($arg:tt)+) => {
         ($detail_tracker.scope(
@@ -125,230 +698,4138 @@ macro_rules! scope {
     };
 }
 
+/// Collects `scope!()` attributes into an array of keys and formatted values.
+#[doc(hidden)]
+#[macro_export(local_inner_macros)]
+macro_rules! __scope_attributes {
+    ([$($done:tt)*]) => { [$($done)*] };
+    ([$($done:tt)*] $key:ident = % $value:expr $(, $($rest:tt)*)?) => {
+        __scope_attributes!(
+            [$($done)* (core::stringify!($key), core::format_args!("{}", $value)),]
+            $($($rest)*)?
+        )
+    };
+    ([$($done:tt)*] $key:ident = ? $value:expr $(, $($rest:tt)*)?) => {
+        __scope_attributes!(
+            [$($done)* (core::stringify!($key), core::format_args!("{:?}", $value)),]
+            $($($rest)*)?
+        )
+    };
+    ([$($done:tt)*] $key:ident = $value:expr $(, $($rest:tt)*)?) => {
+        __scope_attributes!(
+            [$($done)* (core::stringify!($key), core::format_args!("{}", $value)),]
+            $($($rest)*)?
+        )
+    };
+}
+
 impl Detailer {
     /// Create a new event Detailer logger.
     ///
     /// When dropped or flush()ed it will output its accumulated input.
     pub fn new(level: log::LevelFilter, timing_setting: TimingSetting, limit: usize) -> Detailer {
+        Self::with_buffer(level, timing_setting, limit, String::new())
+    }
+
+    /// Create a new event Detailer logger that accumulates into `buffer`.
+    ///
+    /// Hand in a buffer with capacity for the soft limit, and the detailer
+    /// does not need to allocate for its text. The buffer is cleared first.
+    ///
+    /// ```
+    /// use detailer::{Detailer, TimingSetting};
+    ///
+    /// let detailer = Detailer::with_buffer(
+    ///     log::LevelFilter::Info,
+    ///     TimingSetting::WithoutTiming,
+    ///     1024,
+    ///     String::with_capacity(1024),
+    /// );
+    /// ```
+    pub fn with_buffer(
+        level: log::LevelFilter,
+        timing_setting: TimingSetting,
+        limit: usize,
+        mut buffer: String,
+    ) -> Detailer {
+        buffer.clear();
+        let clock = match timing_setting {
+            TimingSetting::WithTiming => clock::platform(),
+            TimingSetting::WithoutTiming => None,
+            TimingSetting::WithClock(clock) => Some(clock),
+        };
         Detailer {
             level,
-            accumulated: Default::default(),
+            accumulated: buffer,
             current_indentation: Default::default(),
-            start: match timing_setting {
-                TimingSetting::WithTiming => Some(Instant::now()),
-                TimingSetting::WithoutTiming => None,
-            },
+            scope_level: Arc::new(AtomicUsize::new(NO_SCOPE_LEVEL)),
+            clock,
+            start: clock.map(|clock| clock()).unwrap_or_default(),
             soft_limit: limit,
+            line_format: LineFormat::Elapsed,
+            line_formatter: None,
+            elapsed_format: ElapsedFormat::Micros,
+            ansi: false,
+            severity_markers: None,
+            continuation_lines: ContinuationLines::Unaligned,
+            elapsed_width: parse::ELAPSED_WIDTH,
+            deduplicate: false,
+            flush_at_highest_level: false,
+            #[cfg(feature = "std")]
+            start_time_header: false,
+            #[cfg(feature = "std")]
+            wall_clock: false,
+            #[cfg(feature = "std")]
+            thread_labels: false,
+            #[cfg(all(feature = "cpu-time", unix))]
+            cpu_time: false,
+            coarse_timing: None,
+            granularity_micros: 1,
+            records: Vec::new(),
+            last_line: None,
+            throttles: Vec::new(),
+            logged_once: Vec::new(),
+            resumable_scopes: Vec::new(),
+            max_message_length: None,
+            start_line: None,
+            end_line: None,
+            drop_line: Some("dropped".into()),
+            flush_on_drop: true,
+            shrink_after_flush: None,
+            sink: None,
+            tag_filter: None,
+            target_filter: None,
+            lane_layout: LaneLayout::default(),
+            lanes: Vec::new(),
+            collapse_below: None,
+            prune_empty_scopes: false,
+            summary_only: false,
+            scope_bars: None,
+            sparkline: None,
+            report_suppressed: false,
+            watermark: None,
+            watermark_crossed: false,
+            chunk_size: None,
+            suppressed: Suppressed::default(),
+            on_flush_error: FlushErrorPolicy::default(),
+            on_flush: None,
+            on_line: None,
+            trace_id: None,
+            context: Vec::new(),
+            pushed_context: Vec::new(),
+            pushed_depth: Arc::new(AtomicUsize::new(0)),
+            attributes: AttributeValues::new(),
+            scratch: String::new(),
+            #[cfg(feature = "std")]
+            registration: None,
+            #[cfg(feature = "std")]
+            subscribers: Vec::new(),
         }
     }
 
-    /// See what's currently accumulated
-    pub fn peek(&self) -> &str {
-        &self.accumulated
+    /// A new, empty detailer for work split off from this one, which
+    /// records and flushes the same way. Callbacks, subscribers and the
+    /// registry entry stay with this detailer, and so do the start and end
+    /// lines, since most children are merged back into this trace.
+    pub(crate) fn child(&self) -> Detailer {
+        let mut child = Detailer::new(self.level, TimingSetting::WithoutTiming, self.soft_limit);
+        child.clock = self.clock;
+        child.start = self.clock.map(|clock| clock()).unwrap_or_default();
+        child.line_format = self.line_format;
+        child.line_formatter = self.line_formatter.clone();
+        child.elapsed_format = self.elapsed_format;
+        child.ansi = self.ansi;
+        child.severity_markers = self.severity_markers;
+        child.continuation_lines = self.continuation_lines;
+        child.elapsed_width = self.elapsed_width;
+        child.deduplicate = self.deduplicate;
+        child.flush_at_highest_level = self.flush_at_highest_level;
+        #[cfg(feature = "std")]
+        {
+            child.start_time_header = self.start_time_header;
+            child.wall_clock = self.wall_clock;
+            child.thread_labels = self.thread_labels;
+        }
+        #[cfg(all(feature = "cpu-time", unix))]
+        {
+            child.cpu_time = self.cpu_time;
+        }
+        child.coarse_timing = self.coarse_timing.as_ref().map(|coarse| CoarseTiming {
+            every: coarse.every,
+            lines: 0,
+            elapsed: None,
+        });
+        child.granularity_micros = self.granularity_micros;
+        child.max_message_length = self.max_message_length;
+        child.drop_line = self.drop_line.clone();
+        child.flush_on_drop = self.flush_on_drop;
+        child.shrink_after_flush = self.shrink_after_flush;
+        child.sink = self.sink.clone();
+        child.tag_filter = self.tag_filter.clone();
+        child.target_filter = self.target_filter.clone();
+        child.lane_layout = self.lane_layout;
+        child.collapse_below = self.collapse_below;
+        child.prune_empty_scopes = self.prune_empty_scopes;
+        child.summary_only = self.summary_only;
+        child.scope_bars = self.scope_bars;
+        child.sparkline = self.sparkline;
+        child.report_suppressed = self.report_suppressed;
+        child.watermark = self.watermark;
+        child.chunk_size = self.chunk_size;
+        child.on_flush_error = self.on_flush_error;
+        child.trace_id = self.trace_id.clone();
+        child.context = self.context.clone();
+        child
     }
 
-    /// Remove the contents and reset the timer (if enabled)
-    pub fn reset(&mut self) {
-        self.accumulated.clear();
-        if self.start.is_some() {
-            self.start = Some(Instant::now());
+    /// Start a detailer of its own for fire-and-forget work.
+    ///
+    /// The background work owns the returned detailer, so it doesn't hold up
+    /// this one, and it flushes its own trace when the work finishes and
+    /// drops it. It records and flushes the same way as this detailer: its
+    /// settings, like the line format, filters and start and end lines, are
+    /// copied, and so are the sink, context and trace ID, so the two traces
+    /// can be found together. Callbacks like [`on_flush()`](Self::on_flush)
+    /// and subscribers stay with this detailer. This detailer notes that the
+    /// work was detached.
+    ///
+    /// ```
+    /// use detailer::{detail, new_detailer};
+    ///
+    /// let mut detailer = new_detailer!(Info, WithoutTiming);
+    /// detailer.trace_id("4bf92f35");
+    /// detailer.prefix_with(|_, level| format!("{level} "));
+    /// let mut background = detailer.detach("async flush");
+    /// assert_eq!("INFO async flush\n", background.peek());
+    /// std::thread::spawn(move || {
+    ///     detail!(background, "flushed 12 pages");
+    /// });
+    ///
+    /// assert_eq!("INFO detached async flush\n", detailer.peek());
+    /// ```
+    pub fn detach(&mut self, name: &str) -> Detailer {
+        if let Some(level) = self.effective_level().to_level() {
+            self.log(level, format_args!("detached {name}"));
+        }
+        let mut detached = self.child();
+        detached.start_line = self.start_line.clone();
+        detached.end_line = self.end_line.clone();
+        if let Some(level) = detached.level.to_level() {
+            detached.log(level, format_args!("{name}"));
         }
+        detached
     }
 
-    /// Output and clear the contents
-    pub fn flush(&mut self) {
-        let to_flush = self.accumulated.trim_end();
-        if !to_flush.is_empty() {
-            log::log!(
-                self.level.to_level().unwrap_or(log::Level::Info),
-                "{}",
-                to_flush
-            );
-        }
-        self.reset();
+    /// See what's currently accumulated
+    pub fn peek(&self) -> &str {
+        &self.accumulated
     }
 
-    /// Indent output one more level as long as the scope guard exists
-    pub fn scope(&mut self, scope_name: Arguments) -> DetailScopeGuard {
-        if let Some(level) = self.level.to_level() {
-            self.log(level, scope_name);
-        }
-        DetailScopeGuard::new(self.current_indentation.clone())
+    /// Copy what's currently accumulated, without clearing it
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot::new(
+            &self.accumulated,
+            self.elapsed_micros().map(Duration::from_micros),
+        )
     }
 
-    /// log a line, if the level is enabled.
+    /// How many bytes of heap this detailer is holding on to, including
+    /// buffer capacity that is not currently in use.
     ///
     /// ```
-    /// use detailer::{Detailer, new_detailer};
+    /// use detailer::{detail, new_detailer};
     ///
     /// let mut detailer = new_detailer!();
+    /// detail!(detailer, "some detail");
     ///
-    /// detailer.log(log::Level::Warn, format_args!("yikes {}", 42));
+    /// assert!(detailer.peek().len() <= detailer.memory_used());
+    ///
+    /// let before = detailer.memory_used();
+    /// detailer.set_context("tenant", "acme-corporation");
+    /// assert!(before < detailer.memory_used());
     /// ```
-    pub fn log(&mut self, level: log::Level, message: Arguments) {
-        if level <= self.level {
-            if self.soft_limit <= self.accumulated.len() {
-                log::warn!("truncated");
-                return;
-            }
-            let current_indentation = self
-                .current_indentation
-                .load(std::sync::atomic::Ordering::Relaxed);
-            if 0 < current_indentation {
-                let message = message.to_string();
-                let mut lines = message.split('\n');
-                if let Some(first_line) = lines.next() {
-                    if let Some(start) = &self.start {
-                        let elapsed = start.elapsed().as_micros() as u64;
-                        let _ = self.accumulated.write_fmt(format_args!("{elapsed:<6} "));
-                    }
-                    for _ in 0..current_indentation {
-                        let _ = self.accumulated.write_str("  ");
-                    }
-                    let _ = self.accumulated.write_fmt(format_args!("{first_line}\n"));
-                }
-                for line in lines {
-                    for _ in 0..current_indentation {
-                        let _ = self.accumulated.write_str("  ");
-                    }
-                    let _ = self.accumulated.write_fmt(format_args!("{line}\n"));
-                }
-            } else {
-                if let Some(start) = &self.start {
-                    let elapsed = start.elapsed().as_micros() as u64;
-                    let _ = self.accumulated.write_fmt(format_args!("{elapsed:<6} "));
-                }
-                let _ = self.accumulated.write_fmt(message);
-                let _ = self.accumulated.write_char('\n');
-            }
+    pub fn memory_used(&self) -> usize {
+        fn optional(text: &Option<String>) -> usize {
+            text.as_ref().map_or(0, String::capacity)
+        }
+        fn filter(filter: &Option<Vec<String>>) -> usize {
+            filter.as_ref().map_or(0, |names| {
+                names.capacity() * core::mem::size_of::<String>()
+                    + names.iter().map(String::capacity).sum::<usize>()
+            })
         }
+        fn context(context: &[(&str, String)], capacity: usize) -> usize {
+            capacity * core::mem::size_of::<(&str, String)>()
+                + context
+                    .iter()
+                    .map(|(_, value)| value.capacity())
+                    .sum::<usize>()
+        }
+
+        let used = self.accumulated.capacity()
+            + self.records.capacity() * core::mem::size_of::<Record>()
+            + self.throttles.capacity() * core::mem::size_of::<Throttle>()
+            + self
+                .throttles
+                .iter()
+                .map(|throttle| throttle.label.capacity())
+                .sum::<usize>()
+            + self.logged_once.capacity() * core::mem::size_of::<&Location>()
+            + self.resumable_scopes.capacity() * core::mem::size_of::<ResumableScope>()
+            + self
+                .resumable_scopes
+                .iter()
+                .map(|scope| scope.name.capacity() + core::mem::size_of::<AtomicU64>())
+                .sum::<usize>()
+            + optional(&self.start_line)
+            + optional(&self.end_line)
+            + optional(&self.drop_line)
+            + optional(&self.trace_id)
+            + filter(&self.tag_filter)
+            + filter(&self.target_filter)
+            + self.lanes.capacity() * core::mem::size_of::<&str>()
+            + context(&self.context, self.context.capacity())
+            + context(&self.pushed_context, self.pushed_context.capacity())
+            + self.attributes.capacity()
+            + self.scratch.capacity();
+        #[cfg(feature = "std")]
+        let used = used + self.subscribers.capacity() * core::mem::size_of::<Publisher>();
+        used
     }
 
-    /// log a line
+    /// Remove the contents and reset the timer (if enabled)
+    ///
+    /// The lines, their attributes and the fields of [events](Self::event)
+    /// are kept in buffers that are cleared here, not freed: each value is
+    /// written after the last. With the `arena` feature, attribute and field
+    /// values are allocated in a bumpalo arena instead, which grows by
+    /// adding chunks rather than by moving the values it already holds, and
+    /// which this rewinds to its start. Either way, a pooled detailer that
+    /// is reset between traces records the next one without allocating,
+    /// once its buffers have grown to fit.
     ///
     /// ```
-    /// use detailer::{Detailer, new_detailer};
+    /// use std::{
+    ///     alloc::{GlobalAlloc, Layout, System},
+    ///     fmt::Formatter,
+    ///     sync::atomic::{AtomicUsize, Ordering},
+    /// };
     ///
-    /// let mut detailer = new_detailer!();
+    /// use detailer::{new_detailer, DetailEvent, Detailer, EventFields};
     ///
-    /// detailer.info(format_args!("yikes {}", 42));
-    /// ```
-    pub fn info(&mut self, message: Arguments) {
-        self.log(log::Level::Info, message)
-    }
-
-    /// log a line
+    /// struct Counting;
     ///
-    /// ```
-    /// use detailer::{Detailer, new_detailer};
+    /// static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
     ///
-    /// let mut detailer = new_detailer!();
+    /// unsafe impl GlobalAlloc for Counting {
+    ///     unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+    ///         ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+    ///         unsafe { System.alloc(layout) }
+    ///     }
     ///
-    /// detailer.trace(format_args!("yikes {}", 42));
-    /// ```
-    pub fn trace(&mut self, message: Arguments) {
-        self.log(log::Level::Trace, message)
-    }
-
-    /// log a line
+    ///     unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+    ///         unsafe { System.dealloc(ptr, layout) }
+    ///     }
+    /// }
+    ///
+    /// #[global_allocator]
+    /// static GLOBAL: Counting = Counting;
+    ///
+    /// struct Lookup {
+    ///     key: u32,
+    ///     hit: bool,
+    /// }
+    ///
+    /// impl DetailEvent for Lookup {
+    ///     fn render(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    ///         write!(f, "cache lookup {}", self.key)
+    ///     }
+    ///
+    ///     fn fields(&self, fields: &mut EventFields) {
+    ///         fields.field("key", self.key);
+    ///         fields.field("hit", self.hit);
+    ///     }
+    /// }
+    ///
+    /// fn handle_request(detailer: &mut Detailer) {
+    ///     for key in 0..100 {
+    ///         let _shard = detailer.scope_with_attributes(
+    ///             format_args!("shard"),
+    ///             &[("shard", format_args!("{}", key % 4))],
+    ///         );
+    ///         detailer.event(Lookup { key, hit: key % 2 == 0 });
+    ///     }
+    /// }
+    ///
+    /// fn main() {
+    ///     let mut detailer = new_detailer!(Info, WithTiming, 64 * 1024);
+    ///     handle_request(&mut detailer);
+    ///     detailer.reset();
+    ///
+    ///     let before = ALLOCATIONS.load(Ordering::Relaxed);
+    ///     handle_request(&mut detailer);
+    ///     assert_eq!(before, ALLOCATIONS.load(Ordering::Relaxed));
+    ///     detailer.reset();
+    /// }
+    /// ```
+    pub fn reset(&mut self) {
+        self.accumulated.clear();
+        self.records.clear();
+        self.attributes.clear();
+        self.lanes.clear();
+        self.last_line = None;
+        self.throttles.clear();
+        self.resumable_scopes.clear();
+        self.suppressed = Suppressed::default();
+        self.watermark_crossed = false;
+        if let Some(coarse) = &mut self.coarse_timing {
+            coarse.lines = 0;
+        }
+        #[cfg(feature = "std")]
+        if let Some(registration) = &self.registration {
+            registration.set_lines(0);
+        }
+        if let Some(clock) = self.clock {
+            self.start = clock();
+        }
+    }
+
+    /// Output and clear the contents
+    pub fn flush(&mut self) {
+        let _ = self.try_flush();
+    }
+
+    /// Output and clear the contents, reporting if the sink could not
+    /// write them.
+    ///
+    /// The detailer's [`FlushErrorPolicy`] has already been applied when
+    /// this returns an error, so the trace may have been logged anyway. The
+    /// contents are cleared either way.
+    pub fn try_flush(&mut self) -> Result<(), FlushError> {
+        self.report_resumable_scopes();
+        self.report_throttled();
+        if !self.accumulated.is_empty() {
+            if let Some(end_line) = self.end_line.take() {
+                self.log_template(&end_line);
+                self.end_line = Some(end_line);
+            }
+        }
+        let result = self.emit();
+        self.reset();
+        if let Some(baseline) = self.shrink_after_flush {
+            if baseline < self.memory_used() {
+                self.records.shrink_to_fit();
+                self.throttles.shrink_to_fit();
+                self.resumable_scopes.shrink_to_fit();
+                self.lanes.shrink_to_fit();
+                self.attributes.shrink();
+                self.scratch = String::new();
+                let kept = self.memory_used() - self.accumulated.capacity();
+                self.accumulated.shrink_to(baseline.saturating_sub(kept));
+            }
+        }
+        result
+    }
+
+    /// Output and clear the contents so far, but keep the timer running.
+    ///
+    /// Long workflows can emit progress this way, and the timings of every
+    /// part stay on the same timeline.
+    ///
+    /// ```
+    /// use detailer::{detail, new_detailer};
+    ///
+    /// let mut detailer = new_detailer!();
+    /// detail!(detailer, "loaded batch 1");
+    /// detailer.flush_partial();
+    ///
+    /// detail!(detailer, "loaded batch 2"); // timed relative to the same start
+    /// ```
+    ///
+    /// [Resumable scopes](Self::resume_scope) and
+    /// [throttled lines](Self::log_throttled) keep counting across parts, and
+    /// their totals are reported once, by the final [`flush()`](Self::flush):
+    ///
+    /// ```
+    /// use std::sync::{Arc, Mutex};
+    ///
+    /// use detailer::{detail, new_detailer};
+    ///
+    /// let traces = Arc::new(Mutex::new(Vec::new()));
+    /// let mut detailer = new_detailer!(Info, WithoutTiming);
+    /// let flushed = traces.clone();
+    /// detailer.on_flush(move |trace| flushed.lock().unwrap().push(trace.text.to_string()));
+    ///
+    /// for batch in 1..=2 {
+    ///     drop(detailer.resume_scope("io wait"));
+    ///     detail!(detailer, "loaded batch {batch}");
+    ///     detailer.flush_partial();
+    /// }
+    /// detailer.flush();
+    /// assert_eq!(
+    ///     [
+    ///         "io wait\nloaded batch 1",
+    ///         "io wait\nloaded batch 2",
+    ///         "io wait: entered 2 times",
+    ///     ],
+    ///     traces.lock().unwrap()[..],
+    /// );
+    /// ```
+    pub fn flush_partial(&mut self) {
+        let _ = self.emit();
+        self.accumulated.clear();
+        self.records.clear();
+        self.attributes.clear();
+        self.lanes.clear();
+        self.suppressed = Suppressed::default();
+        self.watermark_crossed = false;
+        self.last_line = None;
+    }
+
+    fn emit(&mut self) -> Result<(), FlushError> {
+        self.regroup();
+        let rendered;
+        let (to_flush, lines) = if self.tag_filter.is_some()
+            || self.target_filter.is_some()
+            || (self.lane_layout != LaneLayout::Interleaved && !self.lanes.is_empty())
+            || self.collapse_below.is_some()
+            || self.prune_empty_scopes
+            || self.summary_only
+            || self.records.iter().any(|record| record.cpu.is_some())
+        {
+            rendered = self.render_flushed();
+            (rendered.0.trim_end(), rendered.1)
+        } else {
+            (self.accumulated.trim_end(), self.records.len())
+        };
+        if to_flush.is_empty() {
+            return Ok(());
+        }
+        let report_suppressed = self.report_suppressed
+            && (0 < self.suppressed.below_level || 0 < self.suppressed.over_limit);
+        let scope_bars = self.scope_bars.filter(|_| self.clock.is_some());
+        let sparkline = self
+            .sparkline
+            .filter(|_| self.clock.is_some() && 1 < self.records.len());
+        let decorated;
+        let mut body = 0..to_flush.len();
+        let to_flush = if self.context.is_empty()
+            && !report_suppressed
+            && scope_bars.is_none()
+            && sparkline.is_none()
+        {
+            to_flush
+        } else {
+            let mut text = String::with_capacity(to_flush.len() + 64);
+            if !self.context.is_empty() {
+                let _ = writeln!(text, "{}", ContextHeader(&self.context));
+            }
+            body = text.len()..text.len() + to_flush.len();
+            text.push_str(to_flush);
+            if let Some(width) = scope_bars {
+                self.write_scope_bars(&mut text, width);
+            }
+            if let Some(width) = sparkline {
+                self.write_sparkline(&mut text, width);
+            }
+            if report_suppressed {
+                self.write_suppressed(&mut text);
+            }
+            decorated = text;
+            &decorated
+        };
+        let mut level = self.level.to_level().unwrap_or(log::Level::Info);
+        if self.flush_at_highest_level {
+            // More severe levels compare as less.
+            level = self
+                .records
+                .iter()
+                .map(|record| record.level)
+                .fold(level, Ord::min);
+        }
+        let events: Vec<parse::Event> = if self.sink.as_deref().is_some_and(Sink::wants_events) {
+            (0..self.records.len())
+                .map(|index| self.recorded_event(index))
+                .collect()
+        } else {
+            Vec::new()
+        };
+        // Where the lines are in a piece of the text, once the piece is
+        // written `offset` bytes into a record of its own.
+        let body_of = |piece: &str, offset: usize| {
+            let start = piece.as_ptr() as usize - to_flush.as_ptr() as usize;
+            let from = body.start.max(start);
+            let to = body.end.min(start + piece.len()).max(from);
+            offset + from - start..offset + to - start
+        };
+        let trace = FlushedTrace {
+            level,
+            text: to_flush,
+            trace_id: self.trace_id.as_deref(),
+            context: &self.context,
+            elapsed: self.elapsed_micros().map(Duration::from_micros),
+            lines,
+            body: &to_flush[body.clone()],
+            part: None,
+            events: &events,
+        };
+        let limit = self
+            .sink
+            .as_deref()
+            .and_then(Sink::record_limit)
+            .or(self.chunk_size.map(RecordLimit::Chunk))
+            .filter(|limit| limit.bytes() < to_flush.len());
+        let chunk_size = match limit {
+            None => {
+                return deliver(
+                    &mut self.on_flush,
+                    self.sink.as_deref(),
+                    self.on_flush_error,
+                    &trace,
+                )
+            }
+            Some(RecordLimit::Chunk(bytes)) => bytes,
+            Some(RecordLimit::Truncate(bytes)) => {
+                // Leave room for the longest marker: no more than every byte
+                // can be cut.
+                let marker = |cut: usize| {
+                    let mut marker = String::with_capacity(48);
+                    let _ = write!(marker, "\n(+{cut} bytes truncated over the record limit)");
+                    marker
+                };
+                let budget = bytes.saturating_sub(marker(to_flush.len()).len()).max(1);
+                let kept = split(to_flush, budget)[0];
+                let mut text = String::with_capacity(bytes);
+                text.push_str(kept);
+                text.push_str(&marker(to_flush.len() - kept.len()));
+                return deliver(
+                    &mut self.on_flush,
+                    self.sink.as_deref(),
+                    self.on_flush_error,
+                    &FlushedTrace {
+                        text: &text,
+                        body: &text[body_of(kept, 0)],
+                        ..trace
+                    },
+                );
+            }
+            Some(RecordLimit::Reject(bytes)) => {
+                return deliver(
+                    &mut self.on_flush,
+                    Some(&Rejected {
+                        bytes: to_flush.len(),
+                        limit: bytes,
+                    }),
+                    self.on_flush_error,
+                    &trace,
+                )
+            }
+        };
+
+        // Size the parts with the longest header any of them could have: there
+        // can't be more parts than bytes.
+        let write_part_header = |text: &mut String, part: usize, parts: usize| {
+            let _ = write!(text, "part {part}/{parts}");
+            if let Some(trace_id) = trace.trace_id {
+                let _ = write!(text, " trace_id={trace_id}");
+            }
+            text.push('\n');
+        };
+        let mut text = String::with_capacity(chunk_size);
+        write_part_header(&mut text, to_flush.len(), to_flush.len());
+        let parts = split(to_flush, chunk_size.saturating_sub(text.len()).max(1));
+        let mut result = Ok(());
+        for (index, part) in parts.iter().enumerate() {
+            text.clear();
+            write_part_header(&mut text, index + 1, parts.len());
+            let part_body = body_of(part, text.len());
+            text.push_str(part);
+            let delivered = deliver(
+                &mut self.on_flush,
+                self.sink.as_deref(),
+                self.on_flush_error,
+                &FlushedTrace {
+                    text: &text,
+                    body: &text[part_body],
+                    part: Some((index + 1, parts.len())),
+                    ..trace
+                },
+            );
+            result = result.and(delivered);
+        }
+        result
+    }
+
+    /// The text of the lines that are flushed when some are left out or
+    /// collapsed, and how many lines there are
+    fn render_flushed(&self) -> (String, usize) {
+        // Leaving lines out only shrinks the trace, so one allocation is
+        // almost always enough for the whole pass.
+        let mut text = String::with_capacity(self.accumulated.len());
+        let mut lines = 0;
+        let mut lanes = match self.lane_layout {
+            LaneLayout::Grouped => self.lanes.iter().map(|_| String::new()).collect(),
+            _ => Vec::new(),
+        };
+        // Side by side, lines are laid out in columns once they are all
+        // chosen, from their messages in `text`.
+        let side_by_side = self.lane_layout == LaneLayout::SideBySide;
+        let mut cells = Vec::new();
+        let line_start = |record: &Record| {
+            if side_by_side {
+                record.message_start
+            } else {
+                record.start
+            }
+        };
+        let mut index = 0;
+        while let Some(record) = self.records.get(index) {
+            let end = self.record_end(index);
+            index += 1;
+            if let Some(filter) = &self.tag_filter {
+                if !record
+                    .labels
+                    .tags
+                    .iter()
+                    .any(|tag| filter.iter().any(|wanted| wanted == tag))
+                {
+                    continue;
+                }
+            }
+            if let Some(filter) = &self.target_filter {
+                if !record
+                    .labels
+                    .target
+                    .is_some_and(|target| filter.iter().any(|wanted| within(target, wanted)))
+                {
+                    continue;
+                }
+            }
+            let contents = self.records[index..]
+                .iter()
+                .take_while(|inner| record.indentation < inner.indentation)
+                .count();
+            if self.prune_empty_scopes
+                && record.scope
+                && self.records[index..index + contents]
+                    .iter()
+                    .all(|inner| inner.scope)
+            {
+                // Scopes inside an empty scope are empty too.
+                index += contents;
+                continue;
+            }
+            if self.summary_only {
+                if record.scope {
+                    let cell_start = text.len();
+                    let header = &self.accumulated[line_start(record)..end];
+                    text.push_str(header.strip_suffix('\n').unwrap_or(header));
+                    let (finished, cpu_finished) = self.scope_end(index + contents);
+                    if let (Some(start), Some(finished)) = (record.elapsed, finished) {
+                        let _ = write!(text, " {}µs,", finished.saturating_sub(start));
+                    }
+                    if let (Some(start), Some(finished)) = (record.cpu, cpu_finished) {
+                        let _ = write!(text, " {}µs cpu,", finished.saturating_sub(start));
+                    }
+                    let _ = writeln!(text, " {contents} line{}", plural(contents as u64));
+                    if side_by_side {
+                        cells.push((record, cell_start..text.len()));
+                    }
+                    lines += 1;
+                }
+                continue;
+            }
+            if let Some(below) = self.collapse_below {
+                let inside = &self.records[index..index + contents];
+                if 0 < contents && inside.iter().all(|inner| below < inner.level) {
+                    let cell_start = text.len();
+                    let header = &self.accumulated[line_start(record)..end];
+                    text.push_str(header.strip_suffix('\n').unwrap_or(header));
+                    let _ = write!(text, " … {contents} line{} elided", plural(contents as u64));
+                    if let (Some(start), Some(last)) = (
+                        record.elapsed,
+                        inside.last().and_then(|inner| inner.elapsed),
+                    ) {
+                        let _ = write!(text, ", {}µs", last.saturating_sub(start));
+                    }
+                    text.push('\n');
+                    if side_by_side {
+                        cells.push((record, cell_start..text.len()));
+                    }
+                    lines += 1;
+                    index += contents;
+                    continue;
+                }
+            }
+            let lane = record.labels.lane.and_then(|lane| {
+                let position = self.lanes.iter().position(|used| *used == lane)?;
+                Some((lane, lanes.get_mut(position)?))
+            });
+            if let Some((lane, grouped)) = lane {
+                // Indent the line under its lane, without the lane's marker.
+                let message = &self.accumulated[record.message_start..end];
+                grouped.push_str(&self.accumulated[record.start..record.message_start]);
+                grouped.push_str("  ");
+                grouped.push_str(LaneMarker(lane).strip(message));
+            } else if side_by_side {
+                let cell_start = text.len();
+                let message = &self.accumulated[record.message_start..end];
+                text.push_str(
+                    record
+                        .labels
+                        .lane
+                        .map_or(message, |lane| LaneMarker(lane).strip(message)),
+                );
+                self.write_scope_times(&mut text, record, index + contents);
+                cells.push((record, cell_start..text.len()));
+            } else {
+                text.push_str(&self.accumulated[record.start..end]);
+                self.write_scope_times(&mut text, record, index + contents);
+            }
+            lines += 1;
+        }
+        if side_by_side {
+            text = self.lane_columns(&text, &cells);
+        }
+        for (lane, grouped) in self.lanes.iter().zip(&lanes) {
+            if !grouped.is_empty() {
+                let _ = writeln!(text, "{lane}");
+                text.push_str(grouped);
+                lines += 1;
+            }
+        }
+        (text, lines)
+    }
+
+    /// When the scope whose contents end before the record at `after`
+    /// finished, in elapsed and CPU microseconds.
+    fn scope_end(&self, after: usize) -> (Option<u64>, Option<u64>) {
+        match self.records.get(after) {
+            Some(after) => (after.elapsed, after.cpu),
+            None => (self.elapsed_micros(), self.thread_cpu_micros()),
+        }
+    }
+
+    /// Finish a scope's opening line, just written to `text`, with how long
+    /// the scope took and the CPU time it used, when CPU time is recorded.
+    fn write_scope_times(&self, text: &mut String, record: &Record, after: usize) {
+        let Some(cpu_start) = record.cpu.filter(|_| record.scope) else {
+            return;
+        };
+        if text.ends_with('\n') {
+            text.pop();
+        }
+        let (finished, cpu_finished) = self.scope_end(after);
+        if let (Some(start), Some(finished)) = (record.elapsed, finished) {
+            let _ = write!(text, " {}µs wall,", finished.saturating_sub(start));
+        }
+        let _ = writeln!(
+            text,
+            " {}µs cpu",
+            cpu_finished.unwrap_or(cpu_start).saturating_sub(cpu_start)
+        );
+    }
+
+    /// Lay out lines in a column for each lane, after one for the lines
+    /// outside any lane, with a row for each line in the order they were
+    /// recorded. `cells` are the records and where their messages are in
+    /// `text`.
+    fn lane_columns(&self, text: &str, cells: &[(&Record, Range<usize>)]) -> String {
+        let mut columns: Vec<Option<&str>> = Vec::new();
+        if cells.iter().any(|(record, _)| record.labels.lane.is_none()) {
+            columns.push(None);
+        }
+        columns.extend(
+            self.lanes
+                .iter()
+                .filter(|lane| {
+                    cells
+                        .iter()
+                        .any(|(record, _)| record.labels.lane == Some(**lane))
+                })
+                .map(|lane| Some(*lane)),
+        );
+
+        // Each row is when it was recorded, its column, its indentation and
+        // its text. Continuation lines of a message get rows of their own.
+        let mut rows = Vec::with_capacity(cells.len());
+        for (record, range) in cells {
+            let column = columns
+                .iter()
+                .position(|column| *column == record.labels.lane)
+                .unwrap_or_default();
+            for (line, line_text) in text[range.clone()].trim_end().split('\n').enumerate() {
+                match line {
+                    0 => rows.push((record.elapsed, column, record.indentation, line_text)),
+                    _ => rows.push((None, column, record.indentation, line_text.trim_start())),
+                }
+            }
+        }
+        let mut widths: Vec<usize> = columns
+            .iter()
+            .map(|column| column.map_or(0, |lane| lane.chars().count()))
+            .collect();
+        let mut elapsed_width = 0;
+        for (elapsed, column, indentation, line_text) in &rows {
+            if let Some(width) = widths.get_mut(*column) {
+                *width = (*width).max(2 * indentation + line_text.chars().count());
+            }
+            if let Some(elapsed) = elapsed {
+                elapsed_width =
+                    elapsed_width.max(elapsed.checked_ilog10().unwrap_or(0) as usize + 1);
+            }
+        }
+
+        let mut table = String::with_capacity(text.len() + rows.len() * 8 * widths.len());
+        let mut row = String::new();
+        let finish_row = |table: &mut String, row: &mut String| {
+            table.push_str(row.trim_end());
+            table.push('\n');
+            row.clear();
+        };
+        if 0 < elapsed_width {
+            let _ = write!(row, "{:width$}   │ ", "", width = elapsed_width);
+        }
+        for (index, column) in columns.iter().enumerate() {
+            if 0 < index {
+                row.push_str(" │ ");
+            }
+            let _ = write!(
+                row,
+                "{:width$}",
+                column.unwrap_or_default(),
+                width = widths[index]
+            );
+        }
+        finish_row(&mut table, &mut row);
+        for (elapsed, column, indentation, line_text) in rows {
+            if 0 < elapsed_width {
+                match elapsed {
+                    Some(elapsed) => {
+                        let _ = write!(row, "{elapsed:>elapsed_width$}µs │ ");
+                    }
+                    None => {
+                        let _ = write!(row, "{:width$}   │ ", "", width = elapsed_width);
+                    }
+                }
+            }
+            for (index, width) in widths.iter().enumerate() {
+                if 0 < index {
+                    row.push_str(" │ ");
+                }
+                let cell_width = if index == column {
+                    let _ = write!(row, "{:indent$}{line_text}", "", indent = 2 * indentation);
+                    2 * indentation + line_text.chars().count()
+                } else {
+                    0
+                };
+                let _ = write!(
+                    row,
+                    "{:width$}",
+                    "",
+                    width = width.saturating_sub(cell_width)
+                );
+            }
+            finish_row(&mut table, &mut row);
+        }
+        table
+    }
+
+    /// Write a bar for each top-level scope, as long as the scope took, after
+    /// the trace. The longest is `width` characters.
+    fn write_scope_bars(&self, text: &mut String, width: usize) {
+        let mut scopes = Vec::new();
+        for (index, record) in self.records.iter().enumerate() {
+            let Some(start) = record
+                .elapsed
+                .filter(|_| record.scope && record.indentation == 0)
+            else {
+                continue;
+            };
+            let finished = match self.records[index + 1..]
+                .iter()
+                .find(|after| after.indentation == 0)
+            {
+                Some(after) => after.elapsed,
+                None => self.elapsed_micros(),
+            };
+            let message = &self.accumulated[record.message_start..self.record_end(index)];
+            let name = message.lines().next().unwrap_or_default();
+            scopes.push((name, finished.unwrap_or(start).saturating_sub(start)));
+        }
+        let longest = scopes
+            .iter()
+            .map(|(_, took)| *took)
+            .max()
+            .unwrap_or(1)
+            .max(1);
+        let name_width = scopes
+            .iter()
+            .map(|(name, _)| name.chars().count())
+            .max()
+            .unwrap_or_default();
+        for (name, took) in scopes {
+            let bar = (took * width as u64).div_ceil(longest).max(1) as usize;
+            let _ = write!(text, "\n{name:<name_width$} ▏{:█<bar$} {took}µs", "");
+        }
+    }
+
+    /// Write a sparkline of the time between each line and the next, after
+    /// the trace. When there are more than `width` gaps, each character shows
+    /// the longest of the gaps it stands for, so spikes stay visible.
+    fn write_sparkline(&self, text: &mut String, width: usize) {
+        const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+        let mut deltas = self
+            .records
+            .windows(2)
+            .filter_map(|pair| Some(pair[1].elapsed?.saturating_sub(pair[0].elapsed?)));
+        let gaps = self.records.len() - 1;
+        let per_character = gaps.div_ceil(width.max(1));
+        let mut buckets = Vec::with_capacity(gaps.div_ceil(per_character));
+        loop {
+            let bucket = deltas.by_ref().take(per_character).max();
+            let Some(bucket) = bucket else {
+                break;
+            };
+            buckets.push(bucket);
+        }
+        let longest = buckets.iter().copied().max().unwrap_or_default();
+        text.push_str("\ngaps ");
+        for bucket in buckets {
+            let bar = (bucket * (BARS.len() as u64 - 1))
+                .checked_div(longest)
+                .unwrap_or_default();
+            text.push(BARS[bar as usize]);
+        }
+        let _ = write!(text, " longest {longest}µs");
+    }
+
+    /// Move the lines recorded into each scope begun with
+    /// [`enter_scope()`](Self::enter_scope) after the scope's line and the
+    /// lines recorded into it before them, so that each scope's lines are
+    /// together in the text, events and structure that are flushed.
+    fn regroup(&mut self) {
+        if self
+            .records
+            .iter()
+            .all(|record| record.labels.scope.is_none())
+        {
+            return;
+        }
+        // The lines of each scope, and of no scope, in the order they were
+        // recorded. Lines whose scope began in an earlier part of the trace
+        // stay where they are.
+        let mut lines: BTreeMap<Option<u64>, Vec<usize>> = BTreeMap::new();
+        let opened: BTreeSet<u64> = self
+            .records
+            .iter()
+            .filter_map(|record| record.opens)
+            .collect();
+        for (index, record) in self.records.iter().enumerate() {
+            let scope = record.labels.scope.filter(|scope| opened.contains(scope));
+            lines.entry(scope).or_default().push(index);
+        }
+        let mut order = Vec::with_capacity(self.records.len());
+        let mut open = Vec::from([(lines.get(&None).map_or(&[][..], Vec::as_slice), 0)]);
+        while let Some((scope_lines, next)) = open.last_mut() {
+            let Some(&index) = scope_lines.get(*next) else {
+                open.pop();
+                continue;
+            };
+            *next += 1;
+            order.push(index);
+            if let Some(inner) = self.records[index]
+                .opens
+                .and_then(|id| lines.get(&Some(id)))
+            {
+                open.push((inner, 0));
+            }
+        }
+        if order
+            .iter()
+            .enumerate()
+            .all(|(position, index)| position == *index)
+        {
+            return;
+        }
+        let mut text = String::with_capacity(self.accumulated.capacity());
+        text.push_str(&self.accumulated[..self.records[0].start]);
+        let mut starts: Vec<usize> = self.records.iter().map(|record| record.start).collect();
+        for &index in &order {
+            starts[index] = text.len();
+            text.push_str(&self.accumulated[self.records[index].start..self.record_end(index)]);
+        }
+        for (record, start) in self.records.iter_mut().zip(starts) {
+            record.message_start = start + (record.message_start - record.start);
+            record.start = start;
+        }
+        self.records.sort_by_key(|record| record.start);
+        self.accumulated = text;
+        self.last_line = None;
+    }
+
+    /// Write how many lines were not recorded, after the trace.
+    fn write_suppressed(&self, text: &mut String) {
+        if 0 < self.suppressed.below_level {
+            let _ = write!(
+                text,
+                "\n(+{} line{} suppressed below {})",
+                self.suppressed.below_level,
+                plural(self.suppressed.below_level as u64),
+                self.level
+            );
+        }
+        if 0 < self.suppressed.over_limit {
+            let _ = write!(
+                text,
+                "\n(+{} line{} truncated over the size limit)",
+                self.suppressed.over_limit,
+                plural(self.suppressed.over_limit as u64)
+            );
+        }
+    }
+
+    /// Where the text of the record at `index` ends
+    fn record_end(&self, index: usize) -> usize {
+        self.records
+            .get(index + 1)
+            .map_or(self.accumulated.len(), |next| next.start)
+    }
+
+    /// Record a line with the totals of each resumable scope, and start them
+    /// over.
+    fn report_resumable_scopes(&mut self) {
+        let scopes = core::mem::take(&mut self.resumable_scopes);
+        let Some(level) = self.effective_level().to_level() else {
+            return;
+        };
+        for scope in scopes {
+            if self.clock.is_some() {
+                self.log(
+                    level,
+                    format_args!(
+                        "{}: entered {} time{}, {}µs total",
+                        scope.name,
+                        scope.entries,
+                        plural(scope.entries as u64),
+                        scope.micros.load(core::sync::atomic::Ordering::Relaxed)
+                    ),
+                );
+            } else {
+                self.log(
+                    level,
+                    format_args!(
+                        "{}: entered {} time{}",
+                        scope.name,
+                        scope.entries,
+                        plural(scope.entries as u64)
+                    ),
+                );
+            }
+        }
+    }
+
+    /// Record a line with how many lines each throttled call site suppressed,
+    /// and start the counts over.
+    fn report_throttled(&mut self) {
+        if self.throttles.iter().all(|throttle| throttle.total == 0) {
+            return;
+        }
+        let Some(level) = self.effective_level().to_level() else {
+            return;
+        };
+        let mut line = String::from("suppressed: ");
+        for throttle in self
+            .throttles
+            .iter_mut()
+            .filter(|throttle| 0 < throttle.total)
+        {
+            if line.len() > "suppressed: ".len() {
+                line.push_str(", ");
+            }
+            let _ = write!(
+                line,
+                "{} ×{}",
+                throttle.label,
+                core::mem::take(&mut throttle.total)
+            );
+        }
+        self.log(level, format_args!("{line}"));
+    }
+
+    /// A recorded line as an event, with the layout of its continuation
+    /// lines taken back out of its message
+    fn recorded_event(&self, index: usize) -> parse::Event {
+        let record = &self.records[index];
+        let text = &self.accumulated[record.message_start..self.record_end(index)];
+        let mut lines = text.strip_suffix('\n').unwrap_or(text).split('\n');
+        let mut message = SmallString::from(lines.next().unwrap_or_default());
+        let padding = match self.continuation_lines {
+            ContinuationLines::Aligned => {
+                visible_width(&self.accumulated[record.start..record.message_start])
+            }
+            _ => 2 * record.indentation,
+        };
+        for line in lines {
+            message.push('\n');
+            let continued = match self.continuation_lines {
+                ContinuationLines::Prefixed => line
+                    .split_once(parse::CONTINUATION_MARKER)
+                    .map(|(_prefix, continued)| continued),
+                _ => None,
+            };
+            message.push_str(continued.unwrap_or_else(|| {
+                let spaces = line.len() - line.trim_start_matches(' ').len();
+                &line[spaces.min(padding)..]
+            }));
+        }
+        parse::Event {
+            elapsed: record.elapsed.map(Duration::from_micros),
+            indent: record.indentation,
+            message,
+        }
+    }
+
+    /// The lines recorded so far, oldest first
+    pub(crate) fn recorded_lines(&self) -> impl Iterator<Item = RecordedLine<'_>> {
+        self.records.iter().enumerate().map(|(index, record)| {
+            let message = &self.accumulated[record.message_start..self.record_end(index)];
+            RecordedLine {
+                level: record.level,
+                elapsed: record.elapsed.map(Duration::from_micros),
+                since_epoch: record.since_epoch,
+                indent: record.indentation,
+                message: message.strip_suffix('\n').unwrap_or(message),
+                target: record.labels.target,
+                lane: record.labels.lane,
+                attributes: self.attributes.attributes(record.attributes.clone()),
+            }
+        })
+    }
+
+    /// Microseconds since the timer started, if timing is enabled
+    pub(crate) fn elapsed_micros(&self) -> Option<u64> {
+        self.clock.map(|clock| {
+            let elapsed = clock().saturating_sub(self.start);
+            elapsed - elapsed % self.granularity_micros
+        })
+    }
+
+    /// Microseconds since the timer started for a new line, which with
+    /// coarse timing may be the time of an earlier line.
+    fn line_elapsed_micros(&mut self) -> Option<u64> {
+        let Some(coarse) = &mut self.coarse_timing else {
+            return self.elapsed_micros();
+        };
+        if coarse.lines % coarse.every != 0 {
+            coarse.lines += 1;
+            return coarse.elapsed;
+        }
+        coarse.lines += 1;
+        let elapsed = self.elapsed_micros();
+        if let Some(coarse) = &mut self.coarse_timing {
+            coarse.elapsed = elapsed;
+        }
+        elapsed
+    }
+
+    /// Indent output one more level as long as the scope guard exists
+    pub fn scope(&mut self, scope_name: Arguments) -> DetailScopeGuard {
+        if let Some(level) = self.effective_level().to_level() {
+            let records = self.records.len();
+            self.log(level, scope_name);
+            self.mark_scope(records);
+        }
+        DetailScopeGuard::new(self.current_indentation.clone(), None)
+    }
+
+    /// Record a typed event as a line, if its level is enabled. The line is
+    /// the event's text, and its fields are the line's attributes. See
+    /// [`DetailEvent`].
+    pub fn event(&mut self, event: impl DetailEvent) {
+        let level = event.level();
+        if self.effective_level() < level {
+            self.suppressed.below_level += 1;
+            return;
+        }
+        let start = self.attributes.len();
+        event.fields(&mut EventFields::new(&mut self.attributes));
+        let records = self.records.len();
+        let end = self.attributes.len();
+        self.record(
+            level,
+            Message::Format(format_args!("{}", EventText(&event))),
+            None,
+            Labels::default(),
+            start..end,
+        );
+        if self.records.len() == records {
+            self.attributes.truncate(start);
+        }
+    }
+
+    /// Indent output one more level as long as the scope guard exists, and
+    /// keep `attributes` on the scope's line. The attributes follow the name
+    /// in the text, and [line callbacks](Self::on_line) and
+    /// [subscribers](Self::subscribe) see them apart from the name.
+    ///
+    /// ```
+    /// use detailer::{detail, new_detailer, scope};
+    ///
+    /// let mut detailer = new_detailer!(Info, WithoutTiming);
+    /// detailer.on_line(|line| {
+    ///     if line.message.starts_with("query") {
+    ///         assert_eq!(Some("users"), line.attributes.get("table"));
+    ///     }
+    /// });
+    /// let table = "users";
+    /// let rows_hint = 10;
+    /// {
+    ///     let _scope = scope!(detailer, "query"; table = %table, rows_hint = rows_hint, plan = ?Some(1));
+    ///     detail!(detailer, "planned");
+    /// }
+    /// assert_eq!(
+    ///     "query table=users rows_hint=10 plan=Some(1)\n  planned\n",
+    ///     detailer.peek(),
+    /// );
+    /// ```
+    pub fn scope_with_attributes(
+        &mut self,
+        scope_name: Arguments,
+        attributes: &[(&'static str, Arguments)],
+    ) -> DetailScopeGuard {
+        if let Some(level) = self.effective_level().to_level() {
+            let start = self.attributes.len();
+            for (key, value) in attributes {
+                self.attributes.push(key, *value);
+            }
+            let records = self.records.len();
+            let end = self.attributes.len();
+            self.record(
+                level,
+                Message::Format(format_args!("{scope_name} {}", ContextHeader(attributes))),
+                None,
+                Labels::default(),
+                start..end,
+            );
+            if self.records.len() == records {
+                self.attributes.truncate(start);
+            }
+            self.mark_scope(records);
+        }
+        DetailScopeGuard::new(self.current_indentation.clone(), None)
+    }
+
+    /// Indent output one more level, and record lines at `level` instead of
+    /// the detailer's level, as long as the scope guard exists. A detailer
+    /// that is `Off` stays off.
+    ///
+    /// ```
+    /// use detailer::new_detailer;
+    ///
+    /// let mut detailer = new_detailer!(Debug, WithoutTiming);
+    /// {
+    ///     let _quiet = detailer.scope_at_level(log::LevelFilter::Warn, format_args!("polling"));
+    ///     detailer.debug(format_args!("not logged"));
+    /// }
+    /// detailer.debug(format_args!("logged"));
+    /// assert_eq!("polling\nlogged\n", detailer.peek());
+    /// ```
+    pub fn scope_at_level(
+        &mut self,
+        level: log::LevelFilter,
+        scope_name: Arguments,
+    ) -> DetailScopeGuard {
+        let mut guard = self.scope(scope_name);
+        guard.scope_level = Some(ScopeLevel::new(self.scope_level.clone(), level));
+        guard
+    }
+
+    /// Enter the scope named `name` again, like [`scope()`](Self::scope),
+    /// keeping a running total of the time spent in it.
+    ///
+    /// Use this for phases that are interleaved with other work, like
+    /// repeated I/O waits. When the trace is flushed, each resumed scope adds
+    /// a line with how many times it was entered and, with timing, the total
+    /// time spent in it.
+    ///
+    /// ```
+    /// use std::sync::atomic::{AtomicU64, Ordering};
+    ///
+    /// use detailer::{detail, Detailer, TimingSetting};
+    ///
+    /// static NOW: AtomicU64 = AtomicU64::new(0);
+    /// fn now() -> u64 {
+    ///     NOW.load(Ordering::Relaxed)
+    /// }
+    ///
+    /// let mut detailer = Detailer::new(log::LevelFilter::Info, TimingSetting::WithClock(now), 4096);
+    /// detailer.on_flush(|trace| assert!(trace.text.ends_with("io wait: entered 2 times, 150µs total")));
+    /// for wait in [100, 50] {
+    ///     let _io = detailer.resume_scope("io wait");
+    ///     NOW.fetch_add(wait, Ordering::Relaxed);
+    /// }
+    /// detail!(detailer, "done");
+    /// detailer.flush();
+    /// ```
+    ///
+    /// The totals come before the [end line](Self::end_line):
+    ///
+    /// ```
+    /// use detailer::{detail, new_detailer};
+    ///
+    /// let mut detailer = new_detailer!(Info, WithoutTiming);
+    /// detailer.end_line(Some("request complete"));
+    /// detailer.on_flush(|trace| {
+    ///     assert!(trace.text.ends_with("io wait: entered 1 time\nrequest complete"))
+    /// });
+    /// drop(detailer.resume_scope("io wait"));
+    /// detail!(detailer, "done");
+    /// detailer.flush();
+    /// ```
+    pub fn resume_scope(&mut self, name: &str) -> DetailScopeGuard {
+        let index = match self
+            .resumable_scopes
+            .iter()
+            .position(|scope| scope.name == name)
+        {
+            Some(index) => index,
+            None => {
+                self.resumable_scopes.push(ResumableScope {
+                    name: name.into(),
+                    entries: 0,
+                    micros: Default::default(),
+                });
+                self.resumable_scopes.len() - 1
+            }
+        };
+        self.resumable_scopes[index].entries += 1;
+        let micros = self.resumable_scopes[index].micros.clone();
+        let mut guard = self.scope(format_args!("{name}"));
+        guard.timer = self.clock.map(|clock| ScopeTimer {
+            micros,
+            clock,
+            entered: clock(),
+        });
+        guard
+    }
+
+    /// Whether a line at `level` would be recorded
+    #[cfg(feature = "std")]
+    pub(crate) fn records_level(&self, level: log::Level) -> bool {
+        level <= self.effective_level()
+    }
+
+    /// The level lines are recorded at, considering the current scope
+    fn effective_level(&self) -> log::LevelFilter {
+        if self.level == log::LevelFilter::Off {
+            return log::LevelFilter::Off;
+        }
+        match self.scope_level.load(core::sync::atomic::Ordering::Relaxed) {
+            NO_SCOPE_LEVEL => self.level,
+            level => level_filter(level),
+        }
+    }
+
+    /// log a line, if the level is enabled.
+    ///
+    /// A message with nothing to format, like `format_args!("connected")`, is
+    /// written the same way as [`log_static()`](Self::log_static), so
+    /// `detail!()` and the other macros take that path on their own.
+    ///
+    /// ```
+    /// use detailer::{Detailer, new_detailer};
+    ///
+    /// let mut detailer = new_detailer!();
+    ///
+    /// detailer.log(log::Level::Warn, format_args!("yikes {}", 42));
+    /// ```
+    pub fn log(&mut self, level: log::Level, message: Arguments) {
+        match message.as_str() {
+            Some(text) => self.record(level, Message::Text(text), None, Labels::default(), 0..0),
+            None => self.record(
+                level,
+                Message::Format(message),
+                None,
+                Labels::default(),
+                0..0,
+            ),
+        }
+    }
+
+    /// log a constant line, if the level is enabled.
+    ///
+    /// This skips the formatting machinery entirely, which is what most
+    /// detail lines need. Under a scope, the prefix, indentation and line are
+    /// written in one pass.
+    ///
+    /// ```
+    /// use detailer::{Detailer, new_detailer};
+    ///
+    /// let mut detailer = new_detailer!();
+    ///
+    /// detailer.log_static(log::Level::Info, "authenticating");
+    /// ```
+    pub fn log_static(&mut self, level: log::Level, message: &'static str) {
+        self.record(level, Message::Text(message), None, Labels::default(), 0..0)
+    }
+
+    /// Begin a scope that is addressed by its handle instead of by a guard.
+    ///
+    /// A [`DetailScopeGuard`] indents everything recorded while it exists, so
+    /// one held across an `.await` also indents whatever other tasks record
+    /// on the same detailer in the meantime. Lines recorded with
+    /// [`log_in()`](Self::log_in) are indented under their scope no matter
+    /// what else is open, and flushed under its line with the scope's other
+    /// lines, which keeps interleaved async steps straight. The scope is
+    /// nested under the scopes open when it begins. There is nothing to
+    /// close: stop recording into the scope when it is done.
+    ///
+    /// ```
+    /// use detailer::{detail, detail_in, new_detailer};
+    ///
+    /// let mut detailer = new_detailer!(Info, WithoutTiming);
+    /// let fetch = detailer.enter_scope(format_args!("fetch"));
+    /// let render = detailer.enter_scope(format_args!("render"));
+    /// detail_in!(detailer, fetch, "connected");
+    /// detail_in!(detailer, render, "layout");
+    /// let retry = detailer.enter_scope_in(fetch, format_args!("retry"));
+    /// detail_in!(detailer, retry, "connected");
+    /// detail!(detailer, "done");
+    /// detailer.on_flush(|trace| {
+    ///     assert_eq!(
+    ///         "fetch\n  connected\n  retry\n    connected\nrender\n  layout\ndone",
+    ///         trace.text,
+    ///     )
+    /// });
+    /// detailer.flush();
+    /// ```
+    ///
+    /// Until then, [`peek()`](Self::peek) shows the lines in the order they
+    /// were recorded.
+    pub fn enter_scope(&mut self, scope_name: Arguments) -> ScopeId {
+        let depth = self
+            .current_indentation
+            .load(core::sync::atomic::Ordering::Relaxed);
+        self.open_scope(depth, None, scope_name)
+    }
+
+    /// Begin a scope nested under `parent`, like
+    /// [`enter_scope()`](Self::enter_scope).
+    pub fn enter_scope_in(&mut self, parent: ScopeId, scope_name: Arguments) -> ScopeId {
+        self.open_scope(parent.depth + 1, Some(parent.id), scope_name)
+    }
+
+    fn open_scope(&mut self, depth: usize, parent: Option<u64>, scope_name: Arguments) -> ScopeId {
+        let id = NEXT_SCOPE_ID.fetch_add(1, core::sync::atomic::Ordering::Relaxed);
+        if let Some(level) = self.effective_level().to_level() {
+            let records = self.records.len();
+            self.record(
+                level,
+                Message::Format(scope_name),
+                Some(depth),
+                Labels {
+                    scope: parent,
+                    ..Labels::default()
+                },
+                0..0,
+            );
+            self.mark_scope(records);
+            if let Some(record) = self.records.get_mut(records) {
+                record.opens = Some(id);
+            }
+        }
+        ScopeId { depth, id }
+    }
+
+    /// Mark the line recorded after there were `records`, if there is one, as
+    /// a scope's opening line.
+    fn mark_scope(&mut self, records: usize) {
+        if let Some(record) = self.records.get_mut(records) {
+            record.scope = true;
+        }
+    }
+
+    /// log a line with `tags`, if the level is enabled. A
+    /// [tag filter](Self::tag_filter) can flush only the lines with some
+    /// tags.
+    ///
+    /// ```
+    /// use detailer::new_detailer;
+    ///
+    /// let mut detailer = new_detailer!(Info, WithoutTiming);
+    /// detailer.log_tagged(log::Level::Info, &["db"], format_args!("query took {}ms", 3));
+    /// ```
+    pub fn log_tagged(
+        &mut self,
+        level: log::Level,
+        tags: &'static [&'static str],
+        message: Arguments,
+    ) {
+        self.record(
+            level,
+            Message::Format(message),
+            None,
+            Labels {
+                tags,
+                ..Labels::default()
+            },
+            0..0,
+        )
+    }
+
+    /// log a line for `target`, if the level is enabled. The target stands in
+    /// for the detailer's in the [`LineFormat::EnvLogger`] layout, goes with
+    /// the line to [line callbacks](Self::on_line) and subscribers, and a
+    /// [target filter](Self::target_filter) can flush only the lines for some
+    /// targets.
+    ///
+    /// ```
+    /// use detailer::{new_detailer, LineFormat};
+    ///
+    /// # #[cfg(feature = "std")]
+    /// # {
+    /// let mut detailer = new_detailer!(Info, WithoutTiming);
+    /// detailer.line_format(LineFormat::EnvLogger { target: "checkout" });
+    /// detailer.log_target(log::Level::Info, "checkout::db", format_args!("query took {}ms", 3));
+    /// assert!(detailer.peek().ends_with(" INFO  checkout::db] query took 3ms\n"));
+    /// # }
+    /// ```
+    pub fn log_target(&mut self, level: log::Level, target: &'static str, message: Arguments) {
+        self.record(
+            level,
+            Message::Format(message),
+            None,
+            Labels {
+                target: Some(target),
+                ..Labels::default()
+            },
+            0..0,
+        )
+    }
+
+    /// Record lines into the lane called `name`, for one of several streams
+    /// of activity that go on at the same time within a workflow. How they
+    /// are flushed is up to the [lane layout](Self::lane_layout).
+    ///
+    /// ```
+    /// use detailer::{detail, new_detailer, LaneLayout};
+    ///
+    /// let mut detailer = new_detailer!(Info, WithoutTiming);
+    /// detail!(detailer, "started");
+    /// detail!(detailer.lane("network"), "sent request");
+    /// detail!(detailer.lane("compute"), "decoded frame 1");
+    /// detail!(detailer.lane("network"), "received response");
+    /// assert_eq!(
+    ///     "started\n[network] sent request\n[compute] decoded frame 1\n[network] received response\n",
+    ///     detailer.peek(),
+    /// );
+    ///
+    /// detailer.lane_layout(LaneLayout::Grouped);
+    /// detailer.on_flush(|trace| {
+    ///     assert_eq!(
+    ///         "started\nnetwork\n  sent request\n  received response\ncompute\n  decoded frame 1",
+    ///         trace.text,
+    ///     )
+    /// });
+    /// detailer.flush();
+    /// ```
+    pub fn lane(&mut self, name: &'static str) -> Lane<'_> {
+        Lane {
+            detailer: self,
+            name,
+        }
+    }
+
+    /// log a line under `scope`, if the level is enabled.
+    pub fn log_in(&mut self, scope: ScopeId, level: log::Level, message: Arguments) {
+        self.record(
+            level,
+            Message::Format(message),
+            Some(scope.depth + 1),
+            Labels {
+                scope: Some(scope.id),
+                ..Labels::default()
+            },
+            0..0,
+        )
+    }
+
+    /// Record a line, indented by `indentation` or by the open scope guards.
+    fn record(
+        &mut self,
+        level: log::Level,
+        message: Message,
+        indentation: Option<usize>,
+        labels: Labels,
+        attributes: Range<usize>,
+    ) {
+        if level <= self.effective_level() {
+            #[cfg(feature = "std")]
+            if self
+                .registration
+                .as_ref()
+                .is_some_and(|registration| registration.take_flush_request())
+            {
+                self.flush();
+            }
+            if self.soft_limit <= self.accumulated.len() {
+                log::warn!("truncated");
+                self.suppressed.over_limit += 1;
+                return;
+            }
+            let elapsed = self.line_elapsed_micros();
+            let since_epoch = self.since_epoch();
+            let current_indentation = indentation.unwrap_or_else(|| {
+                self.current_indentation
+                    .load(core::sync::atomic::Ordering::Relaxed)
+            });
+            if self.accumulated.is_empty() {
+                if let Some(start_line) = self.start_line.take() {
+                    self.log_template(&start_line);
+                    self.start_line = Some(start_line);
+                }
+            }
+            #[cfg(feature = "std")]
+            if self.start_time_header && self.accumulated.is_empty() {
+                self.write_start_time_header(elapsed);
+            }
+            self.pushed_context.truncate(
+                self.pushed_depth
+                    .load(core::sync::atomic::Ordering::Relaxed),
+            );
+            #[cfg(feature = "std")]
+            let thread_label = self.thread_labels.then(crate::process::thread_label);
+            #[cfg(feature = "std")]
+            let thread = thread_label.as_deref();
+            #[cfg(not(feature = "std"))]
+            let thread: Option<&str> = None;
+            let record_start = self.accumulated.len();
+            let message_start;
+            if 0 < current_indentation || self.continuation_lines != ContinuationLines::Unaligned {
+                // Formatted messages are laid out from a buffer that is kept
+                // between lines, so they don't allocate once it has grown.
+                let mut formatted = core::mem::take(&mut self.scratch);
+                formatted.clear();
+                let message_text = match message {
+                    Message::Text(text)
+                        if self.max_message_length.is_none()
+                            && labels.lane.is_none()
+                            && thread.is_none()
+                            && self.pushed_context.is_empty() =>
+                    {
+                        text
+                    }
+                    message => {
+                        write_line_markers(
+                            &mut formatted,
+                            labels.lane,
+                            thread,
+                            &self.pushed_context,
+                        );
+                        write_message(&mut formatted, message, self.max_message_length);
+                        &formatted
+                    }
+                };
+                message_start = self.write_lines(
+                    elapsed,
+                    level,
+                    labels.target,
+                    current_indentation,
+                    message_text.split('\n'),
+                );
+                self.scratch = formatted;
+            } else {
+                self.write_prefix(elapsed, level, labels.target, 0);
+                message_start = self.accumulated.len();
+                write_line_markers(
+                    &mut self.accumulated,
+                    labels.lane,
+                    thread,
+                    &self.pushed_context,
+                );
+                write_message(&mut self.accumulated, message, self.max_message_length);
+                let _ = self.accumulated.write_char('\n');
+            }
+            let record = Record {
+                start: record_start,
+                message_start,
+                level,
+                labels,
+                attributes,
+                scope: false,
+                opens: None,
+                indentation: current_indentation,
+                elapsed,
+                since_epoch,
+                cpu: self.thread_cpu_micros(),
+            };
+            if let Some(lane) = labels.lane {
+                if !self.lanes.contains(&lane) {
+                    self.lanes.push(lane);
+                }
+            }
+            if self.deduplicate
+                && self.collapse_repeat(message_start, level, labels, current_indentation, elapsed)
+            {
+                return;
+            }
+            self.observe(&record);
+            self.records.push(record);
+            #[cfg(feature = "std")]
+            if let Some(registration) = &self.registration {
+                registration.set_lines(self.records.len());
+            }
+            self.check_watermark();
+        } else {
+            self.suppressed.below_level += 1;
+        }
+    }
+
+    /// Record a warning the first time the trace grows past the watermark.
+    fn check_watermark(&mut self) {
+        let Some(watermark) = self.watermark else {
+            return;
+        };
+        if self.watermark_crossed || self.accumulated.len() < watermark {
+            return;
+        }
+        self.watermark_crossed = true;
+        if let Some(level) = self.effective_level().to_level() {
+            self.log(
+                log::Level::Warn.min(level),
+                format_args!(
+                    "trace passed {watermark} bytes, at {} line{}",
+                    self.records.len(),
+                    plural(self.records.len() as u64)
+                ),
+            );
+        }
+    }
+
+    /// Show the line just written for `record` to the line callback and
+    /// subscribers.
+    fn observe(&mut self, record: &Record) {
+        #[cfg(feature = "std")]
+        let subscribed = !self.subscribers.is_empty();
+        #[cfg(not(feature = "std"))]
+        let subscribed = false;
+        if self.on_line.is_none() && !subscribed {
+            return;
+        }
+        let line = RecordedLine {
+            level: record.level,
+            elapsed: record.elapsed.map(Duration::from_micros),
+            since_epoch: record.since_epoch,
+            indent: record.indentation,
+            message: &self.accumulated[record.message_start..self.accumulated.len() - 1],
+            target: record.labels.target,
+            lane: record.labels.lane,
+            attributes: self.attributes.attributes(record.attributes.clone()),
+        };
+        if let Some(on_line) = &mut self.on_line {
+            on_line(&line);
+        }
+        #[cfg(feature = "std")]
+        {
+            self.subscribers
+                .retain(|subscriber| !subscriber.is_abandoned());
+            for subscriber in &self.subscribers {
+                subscriber.publish(&line);
+            }
+        }
+    }
+
+    /// Record a boilerplate line, filling in its template. Like scopes, these
+    /// bypass log level.
+    fn log_template(&mut self, template: &str) {
+        if let Some(level) = self.effective_level().to_level() {
+            let line = Template {
+                template,
+                elapsed: self.elapsed_micros(),
+                lines: self.records.len(),
+            };
+            self.log(level, format_args!("{line}"));
+        }
+    }
+
+    /// Write the start of a line, up to its message, in one formatting call.
+    fn write_prefix(
+        &mut self,
+        elapsed: Option<u64>,
+        level: log::Level,
+        target: Option<&'static str>,
+        indentation: usize,
+    ) {
+        let format = match (self.line_format, target) {
+            #[cfg(feature = "std")]
+            (LineFormat::EnvLogger { .. }, Some(target)) => LineFormat::EnvLogger { target },
+            (format, _) => format,
+        };
+        let prefix = LinePrefix {
+            format,
+            formatter: self.line_formatter.as_deref(),
+            target,
+            elapsed,
+            elapsed_format: self.elapsed_format,
+            elapsed_width: self.elapsed_width,
+            level,
+            ansi: self.ansi,
+            severity_markers: self.severity_markers,
+            indentation,
+        };
+        let _ = self.accumulated.write_fmt(format_args!("{prefix}"));
+    }
+
+    /// The recording thread's CPU time, if it is recorded for scopes.
+    fn thread_cpu_micros(&self) -> Option<u64> {
+        #[cfg(all(feature = "cpu-time", unix))]
+        if self.cpu_time {
+            return crate::process::thread_cpu_micros();
+        }
+        None
+    }
+
+    /// The wall clock time now, if lines are stamped with it.
+    fn since_epoch(&self) -> Option<Duration> {
+        #[cfg(feature = "std")]
+        if self.wall_clock {
+            return Some(
+                std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .unwrap_or_default(),
+            );
+        }
+        None
+    }
+
+    /// Write the wall clock time the trace started at, `elapsed` before now.
+    #[cfg(feature = "std")]
+    fn write_start_time_header(&mut self, elapsed: Option<u64>) {
+        let since_epoch = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .saturating_sub(Duration::from_micros(elapsed.unwrap_or_default()));
+        self.write_prefix(self.clock.map(|_| 0), log::Level::Info, None, 0);
+        self.accumulated.push_str("trace started ");
+        let _ = clock::write_rfc3339(&mut self.accumulated, since_epoch);
+        self.accumulated.push('\n');
+    }
+
+    /// Write the lines of one record, each indented, returning where the
+    /// message starts.
+    fn write_lines<'a>(
+        &mut self,
+        elapsed: Option<u64>,
+        level: log::Level,
+        target: Option<&'static str>,
+        indentation: usize,
+        mut lines: impl Iterator<Item = &'a str>,
+    ) -> usize {
+        let mut message_start = self.accumulated.len();
+        let mut padding = 0;
+        if let Some(first_line) = lines.next() {
+            let prefix_start = self.accumulated.len();
+            self.write_prefix(elapsed, level, target, indentation);
+            if self.continuation_lines == ContinuationLines::Aligned {
+                padding = visible_width(&self.accumulated[prefix_start..]) - 2 * indentation;
+            }
+            message_start = self.accumulated.len();
+            self.accumulated.push_str(first_line);
+            self.accumulated.push('\n');
+        }
+        for line in lines {
+            if self.continuation_lines == ContinuationLines::Prefixed {
+                self.write_prefix(elapsed, level, target, indentation);
+                self.accumulated.push_str(parse::CONTINUATION_MARKER);
+            } else {
+                let _ = self.accumulated.write_fmt(format_args!(
+                    "{:width$}",
+                    "",
+                    width = padding + 2 * indentation
+                ));
+            }
+            self.accumulated.push_str(line);
+            self.accumulated.push('\n');
+        }
+        message_start
+    }
+
+    /// Add the lines of another detailer, indented under this detailer's
+    /// current scope.
+    ///
+    /// When both detailers record timings, the other detailer's timings are
+    /// moved onto this detailer's timeline. That assumes both use the same
+    /// clock. Use this when a library hands
+    /// back a detailer of its own.
+    ///
+    /// ```
+    /// use detailer::{detail, new_detailer, scope};
+    ///
+    /// let mut library_detailer = new_detailer!(Info, WithoutTiming);
+    /// detail!(library_detailer, "cache miss");
+    ///
+    /// let mut detailer = new_detailer!(Info, WithoutTiming);
+    /// {
+    ///     let _scope = scope!(detailer, "calling library");
+    ///     detailer.append(library_detailer);
+    /// }
+    /// detail!(detailer, "done");
+    /// assert_eq!("calling library\n  cache miss\ndone\n", detailer.peek());
+    /// ```
+    ///
+    /// The other detailer's lines are recorded the way this detailer's own
+    /// are, so lines below this detailer's level are left out, and counted
+    /// as [suppressed](Self::report_suppressed):
+    ///
+    /// ```
+    /// use detailer::{detail, detail_at, new_detailer};
+    ///
+    /// let mut library_detailer = new_detailer!(Debug, WithoutTiming);
+    /// detail_at!(library_detailer, Debug, "opened connection");
+    /// detail!(library_detailer, "cache miss");
+    ///
+    /// let mut detailer = new_detailer!(Info, WithoutTiming);
+    /// detailer.report_suppressed(true);
+    /// detailer.append(library_detailer);
+    /// detailer.on_flush(|trace| {
+    ///     assert_eq!("cache miss\n(+1 line suppressed below INFO)", trace.text)
+    /// });
+    /// detailer.flush();
+    /// ```
+    ///
+    /// The other detailer's [throttled lines](Self::log_throttled) and
+    /// [resumable scopes](Self::resume_scope) are counted with this
+    /// detailer's, and reported when this detailer flushes:
+    ///
+    /// ```
+    /// use detailer::new_detailer;
+    ///
+    /// let mut library_detailer = new_detailer!(Info, WithoutTiming);
+    /// for _ in 0..3 {
+    ///     library_detailer.log_throttled(log::Level::Info, 10, format_args!("poll tick"));
+    /// }
+    ///
+    /// let mut detailer = new_detailer!(Info, WithoutTiming);
+    /// detailer.on_flush(|trace| {
+    ///     assert_eq!("poll tick\nsuppressed: poll tick ×2", trace.text)
+    /// });
+    /// detailer.append(library_detailer);
+    /// detailer.flush();
+    /// ```
+    pub fn append(&mut self, other: Detailer) {
+        let current_indentation = self
+            .current_indentation
+            .load(core::sync::atomic::Ordering::Relaxed);
+        self.append_at(current_indentation, None, other)
+    }
+
+    /// Add the lines of another detailer under `scope`, like
+    /// [`append()`](Self::append).
+    ///
+    /// ```
+    /// use detailer::{detail, new_detailer};
+    ///
+    /// let mut worker = new_detailer!(Info, WithoutTiming);
+    /// detail!(worker, "compressed");
+    ///
+    /// let mut detailer = new_detailer!(Info, WithoutTiming);
+    /// let background = detailer.enter_scope(format_args!("background"));
+    /// detail!(detailer, "serving");
+    /// detailer.append_in(background, worker);
+    /// assert_eq!("background\nserving\n  compressed\n", detailer.peek());
+    /// ```
+    pub fn append_in(&mut self, scope: ScopeId, other: Detailer) {
+        self.append_at(scope.depth + 1, Some(scope.id), other)
+    }
+
+    fn append_at(&mut self, current_indentation: usize, scope: Option<u64>, mut other: Detailer) {
+        // Messages are taken back out of other's layout before its text is.
+        let events: Vec<parse::Event> = (0..other.records.len())
+            .map(|index| other.recorded_event(index))
+            .collect();
+        // Taking the contents keeps `other` from flushing them when it drops.
+        other.accumulated.clear();
+        let records = core::mem::take(&mut other.records);
+        let attributes = core::mem::replace(&mut other.attributes, AttributeValues::new());
+        // Other's lines are shifted by the difference between the two starts.
+        // Lines that other recorded without timing are stamped with now.
+        let now = self.elapsed_micros();
+        let shift = match (self.clock, other.clock) {
+            (Some(_), Some(_)) => Some((
+                other.start.saturating_sub(self.start),
+                self.start.saturating_sub(other.start),
+            )),
+            _ => None,
+        };
+        let rebase = |elapsed: Option<u64>| match (shift, elapsed) {
+            (Some((ahead, behind)), Some(elapsed)) => {
+                Some((elapsed + ahead).saturating_sub(behind))
+            }
+            _ => now,
+        };
+        for (index, record) in records.iter().enumerate() {
+            // Scopes are shown whatever their level, the way they are when
+            // they are opened here.
+            let enabled = match record.scope {
+                true => self.effective_level() != log::LevelFilter::Off,
+                false => record.level <= self.effective_level(),
+            };
+            if !enabled {
+                if !record.scope {
+                    self.suppressed.below_level += 1;
+                }
+                continue;
+            }
+            if self.soft_limit <= self.accumulated.len() {
+                log::warn!("truncated");
+                self.suppressed.over_limit += records.len() - index;
+                break;
+            }
+            if self.accumulated.is_empty() {
+                if let Some(start_line) = self.start_line.take() {
+                    self.log_template(&start_line);
+                    self.start_line = Some(start_line);
+                }
+            }
+            let lines = events[index].message.split('\n');
+            let elapsed = rebase(record.elapsed);
+            let indentation = current_indentation + record.indentation;
+            let record_start = self.accumulated.len();
+            let message_start = self.write_lines(
+                elapsed,
+                record.level,
+                record.labels.target,
+                indentation,
+                lines,
+            );
+            let attributes_start = self.attributes.len();
+            for (key, value) in attributes.iter(record.attributes.clone()) {
+                self.attributes.push_str(key, value);
+            }
+            let record = Record {
+                start: record_start,
+                message_start,
+                level: record.level,
+                labels: Labels {
+                    scope: record.labels.scope.or(scope),
+                    ..record.labels
+                },
+                attributes: attributes_start..self.attributes.len(),
+                scope: record.scope,
+                opens: record.opens,
+                indentation,
+                elapsed,
+                since_epoch: record.since_epoch,
+                cpu: record.cpu,
+            };
+            if let Some(lane) = record.labels.lane {
+                if !self.lanes.contains(&lane) {
+                    self.lanes.push(lane);
+                }
+            }
+            if self.deduplicate
+                && self.collapse_repeat(
+                    message_start,
+                    record.level,
+                    record.labels,
+                    indentation,
+                    elapsed,
+                )
+            {
+                continue;
+            }
+            self.observe(&record);
+            self.records.push(record);
+            #[cfg(feature = "std")]
+            if let Some(registration) = &self.registration {
+                registration.set_lines(self.records.len());
+            }
+            self.check_watermark();
+        }
+        // Other's totals are reported with this detailer's, and other is left
+        // with nothing to flush.
+        for throttle in core::mem::take(&mut other.throttles) {
+            match self
+                .throttles
+                .iter_mut()
+                .find(|known| known.site == throttle.site)
+            {
+                Some(known) => {
+                    known.suppressed += throttle.suppressed;
+                    known.total += throttle.total;
+                }
+                None => self.throttles.push(throttle),
+            }
+        }
+        for scope in core::mem::take(&mut other.resumable_scopes) {
+            match self
+                .resumable_scopes
+                .iter_mut()
+                .find(|known| known.name == scope.name)
+            {
+                Some(known) => {
+                    known.entries += scope.entries;
+                    known.micros.fetch_add(
+                        scope.micros.load(core::sync::atomic::Ordering::Relaxed),
+                        core::sync::atomic::Ordering::Relaxed,
+                    );
+                }
+                None => self.resumable_scopes.push(scope),
+            }
+        }
+        other.flush_on_drop = false;
+    }
+
+    /// log a line, if the level is enabled, but only once every `every` times
+    /// it is called from the same place in the code.
+    ///
+    /// The first call is always recorded. Each recorded line after that notes
+    /// how many calls were suppressed since the previous one, and the flushed
+    /// trace ends with how many were suppressed in all at each place, by the
+    /// first line recorded there.
+    ///
+    /// ```
+    /// use detailer::{Detailer, new_detailer};
+    ///
+    /// let mut detailer = new_detailer!();
+    ///
+    /// for n in 0..1000 {
+    ///     detailer.log_throttled(log::Level::Info, 100, format_args!("tick {n}"));
+    /// }
+    /// ```
+    ///
+    /// ```
+    /// use detailer::new_detailer;
+    ///
+    /// let mut detailer = new_detailer!(Info, WithoutTiming);
+    /// detailer.on_flush(|trace| {
+    ///     assert!(trace.text.ends_with("suppressed: poll tick ×4, lease renew ×1"))
+    /// });
+    /// for _ in 0..5 {
+    ///     detailer.log_throttled(log::Level::Info, 10, format_args!("poll tick"));
+    /// }
+    /// for _ in 0..2 {
+    ///     detailer.log_throttled(log::Level::Info, 10, format_args!("lease renew"));
+    /// }
+    /// detailer.flush();
+    /// ```
+    ///
+    /// The totals come before the [end line](Self::end_line):
+    ///
+    /// ```
+    /// use detailer::new_detailer;
+    ///
+    /// let mut detailer = new_detailer!(Info, WithoutTiming);
+    /// detailer.end_line(Some("request complete"));
+    /// detailer.on_flush(|trace| {
+    ///     assert!(trace.text.ends_with("suppressed: poll tick ×1\nrequest complete"))
+    /// });
+    /// for _ in 0..2 {
+    ///     detailer.log_throttled(log::Level::Info, 10, format_args!("poll tick"));
+    /// }
+    /// detailer.flush();
+    /// ```
+    #[track_caller]
+    pub fn log_throttled(&mut self, level: log::Level, every: u64, message: Arguments) {
+        if self.effective_level() < level {
+            self.suppressed.below_level += 1;
+            return;
+        }
+        let site = Location::caller();
+        let index = match self
+            .throttles
+            .iter()
+            .position(|throttle| throttle.site == site)
+        {
+            Some(index) => index,
+            None => {
+                self.throttles.push(Throttle {
+                    site,
+                    label: message.to_string(),
+                    skip: 0,
+                    suppressed: 0,
+                    total: 0,
+                });
+                self.throttles.len() - 1
+            }
+        };
+        let throttle = &mut self.throttles[index];
+        if 0 < throttle.skip {
+            throttle.skip -= 1;
+            throttle.suppressed += 1;
+            throttle.total += 1;
+            return;
+        }
+        throttle.skip = every.saturating_sub(1);
+        let suppressed = core::mem::take(&mut throttle.suppressed);
+        if 0 < suppressed {
+            self.log(
+                level,
+                format_args!(
+                    "{message} (suppressed {suppressed} similar line{})",
+                    plural(suppressed)
+                ),
+            );
+        } else {
+            self.log(level, message);
+        }
+    }
+
+    /// log a line, if the level is enabled, but only the first time it is
+    /// called from the same place in the code. This holds for the lifetime of
+    /// the detailer, across flushes.
+    ///
+    /// ```
+    /// use detailer::new_detailer;
+    ///
+    /// let mut detailer = new_detailer!(Info, WithoutTiming);
+    ///
+    /// for n in 0..3 {
+    ///     detailer.log_once(log::Level::Info, format_args!("fell back at item {n}"));
+    /// }
+    /// assert_eq!("fell back at item 0\n", detailer.peek());
+    /// ```
+    ///
+    /// Lines below the level count as [suppressed](Self::report_suppressed),
+    /// like any other:
+    ///
+    /// ```
+    /// use detailer::{detail, new_detailer};
+    ///
+    /// let mut detailer = new_detailer!(Info, WithoutTiming);
+    /// detailer.report_suppressed(true);
+    /// detailer.on_flush(|trace| {
+    ///     assert_eq!("polling\n(+2 lines suppressed below INFO)", trace.text)
+    /// });
+    /// detail!(detailer, "polling");
+    /// detailer.log_once(log::Level::Debug, format_args!("fell back"));
+    /// detailer.log_throttled(log::Level::Debug, 10, format_args!("tick"));
+    /// detailer.flush();
+    /// ```
+    #[track_caller]
+    pub fn log_once(&mut self, level: log::Level, message: Arguments) {
+        if self.effective_level() < level {
+            self.suppressed.below_level += 1;
+            return;
+        }
+        let site = Location::caller();
+        if self.logged_once.contains(&site) {
+            return;
+        }
+        self.logged_once.push(site);
+        self.log(level, message);
+    }
+
+    /// Fold the line that was just written into the line before it, if it
+    /// repeats that line's message at the same level, with the same target,
+    /// lane and tags. Returns whether it was folded.
+    fn collapse_repeat(
+        &mut self,
+        message_start: usize,
+        level: log::Level,
+        labels: Labels,
+        indentation: usize,
+        elapsed: Option<u64>,
+    ) -> bool {
+        let message = message_start..self.accumulated.len() - 1;
+        if let Some(repeat) = &mut self.last_line {
+            if repeat.indentation == indentation
+                && repeat.level == level
+                && repeat.labels == labels
+                && self.accumulated[repeat.message.clone()] == self.accumulated[message.clone()]
+            {
+                repeat.count += 1;
+                repeat.last = elapsed;
+                self.accumulated.truncate(repeat.message.end);
+                let _ = self
+                    .accumulated
+                    .write_fmt(format_args!(" ×{}", repeat.count));
+                if let (Some(first), Some(last)) = (repeat.first, repeat.last) {
+                    let _ = self
+                        .accumulated
+                        .write_fmt(format_args!(" (first at {first}µs, last at {last}µs)"));
+                }
+                let _ = self.accumulated.write_char('\n');
+                return true;
+            }
+        }
+        self.last_line = Some(RepeatedLine {
+            message,
+            level,
+            labels,
+            indentation,
+            count: 1,
+            first: elapsed,
+            last: elapsed,
+        });
+        false
+    }
+
+    /// log a line
+    ///
+    /// ```
+    /// use detailer::{Detailer, new_detailer};
+    ///
+    /// let mut detailer = new_detailer!();
+    ///
+    /// detailer.info(format_args!("yikes {}", 42));
+    /// ```
+    pub fn info(&mut self, message: Arguments) {
+        self.log(log::Level::Info, message)
+    }
+
+    /// log a line
+    ///
+    /// ```
+    /// use detailer::{Detailer, new_detailer};
+    ///
+    /// let mut detailer = new_detailer!();
+    ///
+    /// detailer.trace(format_args!("yikes {}", 42));
+    /// ```
+    pub fn trace(&mut self, message: Arguments) {
+        self.log(log::Level::Trace, message)
+    }
+
+    /// log a line
+    ///
+    /// ```
+    /// use detailer::{Detailer, new_detailer};
+    ///
+    /// let mut detailer = new_detailer!();
+    ///
+    /// detailer.debug(format_args!("yikes {}", 42));
+    /// ```
+    pub fn debug(&mut self, message: Arguments) {
+        self.log(log::Level::Debug, message)
+    }
+
+    /// log a line
+    ///
+    /// ```
+    /// use detailer::{Detailer, new_detailer};
+    /// use log::{Level, LevelFilter};
+    ///
+    /// let mut detailer = new_detailer!();
+    ///
+    /// detailer.warn(format_args!("yikes {}", 42));
+    /// ```
+    pub fn warn(&mut self, message: Arguments) {
+        self.log(log::Level::Warn, message)
+    }
+
+    /// log a line
+    ///
+    /// ```
+    /// use detailer::{Detailer, new_detailer};
+    /// use log::{Level, LevelFilter};
+    ///
+    /// let mut detailer = new_detailer!();
+    ///
+    /// detailer.error(format_args!("yikes {}", 42));
+    /// ```
+    pub fn error(&mut self, message: Arguments) {
+        self.log(log::Level::Error, message)
+    }
+
+    /// Override the level for this detailer
+    ///
+    /// ```
+    /// use detailer::{Detailer, new_detailer};
+    /// use log::{Level, LevelFilter};
+    ///
+    /// let mut detailer = new_detailer!();
+    ///
+    /// detailer.debug(format_args!("{} logged", "not"));
+    /// detailer.level(log::LevelFilter::Debug);
+    /// detailer.debug(format_args!("{} logged", "is"));
+    /// ```
+    pub fn level(&mut self, level: log::LevelFilter) {
+        self.level = level;
+    }
+
+    /// List this detailer in the global [`registry`](crate::registry) under
+    /// `name`, until it is dropped.
+    ///
+    /// ```
+    /// use detailer::{new_detailer, registry};
+    ///
+    /// let mut detailer = new_detailer!();
+    /// detailer.register("request 42");
+    ///
+    /// assert!(registry::live().iter().any(|live| live.name() == "request 42"));
+    /// drop(detailer);
+    /// assert!(!registry::live().iter().any(|live| live.name() == "request 42"));
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn register(&mut self, name: impl Into<String>) {
+        let registration = Registration::register(name.into());
+        registration.set_lines(self.records.len());
+        self.registration = Some(registration);
+    }
+
+    /// Identify this detailer's trace, for sinks that record trace IDs.
+    ///
+    /// ```
+    /// use detailer::new_detailer;
+    ///
+    /// let mut detailer = new_detailer!();
+    /// detailer.trace_id("4bf92f3577b34da6");
+    /// ```
+    pub fn trace_id(&mut self, trace_id: impl Into<String>) {
+        self.trace_id = Some(trace_id.into());
+    }
+
+    /// Attach `key=value` to every trace this detailer flushes, replacing any
+    /// earlier value for `key`. The context is written on a header line, and
+    /// sinks with structured output record it as fields, so each part of a
+    /// partially flushed trace can be found by it.
+    ///
+    /// ```
+    /// use detailer::{detail, new_detailer};
+    ///
+    /// let mut detailer = new_detailer!(Info, WithoutTiming);
+    /// detailer.set_context("request_id", "8d2e");
+    /// detailer.set_context("shard", 7);
+    /// detailer.on_flush(|trace| assert_eq!("request_id=8d2e shard=7\nbatch 1", trace.text));
+    ///
+    /// detail!(detailer, "batch 1");
+    /// detailer.flush_partial();
+    /// ```
+    pub fn set_context(&mut self, key: &'static str, value: impl Display) {
+        let value = value.to_string();
+        match self
+            .context
+            .iter_mut()
+            .find(|(existing, _)| *existing == key)
+        {
+            Some((_, existing)) => *existing = value,
+            None => self.context.push((key, value)),
+        }
+    }
+
+    /// Attach the process ID as `pid`, the current thread's name or ID as
+    /// `thread` and the host name as `host`, when it can be found, to every
+    /// trace this detailer flushes, so traces from many replicas in one
+    /// stream can be told apart. Like any other [context](Self::set_context),
+    /// they are written on a header line and recorded as fields by sinks
+    /// with structured output.
+    ///
+    /// ```
+    /// use detailer::{detail, new_detailer};
+    ///
+    /// let mut detailer = new_detailer!(Info, WithoutTiming);
+    /// detailer.process_context();
+    /// detailer.on_flush(|trace| {
+    ///     let header = format!("pid={} thread=main", std::process::id());
+    ///     assert!(trace.text.starts_with(&header));
+    /// });
+    /// detail!(detailer, "started");
+    /// detailer.flush();
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn process_context(&mut self) {
+        self.set_context("pid", std::process::id());
+        self.set_context("thread", crate::process::thread_label());
+        if let Some(hostname) = crate::process::hostname() {
+            self.set_context("host", hostname);
+        }
+    }
+
+    /// When flushing, end each scope's opening line with how long the scope
+    /// took and how much CPU time the recording thread used in it, like
+    /// `query 813µs wall, 12µs cpu`, to tell time spent waiting from time
+    /// spent working. A scope ends when the next line outside it is
+    /// recorded, or when the trace is flushed.
+    ///
+    /// The CPU time is the thread's own, so scopes of a detailer shared
+    /// between threads only count the CPU time of the thread that recorded
+    /// their lines.
+    ///
+    /// ```
+    /// use detailer::{detail, new_detailer, scope};
+    ///
+    /// let mut detailer = new_detailer!(Info, WithTiming);
+    /// detailer.cpu_time(true);
+    /// detailer.on_flush(|trace| {
+    ///     let (scope, _) = trace.text.split_once('\n').unwrap();
+    ///     assert!(scope.contains(" hashing "));
+    ///     assert!(scope.ends_with("µs cpu"));
+    /// });
+    /// {
+    ///     let _scope = scope!(detailer, "hashing");
+    ///     let checksum = (0..100_000u64).fold(0, |sum: u64, n| sum.wrapping_mul(31).wrapping_add(n));
+    ///     detail!(detailer, "checksum {checksum}");
+    /// }
+    /// detailer.flush();
+    /// ```
+    #[cfg(all(feature = "cpu-time", unix))]
+    pub fn cpu_time(&mut self, enabled: bool) {
+        self.cpu_time = enabled;
+    }
+
+    /// Mark each line with the name of the thread that recorded it, or its ID
+    /// when it has no name, like `[thread=worker-1] `, so the lines worker
+    /// threads add to a shared [`SyncDetailer`](crate::SyncDetailer) can be
+    /// told apart.
+    ///
+    /// ```
+    /// use detailer::{detail, new_detailer, SyncDetailer};
+    ///
+    /// let mut detailer = new_detailer!(Info, WithoutTiming);
+    /// detailer.thread_labels(true);
+    /// let detailer = SyncDetailer::new(detailer);
+    /// detail!(detailer, "dispatching");
+    /// std::thread::Builder::new()
+    ///     .name("worker-1".to_string())
+    ///     .spawn({
+    ///         let detailer = detailer.clone();
+    ///         move || detail!(detailer, "resized image")
+    ///     })
+    ///     .unwrap()
+    ///     .join()
+    ///     .unwrap();
+    /// assert_eq!(
+    ///     "[thread=main] dispatching\n[thread=worker-1] resized image\n",
+    ///     detailer.lock().peek(),
+    /// );
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn thread_labels(&mut self, enabled: bool) {
+        self.thread_labels = enabled;
+    }
+
+    /// Mark the lines recorded while the guard exists with `key=value`, like
+    /// the tenant a request is being handled for. Contexts pushed inside each
+    /// other all mark the line, outermost first.
+    ///
+    /// ```
+    /// use detailer::{detail, new_detailer, scope};
+    ///
+    /// let mut detailer = new_detailer!(Info, WithoutTiming);
+    /// detail!(detailer, "accepted");
+    /// {
+    ///     let _tenant = detailer.push_context("tenant", "acme");
+    ///     let _scope = scope!(detailer, "billing");
+    ///     detail!(detailer, "charged");
+    /// }
+    /// detail!(detailer, "responded");
+    /// assert_eq!(
+    ///     "accepted\n[tenant=acme] billing\n  [tenant=acme] charged\nresponded\n",
+    ///     detailer.peek(),
+    /// );
+    /// ```
+    pub fn push_context(&mut self, key: &'static str, value: impl Display) -> ContextGuard {
+        let depth = self
+            .pushed_depth
+            .load(core::sync::atomic::Ordering::Relaxed);
+        self.pushed_context.truncate(depth);
+        self.pushed_context.push((key, value.to_string()));
+        self.pushed_depth
+            .store(depth + 1, core::sync::atomic::Ordering::Relaxed);
+        ContextGuard {
+            depth: self.pushed_depth.clone(),
+            enclosing: depth,
+        }
+    }
+
+    /// Send flushed traces to `sink` instead of the `log` crate.
+    pub fn sink(&mut self, sink: Arc<dyn Sink>) {
+        self.sink = Some(sink);
+    }
+
+    /// Flush only the lines tagged with any of `tags`, or every line. The
+    /// lines are all still recorded, so the filter can change right up until
+    /// the trace is flushed.
+    ///
+    /// ```
+    /// use detailer::{detail, detail_tagged, new_detailer};
+    ///
+    /// let mut detailer = new_detailer!(Info, WithoutTiming);
+    /// detailer.tag_filter(Some(&["db"]));
+    /// detailer.on_flush(|trace| assert_eq!("query\nretrying query", trace.text));
+    ///
+    /// detail!(detailer, "authenticating");
+    /// detail_tagged!(detailer, ["db"], "query");
+    /// detail_tagged!(detailer, ["db", "retry"], "retrying query");
+    /// detail_tagged!(detailer, ["cache"], "cache miss");
+    /// detailer.flush();
+    /// ```
+    pub fn tag_filter(&mut self, tags: Option<&[&str]>) {
+        self.tag_filter = tags.map(|tags| tags.iter().map(|tag| String::from(*tag)).collect());
+    }
+
+    /// Choose how the lines of [lanes](Self::lane) are flushed. By default they
+    /// are interleaved in the order they were recorded.
+    ///
+    /// ```
+    /// use detailer::{detail, new_detailer, LaneLayout};
+    ///
+    /// let mut detailer = new_detailer!(Info, WithoutTiming);
+    /// detailer.lane_layout(LaneLayout::SideBySide);
+    /// detailer.on_flush(|trace| {
+    ///     assert_eq!(
+    ///         concat!(
+    ///             "        │ prefetch    │ compute\n",
+    ///             "started │             │\n",
+    ///             "        │ fetched 1   │\n",
+    ///             "        │             │ computed 1\n",
+    ///             "        │ fetched 2   │\n",
+    ///             "        │ cache ready │\n",
+    ///             "        │             │ computed 2",
+    ///         ),
+    ///         trace.text,
+    ///     )
+    /// });
+    /// detail!(detailer, "started");
+    /// detail!(detailer.lane("prefetch"), "fetched 1");
+    /// detail!(detailer.lane("compute"), "computed 1");
+    /// detail!(detailer.lane("prefetch"), "fetched 2");
+    /// detail!(detailer.lane("prefetch"), "cache ready");
+    /// detail!(detailer.lane("compute"), "computed 2");
+    /// detailer.flush();
+    /// ```
+    pub fn lane_layout(&mut self, layout: LaneLayout) {
+        self.lane_layout = layout;
+    }
+
+    /// Flush only the lines [logged for](Self::log_target) any of `targets` or
+    /// the modules inside them, or every line. Like
+    /// [`tag_filter()`](Self::tag_filter), this can change right up until the
+    /// trace is flushed.
+    ///
+    /// ```
+    /// use detailer::{detail, detail_target, new_detailer};
+    ///
+    /// let mut detailer = new_detailer!(Info, WithoutTiming);
+    /// detailer.target_filter(Some(&["my_app::db"]));
+    /// detailer.on_flush(|trace| assert_eq!("query\nchecked out connection", trace.text));
+    ///
+    /// detail!(detailer, "authenticating");
+    /// detail_target!(detailer, "my_app::db", "query");
+    /// detail_target!(detailer, "my_app::db::pool", "checked out connection");
+    /// detail_target!(detailer, "my_app::dbx", "unrelated");
+    /// detailer.flush();
+    /// ```
+    pub fn target_filter(&mut self, targets: Option<&[&str]>) {
+        self.target_filter =
+            targets.map(|targets| targets.iter().map(|target| String::from(*target)).collect());
+    }
+
+    /// When flushing, replace the contents of each scope that recorded only
+    /// lines less severe than `level` with a count of them and how long they
+    /// took, like `authenticating … 14 lines elided, 22µs`. The timing of the
+    /// scope stays in the trace without the noise of its lines.
+    ///
+    /// ```
+    /// use detailer::{detail, detail_at, new_detailer, scope};
+    ///
+    /// let mut detailer = new_detailer!(Debug, WithoutTiming);
+    /// detailer.collapse_scopes_below(Some(log::Level::Info));
+    /// detailer.on_flush(|trace| {
+    ///     assert_eq!("authenticating … 2 lines elided\nquery\n  query took 3ms", trace.text)
+    /// });
+    /// {
+    ///     let _scope = scope!(detailer, "authenticating");
+    ///     detail_at!(detailer, Debug, "header parsed");
+    ///     detail_at!(detailer, Debug, "token valid");
+    /// }
+    /// {
+    ///     let _scope = scope!(detailer, "query");
+    ///     detail!(detailer, "query took 3ms");
+    /// }
+    /// detailer.flush();
+    /// ```
+    pub fn collapse_scopes_below(&mut self, level: Option<log::Level>) {
+        self.collapse_below = level;
+    }
+
+    /// When flushing, leave out the opening lines of scopes that recorded no
+    /// lines, like scopes whose lines were all below the detailer's level.
+    ///
+    /// ```
+    /// use detailer::{detail, detail_at, new_detailer, scope};
+    ///
+    /// let mut detailer = new_detailer!(Info, WithoutTiming);
+    /// detailer.prune_empty_scopes(true);
+    /// detailer.on_flush(|trace| assert_eq!("retrying\n  backed off 20ms", trace.text));
+    /// {
+    ///     let _scope = scope!(detailer, "checking cache");
+    ///     detail_at!(detailer, Debug, "cache hit");
+    /// }
+    /// {
+    ///     let _scope = scope!(detailer, "retrying");
+    ///     detail!(detailer, "backed off 20ms");
+    /// }
+    /// detailer.flush();
+    /// ```
+    pub fn prune_empty_scopes(&mut self, enabled: bool) {
+        self.prune_empty_scopes = enabled;
+    }
+
+    /// When flushing, write only the opening lines of scopes, each with how
+    /// long the scope took and how many lines it recorded, for a compact
+    /// profile of each trace. Every line is still recorded, so this can be
+    /// turned off at any time to see them in full.
+    ///
+    /// ```
+    /// use std::sync::atomic::{AtomicU64, Ordering};
+    ///
+    /// use detailer::{detail, scope, Detailer, TimingSetting};
+    ///
+    /// static NOW: AtomicU64 = AtomicU64::new(0);
+    /// fn now() -> u64 {
+    ///     NOW.fetch_add(10, Ordering::Relaxed)
+    /// }
+    ///
+    /// let mut detailer = Detailer::new(log::LevelFilter::Info, TimingSetting::WithClock(now), 4096);
+    /// detailer.summary_only(true);
+    /// detailer.on_flush(|trace| {
+    ///     assert_eq!("10     authenticating 20µs, 1 line\n30     query 20µs, 1 line", trace.text)
+    /// });
+    /// {
+    ///     let _scope = scope!(detailer, "authenticating");
+    ///     detail!(detailer, "token valid");
+    /// }
+    /// {
+    ///     let _scope = scope!(detailer, "query");
+    ///     detail!(detailer, "12 rows");
+    /// }
+    /// detailer.flush();
+    /// ```
+    pub fn summary_only(&mut self, enabled: bool) {
+        self.summary_only = enabled;
+    }
+
+    /// End each flushed trace with a bar for each top-level scope, as long as
+    /// the scope took relative to the others, for an at-a-glance profile. The
+    /// longest bar is `width` characters. Only detailers that record timing
+    /// have bars.
+    ///
+    /// ```
+    /// use std::sync::atomic::{AtomicU64, Ordering};
+    ///
+    /// use detailer::{detail, scope, Detailer, TimingSetting};
+    ///
+    /// static NOW: AtomicU64 = AtomicU64::new(0);
+    /// fn now() -> u64 {
+    ///     NOW.fetch_add(10, Ordering::Relaxed)
+    /// }
+    ///
+    /// let mut detailer = Detailer::new(log::LevelFilter::Info, TimingSetting::WithClock(now), 4096);
+    /// detailer.scope_bars(Some(8));
+    /// detailer.on_flush(|trace| {
+    ///     assert!(trace.text.ends_with(concat!(
+    ///         "\nauthenticating ▏██ 20µs",
+    ///         "\nquery          ▏████████ 80µs",
+    ///     )))
+    /// });
+    /// {
+    ///     let _scope = scope!(detailer, "authenticating");
+    ///     detail!(detailer, "token valid");
+    /// }
+    /// {
+    ///     let _scope = scope!(detailer, "query");
+    ///     for page in 1..=7 {
+    ///         detail!(detailer, "page {page}");
+    ///     }
+    /// }
+    /// detailer.flush();
+    /// ```
+    pub fn scope_bars(&mut self, width: Option<usize>) {
+        self.scope_bars = width;
+    }
+
+    /// End each flushed trace with a sparkline of the time between each line
+    /// and the next, like `gaps ▁▁▁█▁▁ longest 813µs`, so the shape of a long
+    /// trace, steady or one big stall, shows at a glance. The sparkline is at
+    /// most `width` characters. Only detailers that record timing have one.
+    ///
+    /// ```
+    /// use std::sync::atomic::{AtomicU64, Ordering};
+    ///
+    /// use detailer::{detail, Detailer, TimingSetting};
+    ///
+    /// static NOW: AtomicU64 = AtomicU64::new(0);
+    /// fn now() -> u64 {
+    ///     NOW.fetch_add(10, Ordering::Relaxed)
+    /// }
+    ///
+    /// let mut detailer = Detailer::new(log::LevelFilter::Info, TimingSetting::WithClock(now), 4096);
+    /// detailer.sparkline(Some(40));
+    /// detailer.on_flush(|trace| assert!(trace.text.ends_with("\ngaps ▁▁█▁ longest 80µs")));
+    /// detail!(detailer, "sent request");
+    /// detail!(detailer, "waiting");
+    /// detail!(detailer, "still waiting");
+    /// NOW.fetch_add(70, Ordering::Relaxed);
+    /// detail!(detailer, "received response");
+    /// detail!(detailer, "done");
+    /// detailer.flush();
+    /// ```
+    pub fn sparkline(&mut self, width: Option<usize>) {
+        self.sparkline = width;
+    }
+
+    /// Count the lines that were not recorded, because they were below the
+    /// level or the trace was over its size limit, and note them at the end
+    /// of each flushed trace, like `(+37 lines suppressed below INFO)`, so
+    /// readers know how much is missing.
+    ///
+    /// ```
+    /// use detailer::{detail, detail_at, new_detailer};
+    ///
+    /// let mut detailer = new_detailer!(Info, WithoutTiming);
+    /// detailer.report_suppressed(true);
+    /// detailer.on_flush(|trace| {
+    ///     assert_eq!("polling\n(+2 lines suppressed below INFO)", trace.text)
+    /// });
+    /// detail!(detailer, "polling");
+    /// detail_at!(detailer, Debug, "tick");
+    /// detail_at!(detailer, Debug, "tick");
+    /// detailer.flush();
+    /// ```
+    pub fn report_suppressed(&mut self, enabled: bool) {
+        self.report_suppressed = enabled;
+    }
+
+    /// Record a warning in the trace when it grows past `watermark` bytes,
+    /// once per trace, so runaway instrumentation shows up in the trace
+    /// itself. The warning is recorded even if the detailer's level is
+    /// quieter than warnings, like a scope's opening line.
+    ///
+    /// ```
+    /// use detailer::{detail, new_detailer};
+    ///
+    /// let mut detailer = new_detailer!(Info, WithoutTiming);
+    /// detailer.capacity_watermark(Some(16));
+    /// detail!(detailer, "loaded page 1");
+    /// detail!(detailer, "loaded page 2");
+    /// detail!(detailer, "loaded page 3");
+    /// assert_eq!(
+    ///     "loaded page 1\nloaded page 2\ntrace passed 16 bytes, at 2 lines\nloaded page 3\n",
+    ///     detailer.peek(),
+    /// );
+    /// ```
+    pub fn capacity_watermark(&mut self, watermark: Option<usize>) {
+        self.watermark = watermark;
+    }
+
+    /// Flush traces longer than `size` bytes as several parts of at most
+    /// `size` bytes, split between lines where possible. Use this when
+    /// traces go somewhere that truncates long records.
+    ///
+    /// Each part starts with a `part 2/5` header, with the trace ID when the
+    /// detailer has one, so the parts of a trace can be put back together.
+    /// The parts are flushed separately, at the trace's level. A sink that
+    /// declares its own [`record_limit()`](Sink::record_limit) gets that
+    /// instead.
+    ///
+    /// ```
+    /// use std::sync::{Arc, Mutex};
+    ///
+    /// use detailer::{detail, new_detailer};
+    ///
+    /// let parts = Arc::new(Mutex::new(Vec::new()));
+    /// let mut detailer = new_detailer!(Info, WithoutTiming);
+    /// detailer.trace_id("4bf9");
+    /// detailer.chunk_traces_over(Some(40));
+    /// detailer.on_flush({
+    ///     let parts = parts.clone();
+    ///     move |trace| parts.lock().unwrap().push(trace.text.to_string())
+    /// });
+    /// detail!(detailer, "loaded page 1");
+    /// detail!(detailer, "loaded page 2");
+    /// detail!(detailer, "loaded page 3");
+    /// detailer.flush();
+    /// assert_eq!(
+    ///     vec![
+    ///         "part 1/3 trace_id=4bf9\nloaded page 1",
+    ///         "part 2/3 trace_id=4bf9\nloaded page 2",
+    ///         "part 3/3 trace_id=4bf9\nloaded page 3",
+    ///     ],
+    ///     *parts.lock().unwrap(),
+    /// );
+    /// ```
+    pub fn chunk_traces_over(&mut self, size: Option<usize>) {
+        self.chunk_size = size;
+    }
+
+    /// Choose what happens to a trace the sink could not write. By default
+    /// it is logged through the `log` crate instead.
+    ///
+    /// ```
+    /// use detailer::{new_detailer, FlushErrorPolicy};
+    ///
+    /// let mut detailer = new_detailer!();
+    /// detailer.on_flush_error(FlushErrorPolicy::Retry(2));
+    /// ```
+    pub fn on_flush_error(&mut self, policy: FlushErrorPolicy) {
+        self.on_flush_error = policy;
+    }
+
+    /// Call `callback` with each trace as it is flushed, before it goes to the
+    /// sink.
+    ///
+    /// Use this to do something extra with finished traces, like keeping them
+    /// for a request-scoped error report, without writing a whole sink.
+    ///
+    /// ```
+    /// use std::sync::{Arc, Mutex};
+    ///
+    /// use detailer::{detail, new_detailer};
+    ///
+    /// let last_trace = Arc::new(Mutex::new(String::new()));
+    /// let mut detailer = new_detailer!(Info, WithoutTiming);
+    /// detailer.on_flush({
+    ///     let last_trace = last_trace.clone();
+    ///     move |trace| {
+    ///         if let Ok(mut last_trace) = last_trace.lock() {
+    ///             *last_trace = trace.text.to_string();
+    ///         }
+    ///     }
+    /// });
+    ///
+    /// detail!(detailer, "charged card");
+    /// detailer.flush();
+    /// assert_eq!("charged card", *last_trace.lock().unwrap());
+    /// ```
+    pub fn on_flush(&mut self, callback: impl FnMut(&FlushedTrace) + Send + Sync + 'static) {
+        self.on_flush = Some(Box::new(callback));
+    }
+
+    /// Call `callback` with each line as it is recorded.
+    ///
+    /// Use this to stream a trace while it is in progress, like to a live
+    /// debugging view, instead of waiting for it to be flushed. The callback
+    /// runs on the recording thread, so keep it quick.
+    ///
+    /// ```
+    /// use std::sync::mpsc;
+    ///
+    /// use detailer::{detail, new_detailer};
+    ///
+    /// let (sender, lines) = mpsc::channel();
+    /// let mut detailer = new_detailer!();
+    /// detailer.on_line(move |line| {
+    ///     let _ = sender.send(format!("{}: {}", line.level, line.message));
+    /// });
+    ///
+    /// detail!(detailer, "connecting");
+    /// assert_eq!(Ok("INFO: connecting".to_string()), lines.try_recv());
+    /// ```
+    ///
+    /// Lines that are [folded](Self::deduplicate) into the line before them
+    /// aren't passed on again:
+    ///
+    /// ```
+    /// use std::sync::{
+    ///     atomic::{AtomicUsize, Ordering},
+    ///     Arc,
+    /// };
+    ///
+    /// use detailer::{detail, new_detailer};
+    ///
+    /// let seen = Arc::new(AtomicUsize::new(0));
+    /// let mut detailer = new_detailer!(Info, WithoutTiming);
+    /// detailer.deduplicate(true);
+    /// let counter = seen.clone();
+    /// detailer.on_line(move |_| {
+    ///     counter.fetch_add(1, Ordering::Relaxed);
+    /// });
+    ///
+    /// for _ in 0..3 {
+    ///     detail!(detailer, "retrying");
+    /// }
+    /// assert_eq!(1, seen.load(Ordering::Relaxed));
+    /// ```
+    pub fn on_line(&mut self, callback: impl FnMut(&RecordedLine) + Send + Sync + 'static) {
+        self.on_line = Some(Box::new(callback));
+    }
+
+    /// Receive each line as it is recorded, from any thread.
+    ///
+    /// Use this to tail a long-running operation's trace from a debug
+    /// console. The subscription holds up to `capacity` lines; when it is
+    /// full, the oldest line is dropped to make room.
+    ///
+    /// ```
+    /// use detailer::{detail, new_detailer, scope};
+    ///
+    /// let mut detailer = new_detailer!(Info, WithoutTiming);
+    /// let subscription = detailer.subscribe(2);
+    ///
+    /// {
+    ///     let _scope = scope!(detailer, "compacting");
+    ///     detail!(detailer, "shard 1");
+    /// }
+    /// drop(detailer);
+    ///
+    /// // "compacting" was dropped to make room for "dropped".
+    /// assert_eq!(1, subscription.dropped());
+    /// let tail: Vec<String> = subscription
+    ///     .map(|line| format!("{}{}", "  ".repeat(line.indent), line.message))
+    ///     .collect();
+    /// assert_eq!(vec!["  shard 1", "dropped"], tail);
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn subscribe(&mut self, capacity: usize) -> Subscription {
+        let (publisher, subscription) = subscribe::channel(capacity);
+        self.subscribers.push(publisher);
+        subscription
+    }
+
+    /// Cut messages longer than `limit` bytes short, marking the cut with `…`.
+    ///
+    /// This keeps an accidentally logged response body or other huge value
+    /// from blowing up the trace.
+    ///
+    /// ```
+    /// use detailer::{detail, new_detailer};
+    ///
+    /// let mut detailer = new_detailer!(Info, WithoutTiming);
+    /// detailer.max_message_length(Some(8));
+    ///
+    /// detail!(detailer, "response body: {}", "x".repeat(2 * 1024 * 1024));
+    /// assert_eq!("response…\n", detailer.peek());
+    /// ```
+    pub fn max_message_length(&mut self, limit: Option<usize>) {
+        self.max_message_length = limit;
+    }
+
+    /// After each flush, give back buffer capacity beyond `baseline` bytes.
+    ///
+    /// A long-lived or pooled detailer otherwise keeps the allocation of the
+    /// largest trace it ever recorded.
+    ///
+    /// ```
+    /// use detailer::{detail, new_detailer};
+    ///
+    /// let mut detailer = new_detailer!(Info, WithoutTiming, 1024 * 1024);
+    /// detailer.shrink_after_flush(Some(1024));
+    ///
+    /// detail!(detailer, "{}", "x".repeat(64 * 1024));
+    /// detailer.flush();
+    /// assert!(detailer.memory_used() <= 1024);
+    /// ```
+    pub fn shrink_after_flush(&mut self, baseline: Option<usize>) {
+        self.shrink_after_flush = baseline;
+    }
+
+    /// Collapse consecutive repeats of the same line into the first one,
+    /// with a `×N` count suffix. With timing enabled the suffix also tells
+    /// when the first and last repeats were logged, like
+    /// `×3 (first at 120µs, last at 900µs)`.
+    ///
+    /// ```
+    /// use detailer::{detail, new_detailer};
+    ///
+    /// let mut detailer = new_detailer!(Info, WithoutTiming);
+    /// detailer.deduplicate(true);
+    ///
+    /// for _ in 0..3 {
+    ///     detail!(detailer, "polling");
+    /// }
+    /// detail!(detailer, "ready");
+    /// assert_eq!("polling ×3\nready\n", detailer.peek());
+    /// ```
+    ///
+    /// Only lines with the same level, target, lane and tags are folded:
+    ///
+    /// ```
+    /// use detailer::{detail, detail_at, detail_tagged, new_detailer};
+    ///
+    /// let mut detailer = new_detailer!(Info, WithoutTiming);
+    /// detailer.deduplicate(true);
+    ///
+    /// detail!(detailer, "retrying");
+    /// detail_at!(detailer, Warn, "retrying");
+    /// detail_tagged!(detailer, Warn, ["db"], "retrying");
+    /// detail_tagged!(detailer, Warn, ["db"], "retrying");
+    /// assert_eq!("retrying\nretrying\nretrying ×2\n", detailer.peek());
+    /// ```
+    pub fn deduplicate(&mut self, enabled: bool) {
+        self.deduplicate = enabled;
+        self.last_line = None;
+    }
+
+    /// Begin every trace with a line, like `"request start"`.
+    ///
+    /// `{elapsed}` in the template is replaced with the time since the trace
+    /// started, like `120µs`, and `{lines}` with how many lines the trace
+    /// has recorded.
+    ///
+    /// ```
+    /// use detailer::{detail, new_detailer};
+    ///
+    /// let mut detailer = new_detailer!(Info, WithoutTiming);
+    /// detailer.start_line(Some("request start"));
+    /// detailer.end_line(Some("request complete after {lines} lines"));
+    ///
+    /// detail!(detailer, "authenticated");
+    /// detailer.on_flush(|trace| {
+    ///     assert_eq!(
+    ///         "request start\nauthenticated\nrequest complete after 2 lines",
+    ///         trace.text,
+    ///     )
+    /// });
+    /// detailer.flush();
+    /// ```
+    pub fn start_line(&mut self, template: Option<&str>) {
+        self.start_line = template.map(Into::into);
+    }
+
+    /// End every flushed trace with a line, like `"complete in {elapsed}"`.
+    /// The template is filled in like [`start_line()`](Self::start_line)'s.
+    pub fn end_line(&mut self, template: Option<&str>) {
+        self.end_line = template.map(Into::into);
+    }
+
+    /// Whether dropping the detailer flushes what it holds, which it does by
+    /// default. Turn this off when flushing should only ever be deliberate,
+    /// like when the trace is attached to an error instead.
+    ///
+    /// ```
+    /// use detailer::{detail, new_detailer};
+    ///
+    /// let mut detailer = new_detailer!(Info, WithoutTiming);
+    /// detailer.flush_on_drop(false);
+    /// detailer.on_flush(|_| panic!("not flushed"));
+    /// detail!(detailer, "connecting");
+    /// let error_context = detailer.snapshot();
+    /// ```
+    pub fn flush_on_drop(&mut self, enabled: bool) {
+        self.flush_on_drop = enabled;
+    }
+
+    /// Drop the detailer without flushing what it holds.
+    ///
+    /// ```
+    /// use detailer::{detail, new_detailer};
+    ///
+    /// let mut detailer = new_detailer!(Info, WithoutTiming);
+    /// detailer.on_flush(|_| panic!("not flushed"));
+    /// detail!(detailer, "cache hit, nothing worth logging");
+    /// detailer.discard();
+    /// ```
+    pub fn discard(mut self) {
+        self.flush_on_drop = false;
+    }
+
+    /// Change the line recorded when the detailer is dropped with lines it
+    /// did not flush yet, which is `"dropped"` by default, or record none.
+    /// The template is filled in like [`start_line()`](Self::start_line)'s.
+    ///
+    /// ```
+    /// use detailer::{detail, new_detailer};
+    ///
+    /// let mut detailer = new_detailer!(Info, WithoutTiming);
+    /// detailer.drop_line(Some("abandoned ({lines} recorded)"));
+    /// detailer.on_flush(|trace| assert!(trace.text.ends_with("abandoned (1 recorded)")));
+    /// detail!(detailer, "connecting");
+    /// ```
+    pub fn drop_line(&mut self, template: Option<&str>) {
+        self.drop_line = template.map(Into::into);
+    }
+
+    /// Change how each line starts.
+    ///
+    /// ```
+    /// use detailer::{detail, new_detailer, LineFormat};
+    ///
+    /// # #[cfg(feature = "std")]
+    /// # {
+    /// let mut detailer = new_detailer!();
+    /// detailer.line_format(LineFormat::EnvLogger { target: "checkout" });
+    /// detail!(detailer, "authenticating");
+    ///
+    /// let line = detailer.peek();
+    /// assert!(line.starts_with("[20"));
+    /// assert!(line.ends_with("Z INFO  checkout] authenticating\n"));
+    /// # }
+    /// ```
+    ///
+    /// This replaces a [line formatter](Self::line_formatter), if one was set.
+    pub fn line_format(&mut self, format: LineFormat) {
+        self.line_format = format;
+        self.line_formatter = None;
+    }
+
+    /// Start each line with your own layout instead of one of the built-in
+    /// [`LineFormat`]s. See [`LineFormatter`].
+    ///
+    /// The formatter writes the whole start of the line, so the
+    /// [ANSI badges](Self::ansi) and [severity markers](Self::severity_markers)
+    /// are left to it too. Set a [`LineFormat`] to go back to a built-in one.
+    pub fn line_formatter(&mut self, formatter: impl LineFormatter + 'static) {
+        self.line_formatter = Some(Arc::new(formatter));
+    }
+
+    /// Start each line with whatever `prefix` makes of its elapsed time and
+    /// level, followed by its indentation. This is a shorter way to write a
+    /// [line formatter](Self::line_formatter) when only the prefix changes.
     ///
     /// ```
-    /// use detailer::{Detailer, new_detailer};
+    /// use std::sync::atomic::{AtomicU64, Ordering};
     ///
-    /// let mut detailer = new_detailer!();
+    /// use detailer::{detail, scope, Detailer, TimingSetting};
     ///
-    /// detailer.debug(format_args!("yikes {}", 42));
+    /// static NOW: AtomicU64 = AtomicU64::new(0);
+    /// fn now() -> u64 {
+    ///     NOW.fetch_add(1250, Ordering::Relaxed)
+    /// }
+    ///
+    /// let mut detailer = Detailer::new(log::LevelFilter::Info, TimingSetting::WithClock(now), 4096);
+    /// detailer.prefix_with(|elapsed, _level| match elapsed {
+    ///     Some(elapsed) => format!("[+{:.1}ms] ", elapsed.as_secs_f64() * 1000.0),
+    ///     None => String::new(),
+    /// });
+    /// {
+    ///     let _scope = scope!(detailer, "checkout");
+    ///     detail!(detailer, "charged card");
+    /// }
+    /// assert_eq!("[+1.2ms] checkout\n[+2.5ms]   charged card\n", detailer.peek());
     /// ```
-    pub fn debug(&mut self, message: Arguments) {
-        self.log(log::Level::Debug, message)
+    pub fn prefix_with<D: Display>(
+        &mut self,
+        prefix: impl Fn(Option<Duration>, log::Level) -> D + Send + Sync + 'static,
+    ) {
+        self.line_formatter(PrefixWith(prefix));
     }
 
-    /// log a line
+    /// Render for a terminal, with a colored level badge like `[E]` or `[W]`
+    /// on every line, so it is easy to see where a trace went wrong.
+    ///
+    /// Traces with ANSI escapes can't be read back with
+    /// [`parse`](crate::parse), so keep this for output that people read.
     ///
     /// ```
-    /// use detailer::{Detailer, new_detailer};
-    /// use log::{Level, LevelFilter};
+    /// use detailer::{detail, detail_at, new_detailer};
     ///
-    /// let mut detailer = new_detailer!();
+    /// let mut detailer = new_detailer!(Info, WithoutTiming);
+    /// detailer.ansi(true);
+    /// detail!(detailer, "connecting");
+    /// detail_at!(detailer, Error, "refused");
+    /// assert_eq!(
+    ///     "\x1b[32m[I]\x1b[0m connecting\n\x1b[31m[E]\x1b[0m refused\n",
+    ///     detailer.peek(),
+    /// );
+    /// ```
+    pub fn ansi(&mut self, enabled: bool) {
+        self.ansi = enabled;
+    }
+
+    /// Start warning and error lines with a marker, so failures stand out
+    /// where ANSI colors are stripped.
     ///
-    /// detailer.warn(format_args!("yikes {}", 42));
     /// ```
-    pub fn warn(&mut self, message: Arguments) {
-        self.log(log::Level::Warn, message)
+    /// use detailer::{detail, detail_at, new_detailer, SeverityMarkers};
+    ///
+    /// let mut detailer = new_detailer!(Info, WithoutTiming);
+    /// detailer.severity_markers(Some(SeverityMarkers::default()));
+    /// detail!(detailer, "connecting");
+    /// detail_at!(detailer, Warn, "slow");
+    /// detail_at!(detailer, Error, "refused");
+    /// assert_eq!("  connecting\n! slow\n✗ refused\n", detailer.peek());
+    /// ```
+    pub fn severity_markers(&mut self, markers: Option<SeverityMarkers>) {
+        self.severity_markers = markers;
     }
 
-    /// log a line
+    /// Change how the second and later lines of multi-line messages are laid
+    /// out.
     ///
     /// ```
-    /// use detailer::{Detailer, new_detailer};
-    /// use log::{Level, LevelFilter};
+    /// use std::sync::atomic::{AtomicU64, Ordering};
     ///
-    /// let mut detailer = new_detailer!();
+    /// use detailer::{detail, ContinuationLines, Detailer, TimingSetting};
     ///
-    /// detailer.error(format_args!("yikes {}", 42));
+    /// static NOW: AtomicU64 = AtomicU64::new(0);
+    /// fn now() -> u64 {
+    ///     NOW.load(Ordering::Relaxed)
+    /// }
+    ///
+    /// let mut detailer = Detailer::new(log::LevelFilter::Info, TimingSetting::WithClock(now), 4096);
+    /// detailer.continuation_lines(ContinuationLines::Aligned);
+    /// NOW.store(42, Ordering::Relaxed);
+    /// detail!(detailer, "response:\n{{\n  \"ok\": true\n}}");
+    /// assert_eq!(
+    ///     "42     response:\n       {\n         \"ok\": true\n       }\n",
+    ///     detailer.peek(),
+    /// );
     /// ```
-    pub fn error(&mut self, message: Arguments) {
-        self.log(log::Level::Error, message)
+    ///
+    /// With `Prefixed`, every continuation line carries the elapsed time:
+    ///
+    /// ```
+    /// use detailer::{detail, parse, ContinuationLines, Detailer, TimingSetting};
+    ///
+    /// fn now() -> u64 {
+    ///     0
+    /// }
+    ///
+    /// let mut detailer = Detailer::new(log::LevelFilter::Info, TimingSetting::WithClock(now), 4096);
+    /// detailer.continuation_lines(ContinuationLines::Prefixed);
+    /// detail!(detailer, "response:\n{{}}");
+    /// assert_eq!("0      response:\n0      … {}\n", detailer.peek());
+    /// assert_eq!("response:\n{}", parse::events(detailer.peek())[0].message);
+    /// ```
+    pub fn continuation_lines(&mut self, layout: ContinuationLines) {
+        self.continuation_lines = layout;
     }
 
-    /// Override the level for this detailer
+    /// Pad the elapsed microseconds column to `width` characters, instead of
+    /// the default of 6. Wider values still fit, but they push their line out
+    /// of alignment, so traces that run past a second read better with a
+    /// wider column, and short ones can save the space.
+    ///
+    /// Traces with a custom width are parsed with
+    /// [`parse::events_with_elapsed_width()`].
     ///
     /// ```
-    /// use detailer::{Detailer, new_detailer};
-    /// use log::{Level, LevelFilter};
+    /// use std::sync::atomic::{AtomicU64, Ordering};
     ///
-    /// let mut detailer = new_detailer!();
+    /// use detailer::{detail, Detailer, TimingSetting};
     ///
-    /// detailer.debug(format_args!("{} logged", "not"));
-    /// detailer.level(log::LevelFilter::Debug);
-    /// detailer.debug(format_args!("{} logged", "is"));
+    /// static NOW: AtomicU64 = AtomicU64::new(0);
+    /// fn now() -> u64 {
+    ///     NOW.load(Ordering::Relaxed)
+    /// }
+    ///
+    /// let mut detailer = Detailer::new(log::LevelFilter::Info, TimingSetting::WithClock(now), 4096);
+    /// detailer.elapsed_width(9);
+    /// detail!(detailer, "start");
+    /// NOW.store(12_500_000, Ordering::Relaxed);
+    /// detail!(detailer, "done");
+    /// assert_eq!("0         start\n12500000  done\n", detailer.peek());
     /// ```
-    pub fn level(&mut self, level: log::LevelFilter) {
-        self.level = level;
+    pub fn elapsed_width(&mut self, width: usize) {
+        self.elapsed_width = width;
+    }
+
+    /// Change how the elapsed time starting each line is written, without
+    /// changing the rest of the [line format](Self::line_format).
+    ///
+    /// Only traces with [`ElapsedFormat::Micros`] can be read back with
+    /// [`parse`](crate::parse).
+    ///
+    /// ```
+    /// use std::sync::atomic::{AtomicU64, Ordering};
+    ///
+    /// use detailer::{detail, Detailer, ElapsedFormat, TimingSetting};
+    ///
+    /// static NOW: AtomicU64 = AtomicU64::new(0);
+    /// fn now() -> u64 {
+    ///     NOW.fetch_add(1250, Ordering::Relaxed)
+    /// }
+    ///
+    /// let mut detailer = Detailer::new(log::LevelFilter::Info, TimingSetting::WithClock(now), 4096);
+    /// detailer.elapsed_format(ElapsedFormat::Millis);
+    /// detail!(detailer, "start");
+    /// detailer.elapsed_format(ElapsedFormat::Custom(|elapsed, f| {
+    ///     write!(f, "+{}", elapsed.as_micros())
+    /// }));
+    /// detail!(detailer, "done");
+    /// assert_eq!("1.250  start\n+2500  done\n", detailer.peek());
+    /// ```
+    ///
+    /// [`ElapsedFormat::Scaled`] suits traces that can run from microseconds
+    /// to seconds:
+    ///
+    /// ```
+    /// use std::sync::atomic::{AtomicU64, Ordering};
+    ///
+    /// use detailer::{detail, Detailer, ElapsedFormat, TimingSetting};
+    ///
+    /// static NOW: AtomicU64 = AtomicU64::new(0);
+    /// fn now() -> u64 {
+    ///     NOW.load(Ordering::Relaxed)
+    /// }
+    ///
+    /// let mut detailer = Detailer::new(log::LevelFilter::Info, TimingSetting::WithClock(now), 4096);
+    /// detailer.elapsed_format(ElapsedFormat::Scaled);
+    /// NOW.store(842, Ordering::Relaxed);
+    /// detail!(detailer, "connected");
+    /// NOW.store(1_342, Ordering::Relaxed);
+    /// detail!(detailer, "queried");
+    /// NOW.store(27_500, Ordering::Relaxed);
+    /// detail!(detailer, "fetched");
+    /// NOW.store(2_400_000, Ordering::Relaxed);
+    /// detail!(detailer, "exported");
+    /// assert_eq!(
+    ///     "842µs  connected\n1.3ms  queried\n27ms   fetched\n2.4s   exported\n",
+    ///     detailer.peek(),
+    /// );
+    /// ```
+    pub fn elapsed_format(&mut self, format: ElapsedFormat) {
+        self.elapsed_format = format;
+    }
+
+    /// Begin each trace with a line telling the wall clock time it started,
+    /// like `trace started 2024-04-24T20:31:28.767641Z`, so the relative
+    /// timings can be placed on an absolute timeline even when the log
+    /// record's own timestamp is unreliable.
+    ///
+    /// ```
+    /// use detailer::{detail, new_detailer};
+    ///
+    /// let mut detailer = new_detailer!(Info, WithoutTiming);
+    /// detailer.start_time_header(true);
+    /// detail!(detailer, "connecting");
+    ///
+    /// let (header, rest) = detailer.peek().split_once('\n').unwrap();
+    /// assert!(header.starts_with("trace started 20") && header.ends_with('Z'));
+    /// assert_eq!("connecting\n", rest);
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn start_time_header(&mut self, enabled: bool) {
+        self.start_time_header = enabled;
+    }
+
+    /// Read the clock for only one line in every `every`, and time the lines
+    /// in between the same as the last line that read it, or read it for
+    /// every line again with `None`. For hot paths where reading the clock
+    /// for each line is measurable, at the cost of precision. Each trace's
+    /// total time is still read when it is flushed.
+    ///
+    /// ```
+    /// use std::sync::atomic::{AtomicU64, Ordering};
+    ///
+    /// use detailer::{detail, Detailer, TimingSetting};
+    ///
+    /// static NOW: AtomicU64 = AtomicU64::new(0);
+    /// fn now() -> u64 {
+    ///     NOW.fetch_add(10, Ordering::Relaxed)
+    /// }
+    ///
+    /// let mut detailer = Detailer::new(log::LevelFilter::Info, TimingSetting::WithClock(now), 4096);
+    /// detailer.coarse_timing(Some(2));
+    /// for step in 0..4 {
+    ///     detail!(detailer, "step {step}");
+    /// }
+    /// assert_eq!(
+    ///     "10     step 0\n10     step 1\n20     step 2\n20     step 3\n",
+    ///     detailer.peek(),
+    /// );
+    /// ```
+    pub fn coarse_timing(&mut self, every: Option<usize>) {
+        self.coarse_timing = every.map(|every| CoarseTiming {
+            every: every.max(1),
+            lines: 0,
+            elapsed: None,
+        });
+    }
+
+    /// Round times down to a multiple of `granularity`, like 10µs or 1ms, to
+    /// take the noise out of traces, or to keep precise timings out of traces
+    /// that are shown to users. Times are exact to the microsecond by default.
+    ///
+    /// ```
+    /// use std::{sync::atomic::{AtomicU64, Ordering}, time::Duration};
+    ///
+    /// use detailer::{detail, Detailer, TimingSetting};
+    ///
+    /// static NOW: AtomicU64 = AtomicU64::new(0);
+    /// fn now() -> u64 {
+    ///     NOW.load(Ordering::Relaxed)
+    /// }
+    ///
+    /// let mut detailer = Detailer::new(log::LevelFilter::Info, TimingSetting::WithClock(now), 4096);
+    /// detailer.timing_granularity(Duration::from_micros(10));
+    /// NOW.store(1_237, Ordering::Relaxed);
+    /// detail!(detailer, "queried");
+    /// assert_eq!("1230   queried\n", detailer.peek());
+    /// ```
+    pub fn timing_granularity(&mut self, granularity: Duration) {
+        self.granularity_micros = (granularity.as_micros() as u64).max(1);
+    }
+
+    /// Stamp each line with the wall clock time it was recorded at, as well
+    /// as its time since the trace started. The text keeps showing relative
+    /// times; [line callbacks](Self::on_line) and
+    /// [subscribers](Self::subscribe) get the absolute time too, for exporting
+    /// lines to systems that place them on a shared timeline.
+    ///
+    /// ```
+    /// use detailer::{detail, new_detailer};
+    ///
+    /// let mut detailer = new_detailer!(Info, WithTiming);
+    /// detailer.wall_clock_timestamps(true);
+    /// detailer.on_line(|line| {
+    ///     assert!(line.elapsed.is_some());
+    ///     assert!(line.since_epoch.is_some());
+    /// });
+    /// detail!(detailer, "connecting");
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn wall_clock_timestamps(&mut self, enabled: bool) {
+        self.wall_clock = enabled;
+    }
+
+    /// Flush each trace at the most severe level among its lines, when that
+    /// is more severe than the detailer's level. One error line makes the
+    /// whole trace log as an error, so alerts that key off the record level
+    /// see it.
+    ///
+    /// ```
+    /// use detailer::{detail, detail_at, new_detailer};
+    ///
+    /// let mut detailer = new_detailer!(Info, WithoutTiming);
+    /// detailer.flush_at_highest_level(true);
+    /// detailer.on_flush(|trace| assert_eq!(log::Level::Error, trace.level));
+    ///
+    /// detail!(detailer, "connecting");
+    /// detail_at!(detailer, Error, "connection refused");
+    /// detailer.flush();
+    /// ```
+    pub fn flush_at_highest_level(&mut self, enabled: bool) {
+        self.flush_at_highest_level = enabled;
     }
 }
 
 impl Drop for Detailer {
     fn drop(&mut self) {
+        if !self.flush_on_drop {
+            return;
+        }
         if !self.accumulated.is_empty() {
-            self.log(
-                self.level.to_level().unwrap_or(log::Level::Info),
-                format_args!("dropped"),
-            );
+            if let Some(drop_line) = self.drop_line.take() {
+                self.log_template(&drop_line);
+            }
         }
         self.flush()
     }
 }
 
+/// The suffix that makes a noun counted `count` times plural.
+fn plural(count: u64) -> &'static str {
+    if count == 1 {
+        ""
+    } else {
+        "s"
+    }
+}
+
 impl Default for Detailer {
     fn default() -> Self {
-        Self {
-            level: log::LevelFilter::Info,
-            accumulated: Default::default(),
-            current_indentation: Default::default(),
-            start: Some(Instant::now()),
-            soft_limit: 4 * 1024,
+        Self::new(log::LevelFilter::Info, TimingSetting::WithTiming, 4 * 1024)
+    }
+}
+
+/// A line's message, either still to be formatted or already text.
+enum Message<'a> {
+    Format(Arguments<'a>),
+    Text(&'a str),
+}
+
+/// Whether `target` is `module` or a module inside it.
+fn within(target: &str, module: &str) -> bool {
+    target
+        .strip_prefix(module)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with("::"))
+}
+
+/// Hand a flushed trace to the flush callback and then the sink, applying the
+/// flush error policy.
+fn deliver(
+    on_flush: &mut Option<Box<FlushCallback>>,
+    sink: Option<&dyn Sink>,
+    on_flush_error: FlushErrorPolicy,
+    trace: &FlushedTrace,
+) -> Result<(), FlushError> {
+    if let Some(on_flush) = on_flush {
+        on_flush(trace);
+    }
+    let Some(sink) = sink else {
+        LogSink.flush(trace);
+        return Ok(());
+    };
+    let mut result = sink.try_flush(trace);
+    if let FlushErrorPolicy::Retry(attempts) = on_flush_error {
+        for _ in 0..attempts {
+            if result.is_ok() {
+                break;
+            }
+            result = sink.try_flush(trace);
+        }
+    }
+    if let Err(e) = &result {
+        if on_flush_error != FlushErrorPolicy::Discard {
+            log::warn!("could not flush trace to its sink, logging it instead: {e}");
+            LogSink.flush(trace);
+        }
+    }
+    result
+}
+
+/// Stands in for a sink that can't take a trace over its record limit.
+struct Rejected {
+    bytes: usize,
+    limit: usize,
+}
+
+impl Sink for Rejected {
+    fn flush(&self, _trace: &FlushedTrace) {}
+
+    fn try_flush(&self, _trace: &FlushedTrace) -> Result<(), FlushError> {
+        Err(FlushError::new(format!(
+            "the trace is {} bytes, over the sink's {} byte record limit",
+            self.bytes, self.limit
+        )))
+    }
+}
+
+/// Write the lane, thread and pushed context that mark a line, if there are
+/// any.
+fn write_line_markers(
+    out: &mut String,
+    lane: Option<&str>,
+    thread: Option<&str>,
+    pushed: &[(&'static str, String)],
+) {
+    if let Some(lane) = lane {
+        let _ = write!(out, "{} ", LaneMarker(lane));
+    }
+    match (thread, pushed.is_empty()) {
+        (Some(thread), true) => {
+            let _ = write!(out, "[thread={thread}] ");
+        }
+        (Some(thread), false) => {
+            let _ = write!(out, "[thread={thread} {}] ", ContextHeader(pushed));
+        }
+        (None, false) => {
+            let _ = write!(out, "[{}] ", ContextHeader(pushed));
+        }
+        (None, true) => {}
+    }
+}
+
+/// Write a message into `out`, cut short at `limit` bytes.
+fn write_message(out: &mut String, message: Message, limit: Option<usize>) {
+    match (message, limit) {
+        (Message::Text(text), Some(limit)) if limit < text.len() => {
+            let mut end = limit;
+            while !text.is_char_boundary(end) {
+                end -= 1;
+            }
+            out.push_str(&text[..end]);
+            out.push('…');
+        }
+        (Message::Text(text), _) => out.push_str(text),
+        (Message::Format(message), None) => {
+            let _ = out.write_fmt(message);
+        }
+        (Message::Format(message), Some(limit)) => {
+            let mut truncating = Truncating {
+                out,
+                remaining: limit,
+                truncated: false,
+            };
+            let _ = truncating.write_fmt(message);
+            if truncating.truncated {
+                out.push('…');
+            }
+        }
+    }
+}
+
+/// How many columns `text` takes up in a terminal, not counting ANSI escapes.
+fn visible_width(text: &str) -> usize {
+    let mut width = 0;
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            // Skip to the end of the escape sequence.
+            for c in chars.by_ref() {
+                if c.is_ascii_alphabetic() {
+                    break;
+                }
+            }
+        } else {
+            width += 1;
+        }
+    }
+    width
+}
+
+/// A boilerplate line's template, filled in as it is formatted.
+struct Template<'a> {
+    template: &'a str,
+    elapsed: Option<u64>,
+    lines: usize,
+}
+
+impl core::fmt::Display for Template<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let mut rest = self.template;
+        while let Some(open) = rest.find('{') {
+            f.write_str(&rest[..open])?;
+            rest = &rest[open..];
+            if let Some(after) = rest.strip_prefix("{elapsed}") {
+                match self.elapsed {
+                    Some(elapsed) => write!(f, "{elapsed}µs")?,
+                    None => f.write_str("-")?,
+                }
+                rest = after;
+            } else if let Some(after) = rest.strip_prefix("{lines}") {
+                write!(f, "{}", self.lines)?;
+                rest = after;
+            } else {
+                f.write_str("{")?;
+                rest = &rest[1..];
+            }
+        }
+        f.write_str(rest)
+    }
+}
+
+/// Everything a line is written with before its message.
+struct LinePrefix<'a> {
+    format: LineFormat,
+    formatter: Option<&'a dyn LineFormatter>,
+    target: Option<&'static str>,
+    elapsed: Option<u64>,
+    elapsed_format: ElapsedFormat,
+    elapsed_width: usize,
+    level: log::Level,
+    ansi: bool,
+    severity_markers: Option<SeverityMarkers>,
+    indentation: usize,
+}
+
+impl core::fmt::Display for LinePrefix<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let level = self.level;
+        if let Some(formatter) = self.formatter {
+            return formatter.write_prefix(
+                f,
+                &LineStart {
+                    elapsed: self.elapsed.map(Duration::from_micros),
+                    level,
+                    indentation: self.indentation,
+                    target: self.target,
+                },
+            );
+        }
+        match self.format {
+            LineFormat::Elapsed => {
+                if let Some(elapsed) = self.elapsed {
+                    let mut column = Counted { f, chars: 0 };
+                    write!(column, "{}", ElapsedText(self.elapsed_format, elapsed))?;
+                    let padding = self.elapsed_width.saturating_sub(column.chars);
+                    write!(f, "{:padding$} ", "")?;
+                }
+            }
+            #[cfg(feature = "std")]
+            LineFormat::EnvLogger { target } => {
+                f.write_str("[")?;
+                clock::write_rfc3339(
+                    f,
+                    std::time::SystemTime::now()
+                        .duration_since(std::time::UNIX_EPOCH)
+                        .unwrap_or_default(),
+                )?;
+                write!(f, " {level:<5} {target}] ")?;
+            }
+        }
+        if self.ansi {
+            let (color, badge) = match level {
+                log::Level::Error => ("31", "E"),
+                log::Level::Warn => ("33", "W"),
+                log::Level::Info => ("32", "I"),
+                log::Level::Debug => ("34", "D"),
+                log::Level::Trace => ("35", "T"),
+            };
+            write!(f, "\x1b[{color}m[{badge}]\x1b[0m ")?;
+        }
+        if let Some(markers) = &self.severity_markers {
+            let marker = match level {
+                log::Level::Error => markers.error,
+                log::Level::Warn => markers.warn,
+                _ => "",
+            };
+            let width = markers
+                .warn
+                .chars()
+                .count()
+                .max(markers.error.chars().count());
+            write!(f, "{marker:<width$} ")?;
+        }
+        for _ in 0..self.indentation {
+            f.write_str("  ")?;
+        }
+        Ok(())
+    }
+}
+
+/// An elapsed time in microseconds, written in an [`ElapsedFormat`].
+struct ElapsedText(ElapsedFormat, u64);
+
+impl core::fmt::Display for ElapsedText {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let micros = self.1;
+        match self.0 {
+            ElapsedFormat::Micros => write!(f, "{micros}"),
+            ElapsedFormat::Millis => write!(f, "{}.{:03}", micros / 1000, micros % 1000),
+            ElapsedFormat::Scaled => {
+                let (scale, unit) = match micros {
+                    0..=999 => return write!(f, "{micros}µs"),
+                    1_000..=999_999 => (1_000, "ms"),
+                    _ => (1_000_000, "s"),
+                };
+                let whole = micros / scale;
+                if whole < 10 {
+                    write!(f, "{whole}.{}{unit}", micros % scale * 10 / scale)
+                } else {
+                    write!(f, "{whole}{unit}")
+                }
+            }
+            ElapsedFormat::Custom(write) => write(Duration::from_micros(micros), f),
+        }
+    }
+}
+
+/// Writes through to a formatter, counting the characters written, so
+/// text that doesn't pad itself can still be laid out in a column.
+struct Counted<'a, 'b> {
+    f: &'a mut core::fmt::Formatter<'b>,
+    chars: usize,
+}
+
+impl Write for Counted<'_, '_> {
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        self.chars += s.chars().count();
+        self.f.write_str(s)
+    }
+}
+
+/// The `key=value` header line of a trace's context.
+struct ContextHeader<'a, V>(&'a [(&'static str, V)]);
+
+impl<V: Display> core::fmt::Display for ContextHeader<'_, V> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        for (index, (key, value)) in self.0.iter().enumerate() {
+            if 0 < index {
+                f.write_str(" ")?;
+            }
+            write!(f, "{key}={value}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Writes into a String until it runs out of room, then stops formatting.
+struct Truncating<'a> {
+    out: &'a mut String,
+    remaining: usize,
+    truncated: bool,
+}
+
+impl Write for Truncating<'_> {
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        if s.len() <= self.remaining {
+            self.out.push_str(s);
+            self.remaining -= s.len();
+            return Ok(());
+        }
+        let mut end = self.remaining;
+        while !s.is_char_boundary(end) {
+            end -= 1;
+        }
+        self.out.push_str(&s[..end]);
+        self.remaining = 0;
+        self.truncated = true;
+        Err(core::fmt::Error)
+    }
+}
+
+/// A named lane of a detailer's trace, from [`Detailer::lane()`], which lines
+/// can be recorded into with `detail!()` and the other line macros.
+pub struct Lane<'a> {
+    detailer: &'a mut Detailer,
+    name: &'static str,
+}
+
+impl Lane<'_> {
+    /// log a line in this lane, if the level is enabled.
+    pub fn log(&mut self, level: log::Level, message: Arguments) {
+        let message = match message.as_str() {
+            Some(text) => Message::Text(text),
+            None => Message::Format(message),
+        };
+        self.detailer
+            .record(level, message, None, self.labels(), 0..0)
+    }
+
+    /// log a constant line in this lane, if the level is enabled.
+    pub fn log_static(&mut self, level: log::Level, message: &'static str) {
+        self.detailer
+            .record(level, Message::Text(message), None, self.labels(), 0..0)
+    }
+
+    fn labels(&self) -> Labels {
+        Labels {
+            lane: Some(self.name),
+            ..Labels::default()
         }
     }
 }
 
+/// How the lines of a trace's [lanes](Detailer::lane) are flushed
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum LaneLayout {
+    /// In the order they were recorded, each marked with its lane, like
+    /// `[network] sent request`. This is the default.
+    #[default]
+    Interleaved,
+    /// After the lines outside any lane, under a line for each lane, in the
+    /// order the lanes were first used
+    Grouped,
+    /// In a column for each lane, side by side, with a row for each line in
+    /// the order they were recorded, so lanes can be compared along one
+    /// timeline
+    SideBySide,
+}
+
+/// The marker at the start of a line recorded in a lane.
+struct LaneMarker<'a>(&'a str);
+
+impl LaneMarker<'_> {
+    /// `message` without this marker.
+    fn strip<'m>(&self, message: &'m str) -> &'m str {
+        message
+            .strip_prefix('[')
+            .and_then(|rest| rest.strip_prefix(self.0))
+            .and_then(|rest| rest.strip_prefix("] "))
+            .unwrap_or(message)
+    }
+}
+
+impl Display for LaneMarker<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "[{}]", self.0)
+    }
+}
+
+/// A scope begun with [`Detailer::enter_scope()`], which lines can be recorded
+/// into with [`Detailer::log_in()`] or `detail_in!()`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ScopeId {
+    depth: usize,
+    id: u64,
+}
+
 /// When a DetailScopeGuard is dropped, the level of indentation on following
 /// `detail!()`` messages for the detailer this scope was created for is
 /// immediately decremented.
 pub struct DetailScopeGuard {
     level: Arc<AtomicUsize>,
+    scope_level: Option<ScopeLevel>,
+    timer: Option<ScopeTimer>,
 }
 
 impl DetailScopeGuard {
-    fn new(level: Arc<AtomicUsize>) -> Self {
-        level.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
-        Self { level }
+    fn new(level: Arc<AtomicUsize>, scope_level: Option<ScopeLevel>) -> Self {
+        level.fetch_add(1, core::sync::atomic::Ordering::Relaxed);
+        Self {
+            level,
+            scope_level,
+            timer: None,
+        }
+    }
+
+    /// End the scope with a closing line, like a row count or status that
+    /// was only known at the end. The line is the last one inside the scope,
+    /// and like the scope's opening line it bypasses log level.
+    ///
+    /// ```
+    /// use detailer::{detail, new_detailer, scope};
+    ///
+    /// let mut detailer = new_detailer!(Info, WithoutTiming);
+    /// let scope = scope!(detailer, "loading");
+    /// detail!(detailer, "query");
+    /// scope.close_with(&mut detailer, format_args!("loaded {} rows", 12));
+    /// detail!(detailer, "done");
+    ///
+    /// assert_eq!("loading\n  query\n  loaded 12 rows\ndone\n", detailer.peek());
+    /// ```
+    pub fn close_with(self, detailer: &mut Detailer, message: Arguments) {
+        if let Some(level) = detailer.effective_level().to_level() {
+            detailer.log(level, message);
+        }
     }
 }
 
 impl Drop for DetailScopeGuard {
     fn drop(&mut self) {
         self.level
-            .fetch_sub(1, std::sync::atomic::Ordering::Relaxed);
+            .fetch_sub(1, core::sync::atomic::Ordering::Relaxed);
+    }
+}
+
+/// While a ContextGuard exists, the lines its detailer records are marked
+/// with the context it was pushed with.
+pub struct ContextGuard {
+    depth: Arc<AtomicUsize>,
+    enclosing: usize,
+}
+
+impl Drop for ContextGuard {
+    fn drop(&mut self) {
+        // Dropping an enclosing guard first also ends the contexts inside it.
+        self.depth
+            .fetch_min(self.enclosing, core::sync::atomic::Ordering::Relaxed);
+    }
+}
+
+/// Adds the time a resumed scope was open to its total when it is dropped.
+struct ScopeTimer {
+    micros: Arc<AtomicU64>,
+    clock: Clock,
+    entered: u64,
+}
+
+impl Drop for ScopeTimer {
+    fn drop(&mut self) {
+        self.micros.fetch_add(
+            (self.clock)().saturating_sub(self.entered),
+            core::sync::atomic::Ordering::Relaxed,
+        );
+    }
+}
+
+/// Stored in `scope_level` when no scope overrides the detailer's level.
+const NO_SCOPE_LEVEL: usize = usize::MAX;
+
+fn level_filter(level: usize) -> log::LevelFilter {
+    match level {
+        0 => log::LevelFilter::Off,
+        1 => log::LevelFilter::Error,
+        2 => log::LevelFilter::Warn,
+        3 => log::LevelFilter::Info,
+        4 => log::LevelFilter::Debug,
+        _ => log::LevelFilter::Trace,
+    }
+}
+
+/// A scope's level override, which puts back the enclosing scope's level
+/// when it is dropped.
+struct ScopeLevel {
+    level: Arc<AtomicUsize>,
+    enclosing: usize,
+}
+
+impl ScopeLevel {
+    fn new(level: Arc<AtomicUsize>, filter: log::LevelFilter) -> Self {
+        let enclosing = level.swap(filter as usize, core::sync::atomic::Ordering::Relaxed);
+        Self { level, enclosing }
+    }
+}
+
+impl Drop for ScopeLevel {
+    fn drop(&mut self) {
+        self.level
+            .store(self.enclosing, core::sync::atomic::Ordering::Relaxed);
     }
 }
@@ -0,0 +1,197 @@
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+use core::{fmt::Display, time::Duration};
+
+use crate::parse::{self, Event};
+
+/// Compare two traces of the same instrumentation from different runs.
+///
+/// Lines are matched up by their message and depth. Matched lines report how
+/// long they took in each run, and lines that only one run recorded are
+/// reported as missing from or extra in `b`. This answers "what changed
+/// between the fast run and the slow run".
+///
+/// A line's duration runs from the line until the next line that is not
+/// nested under it, so for a scope it covers the whole scope.
+///
+/// ```rust
+/// use detailer::{diff, Change};
+///
+/// let fast = "\
+/// 0      start
+/// 10     query
+/// 30     done";
+/// let slow = "\
+/// 0      start
+/// 10     query
+/// 12       retrying
+/// 830    done";
+///
+/// let diff = diff(fast, slow);
+/// let query = diff
+///     .changes()
+///     .iter()
+///     .find_map(|change| match change {
+///         Change::Matched(line) if line.message == "query" => Some(line),
+///         _ => None,
+///     });
+/// assert_eq!(Some(800), query.and_then(|query| query.delta_micros()));
+/// assert!(matches!(&diff.changes()[2], Change::Extra(event) if event.message == "retrying"));
+/// ```
+pub fn diff(a: &str, b: &str) -> Diff {
+    let a = with_durations(parse::events(a));
+    let b = with_durations(parse::events(b));
+
+    // Longest common subsequence of (indent, message), built from the end so
+    // the walk below can go forward.
+    let same =
+        |i: usize, j: usize| a[i].0.indent == b[j].0.indent && a[i].0.message == b[j].0.message;
+    let mut lengths = vec![vec![0u32; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lengths[i][j] = if same(i, j) {
+                lengths[i + 1][j + 1] + 1
+            } else {
+                lengths[i + 1][j].max(lengths[i][j + 1])
+            };
+        }
+    }
+
+    let mut changes = Vec::with_capacity(a.len().max(b.len()));
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        if same(i, j) {
+            changes.push(Change::Matched(MatchedLine {
+                indent: a[i].0.indent,
+                message: a[i].0.message.to_string(),
+                a: a[i].1,
+                b: b[j].1,
+            }));
+            i += 1;
+            j += 1;
+        } else if lengths[i][j + 1] <= lengths[i + 1][j] {
+            changes.push(Change::Missing(a[i].0.clone()));
+            i += 1;
+        } else {
+            changes.push(Change::Extra(b[j].0.clone()));
+            j += 1;
+        }
+    }
+    changes.extend(
+        a[i..]
+            .iter()
+            .map(|(event, _)| Change::Missing(event.clone())),
+    );
+    changes.extend(b[j..].iter().map(|(event, _)| Change::Extra(event.clone())));
+    Diff { changes }
+}
+
+/// Pair each event with how long it lasted, until the next event that is not
+/// nested under it.
+pub(crate) fn with_durations(events: Vec<Event>) -> Vec<(Event, Option<Duration>)> {
+    let durations = durations(&events);
+    events.into_iter().zip(durations).collect()
+}
+
+/// How long each event lasted, until the next event that is not nested
+/// under it.
+pub(crate) fn durations(events: &[Event]) -> Vec<Option<Duration>> {
+    let last = events.last().and_then(|event| event.elapsed);
+    events
+        .iter()
+        .enumerate()
+        .map(|(index, event)| {
+            let later = &events[index + 1..];
+            if later.is_empty() {
+                return None;
+            }
+            let end = later
+                .iter()
+                .find(|next| next.indent <= event.indent)
+                .map_or(last, |next| next.elapsed);
+            Some(end?.saturating_sub(event.elapsed?))
+        })
+        .collect()
+}
+
+/// The differences between two traces, in trace order.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Diff {
+    changes: Vec<Change>,
+}
+
+impl Diff {
+    /// Every line of both traces, matched up where possible
+    pub fn changes(&self) -> &[Change] {
+        &self.changes
+    }
+}
+
+/// How one line differs between two traces.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Change {
+    /// The line is in both traces
+    Matched(MatchedLine),
+    /// The line is only in the first trace
+    Missing(Event),
+    /// The line is only in the second trace
+    Extra(Event),
+}
+
+/// A line that both traces recorded.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MatchedLine {
+    /// How many scopes deep the line was recorded
+    pub indent: usize,
+    /// The line's message
+    pub message: String,
+    /// How long the line lasted in the first trace
+    pub a: Option<Duration>,
+    /// How long the line lasted in the second trace
+    pub b: Option<Duration>,
+}
+
+impl MatchedLine {
+    /// How many more microseconds the line took in the second trace. This is
+    /// negative when the second trace was faster.
+    pub fn delta_micros(&self) -> Option<i64> {
+        Some(self.b?.as_micros() as i64 - self.a?.as_micros() as i64)
+    }
+}
+
+/// Renders one line per change: matched lines with their timing delta,
+/// missing lines with `-` and extra lines with `+`.
+impl Display for Diff {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        for change in &self.changes {
+            match change {
+                Change::Matched(line) => {
+                    match line.delta_micros() {
+                        Some(delta) => write!(f, "  {:<10}", format!("{delta:+}µs"))?,
+                        None => write!(f, "  {:<10}", "")?,
+                    }
+                    writeln!(f, "{}{}", "  ".repeat(line.indent), line.message)?;
+                }
+                Change::Missing(event) => writeln!(
+                    f,
+                    "- {:<10}{}{}",
+                    "",
+                    "  ".repeat(event.indent),
+                    event.message
+                )?,
+                Change::Extra(event) => writeln!(
+                    f,
+                    "+ {:<10}{}{}",
+                    "",
+                    "  ".repeat(event.indent),
+                    event.message
+                )?,
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,86 @@
+use std::{
+    fmt::{Arguments, Write},
+    sync::TryLockError,
+};
+
+use slog::{Drain, Key, OwnedKVList, Record, Serializer, KV};
+
+use crate::log_bridge;
+
+/// A slog [`Drain`] that records into the detailer this thread is
+/// [capturing](log_bridge::capture) into, and otherwise logs to the drain it
+/// wraps, the way [`LogBridge`](log_bridge::LogBridge) does for `log`.
+///
+/// Records are recorded as `module: message key=value`. Records the
+/// detailer's level would leave out go to the wrapped drain, as do
+/// detailer's own records, so flushing through a
+/// [`SlogSink`](crate::SlogSink) does not capture a trace into itself.
+///
+/// ```
+/// use detailer::{log_bridge, new_detailer, DetailDrain, SyncDetailer};
+///
+/// let logger = slog::Logger::root(DetailDrain::new(slog::Discard), slog::o!());
+///
+/// let detailer = SyncDetailer::new(new_detailer!(Info, WithoutTiming));
+/// {
+///     let _capture = log_bridge::capture(&detailer);
+///     slog::info!(logger, "cache miss"; "user" => 7);
+/// }
+/// slog::info!(logger, "not captured");
+/// assert!(detailer.lock().peek().ends_with(": cache miss user=7\n"));
+/// ```
+pub struct DetailDrain<D> {
+    drain: D,
+}
+
+impl<D> DetailDrain<D> {
+    /// Capture records into detailers, and log the rest to `drain`.
+    pub fn new(drain: D) -> Self {
+        Self { drain }
+    }
+}
+
+impl<D: Drain<Ok = ()>> Drain for DetailDrain<D> {
+    type Ok = ();
+    type Err = D::Err;
+
+    fn log(&self, record: &Record, values: &OwnedKVList) -> Result<(), D::Err> {
+        let level = match record.level() {
+            slog::Level::Critical | slog::Level::Error => log::Level::Error,
+            slog::Level::Warning => log::Level::Warn,
+            slog::Level::Info => log::Level::Info,
+            slog::Level::Debug => log::Level::Debug,
+            slog::Level::Trace => log::Level::Trace,
+        };
+        let Some(detailer) =
+            log_bridge::current().filter(|_| !record.module().starts_with("detailer"))
+        else {
+            return self.drain.log(record, values);
+        };
+        // A locked detailer is flushing, maybe through this drain.
+        let mut detailer = match detailer.try_lock() {
+            Ok(detailer) => detailer,
+            Err(TryLockError::Poisoned(poisoned)) => poisoned.into_inner(),
+            Err(TryLockError::WouldBlock) => return self.drain.log(record, values),
+        };
+        if !detailer.records_level(level) {
+            return self.drain.log(record, values);
+        }
+        let mut message = format!("{}: {}", record.module(), record.msg());
+        let mut fields = Fields(&mut message);
+        let _ = record.kv().serialize(record, &mut fields);
+        let _ = values.serialize(record, &mut fields);
+        detailer.log(level, format_args!("{message}"));
+        Ok(())
+    }
+}
+
+/// Writes key-value pairs as ` key=value` after what's already written.
+struct Fields<'a>(&'a mut String);
+
+impl Serializer for Fields<'_> {
+    fn emit_arguments(&mut self, key: Key, value: &Arguments) -> slog::Result {
+        let _ = write!(self.0, " {key}={value}");
+        Ok(())
+    }
+}
@@ -0,0 +1,85 @@
+use core::fmt::Display;
+
+use crate::values::AttributeValues;
+
+/// A typed payload that can be recorded as a line with
+/// [`Detailer::event()`](crate::Detailer::event).
+///
+/// An event renders twice: as readable text, which is the line in the trace,
+/// and as fields, which [line callbacks](crate::Detailer::on_line) and
+/// [subscribers](crate::Detailer::subscribe) see as the line's
+/// [attributes](crate::RecordedLine::attributes). The same instrumentation
+/// then makes both a trace to read and data to parse.
+///
+/// ```rust
+/// use std::fmt::Formatter;
+///
+/// use detailer::{new_detailer, DetailEvent, EventFields};
+///
+/// struct CacheLookup<'a> {
+///     key: &'a str,
+///     hit: bool,
+/// }
+///
+/// impl DetailEvent for CacheLookup<'_> {
+///     fn render(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+///         let outcome = if self.hit { "hit" } else { "miss" };
+///         write!(f, "cache {outcome} for {}", self.key)
+///     }
+///
+///     fn fields(&self, fields: &mut EventFields) {
+///         fields.field("key", self.key);
+///         fields.field("hit", self.hit);
+///     }
+/// }
+///
+/// let mut detailer = new_detailer!(Info, WithoutTiming);
+/// detailer.on_line(|line| {
+///     if line.message.starts_with("cache") {
+///         assert_eq!(Some("false"), line.attributes.get("hit"));
+///     }
+/// });
+/// detailer.event(CacheLookup { key: "user:7", hit: false });
+/// assert_eq!("cache miss for user:7\n", detailer.peek());
+/// ```
+pub trait DetailEvent {
+    /// Write the event as readable text
+    fn render(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result;
+
+    /// Add the event's fields, for structured output
+    fn fields(&self, fields: &mut EventFields);
+
+    /// The level to record the event at. By default, info.
+    fn level(&self) -> log::Level {
+        log::Level::Info
+    }
+}
+
+/// Collects the fields of a [`DetailEvent`].
+///
+/// Values are formatted into the detailer's attribute buffer, or with the
+/// `arena` feature its bump arena, which is reused after each flush, so
+/// fields don't allocate once a detailer has warmed up.
+pub struct EventFields<'a> {
+    values: &'a mut AttributeValues,
+}
+
+impl<'a> EventFields<'a> {
+    pub(crate) fn new(values: &'a mut AttributeValues) -> Self {
+        Self { values }
+    }
+
+    /// Add a field, formatting its value with [`Display`]
+    pub fn field(&mut self, key: &'static str, value: impl Display) {
+        self.values.push(key, format_args!("{value}"));
+    }
+}
+
+/// Displays an event as its text rendering.
+pub(crate) struct EventText<'a, E>(pub(crate) &'a E);
+
+impl<E: DetailEvent> Display for EventText<'_, E> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        self.0.render(f)
+    }
+}
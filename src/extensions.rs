@@ -0,0 +1,41 @@
+use http::Extensions;
+
+use crate::SyncDetailer;
+
+/// Carry a request's detailer in the request's [`Extensions`], so that each
+/// middleware layer and the handler record into the same trace.
+///
+/// Extensions must be `Clone + Send + Sync`, which is what [`SyncDetailer`]
+/// is for. The detailer flushes when its last handle is dropped, usually
+/// along with the request.
+///
+/// ```
+/// use detailer::{detail, new_detailer, DetailerExtensions, SyncDetailer};
+///
+/// let mut request = http::Request::new(());
+/// request
+///     .extensions_mut()
+///     .insert_detailer(SyncDetailer::from(new_detailer!(Info, WithoutTiming)));
+///
+/// // ...in a later layer or handler
+/// let detailer = request.extensions().get_detailer().expect("inserted above");
+/// detail!(detailer, "authorized");
+/// assert_eq!("authorized\n", detailer.lock().peek());
+/// ```
+pub trait DetailerExtensions {
+    /// Set the request's detailer, replacing any it had.
+    fn insert_detailer(&mut self, detailer: SyncDetailer) -> Option<SyncDetailer>;
+
+    /// The request's detailer, if one was inserted.
+    fn get_detailer(&self) -> Option<&SyncDetailer>;
+}
+
+impl DetailerExtensions for Extensions {
+    fn insert_detailer(&mut self, detailer: SyncDetailer) -> Option<SyncDetailer> {
+        self.insert(detailer)
+    }
+
+    fn get_detailer(&self) -> Option<&SyncDetailer> {
+        self.get::<SyncDetailer>()
+    }
+}
@@ -0,0 +1,102 @@
+use std::{convert::Infallible, sync::Arc};
+
+use rocket::{
+    fairing::{Fairing, Info, Kind},
+    request::{FromRequest, Outcome},
+    Data, Request, Response,
+};
+
+use crate::{detail, Detailer, SyncDetailer};
+
+/// A Rocket fairing that gives each request a detailer of its own, in the
+/// request's local state.
+///
+/// The detailer records the request's method and URI when the request
+/// arrives, and the route that handled it and the response's status when
+/// the response is ready, then flushes. Handlers record into it by taking a
+/// [`SyncDetailer`] request guard.
+///
+/// ```
+/// use detailer::{detail, new_detailer, DetailFairing, SyncDetailer};
+/// use rocket::local::blocking::Client;
+///
+/// #[rocket::get("/users/<id>")]
+/// fn user(detailer: SyncDetailer, id: u32) -> &'static str {
+///     detail!(detailer, "looking up user {id}");
+///     "ok"
+/// }
+///
+/// let rocket = rocket::build()
+///     .attach(DetailFairing::new(|| {
+///         let mut detailer = new_detailer!(Info, WithoutTiming);
+///         detailer.on_flush(|trace| {
+///             assert_eq!(
+///                 "GET /users/7\nlooking up user 7\n/users/<id> 200 OK",
+///                 trace.text,
+///             )
+///         });
+///         detailer
+///     }))
+///     .mount("/", rocket::routes![user]);
+/// let client = Client::tracked(rocket).unwrap();
+/// assert_eq!("ok", client.get("/users/7").dispatch().into_string().unwrap());
+/// ```
+pub struct DetailFairing {
+    make: Arc<dyn Fn() -> Detailer + Send + Sync>,
+}
+
+impl DetailFairing {
+    /// Give each request a detailer from `make`.
+    pub fn new(make: impl Fn() -> Detailer + Send + Sync + 'static) -> Self {
+        Self {
+            make: Arc::new(make),
+        }
+    }
+}
+
+/// A request's detailer, in its local state.
+struct RequestDetailer(SyncDetailer);
+
+#[rocket::async_trait]
+impl Fairing for DetailFairing {
+    fn info(&self) -> Info {
+        Info {
+            name: "detailer",
+            kind: Kind::Request | Kind::Response,
+        }
+    }
+
+    async fn on_request(&self, request: &mut Request<'_>, _: &mut Data<'_>) {
+        let RequestDetailer(detailer) =
+            request.local_cache(|| RequestDetailer(SyncDetailer::new((self.make)())));
+        detail!(detailer, "{} {}", request.method(), request.uri());
+    }
+
+    async fn on_response<'r>(&self, request: &'r Request<'_>, response: &mut Response<'r>) {
+        let RequestDetailer(detailer) =
+            request.local_cache(|| RequestDetailer(SyncDetailer::new((self.make)())));
+        match request.route() {
+            Some(route) => {
+                detail!(detailer, "{} {}", route.uri, response.status());
+            }
+            None => {
+                detail!(detailer, "{}", response.status());
+            }
+        }
+        detailer.flush();
+    }
+}
+
+/// The request's detailer, from [`DetailFairing`]. Without the fairing,
+/// each request gets a default detailer that flushes when the request is
+/// dropped.
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for SyncDetailer {
+    type Error = Infallible;
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Infallible> {
+        let RequestDetailer(detailer) =
+            request.local_cache(|| RequestDetailer(SyncDetailer::default()));
+        Outcome::Success(detailer.clone())
+    }
+}
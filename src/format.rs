@@ -0,0 +1,87 @@
+use core::time::Duration;
+
+/// Decides how each line of a trace starts, in place of the built-in
+/// [`LineFormat`](crate::LineFormat)s, when it is set with
+/// [`Detailer::line_formatter()`](crate::Detailer::line_formatter).
+///
+/// The formatter writes everything before a line's message: its time, level
+/// and indentation, in whatever layout a team's log tooling expects. The
+/// message follows as it was recorded, so truncation, deduplication and
+/// continuation lines still work the same way.
+///
+/// Traces with a custom layout can't be read back with
+/// [`parse`](crate::parse) unless the layout starts with the elapsed
+/// microseconds the way [`LineFormat::Elapsed`](crate::LineFormat::Elapsed)
+/// does.
+///
+/// ```rust
+/// use std::fmt::Formatter;
+///
+/// use detailer::{detail, new_detailer, scope, LineFormatter, LineStart};
+///
+/// struct Bracketed;
+///
+/// impl LineFormatter for Bracketed {
+///     fn write_prefix(&self, f: &mut Formatter<'_>, line: &LineStart<'_>) -> std::fmt::Result {
+///         write!(f, "<{}> ", line.level)?;
+///         for _ in 0..line.indentation {
+///             f.write_str("| ")?;
+///         }
+///         Ok(())
+///     }
+/// }
+///
+/// let mut detailer = new_detailer!(Info, WithoutTiming);
+/// detailer.line_formatter(Bracketed);
+/// {
+///     let _scope = scope!(detailer, "checkout");
+///     detail!(detailer, "charged card");
+/// }
+/// assert_eq!("<INFO> checkout\n<INFO> | charged card\n", detailer.peek());
+/// ```
+pub trait LineFormatter: Send + Sync {
+    /// Write the start of a line, before its message
+    fn write_prefix(
+        &self,
+        f: &mut core::fmt::Formatter<'_>,
+        line: &LineStart<'_>,
+    ) -> core::fmt::Result;
+}
+
+/// What a [`LineFormatter`] knows about the line it starts.
+#[derive(Clone, Copy, Debug)]
+#[non_exhaustive]
+pub struct LineStart<'a> {
+    /// How long after the trace started the line was recorded, when the
+    /// detailer records timing
+    pub elapsed: Option<Duration>,
+    /// The line's level
+    pub level: log::Level,
+    /// How many scopes deep the line is
+    pub indentation: usize,
+    /// The line's own target, when it was logged with one
+    pub target: Option<&'a str>,
+}
+
+/// A [`LineFormatter`] from a closure that makes each line's prefix, for
+/// [`Detailer::prefix_with()`](crate::Detailer::prefix_with). Lines are
+/// indented after the prefix, two spaces per scope.
+pub(crate) struct PrefixWith<F>(pub(crate) F);
+
+impl<F, D> LineFormatter for PrefixWith<F>
+where
+    F: Fn(Option<Duration>, log::Level) -> D + Send + Sync,
+    D: core::fmt::Display,
+{
+    fn write_prefix(
+        &self,
+        f: &mut core::fmt::Formatter<'_>,
+        line: &LineStart<'_>,
+    ) -> core::fmt::Result {
+        write!(f, "{}", (self.0)(line.elapsed, line.level))?;
+        for _ in 0..line.indentation {
+            f.write_str("  ")?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,96 @@
+use core::{
+    ops::{Deref, DerefMut},
+    time::Duration,
+};
+
+use crate::Detailer;
+
+/// A detailer for game and simulation loops, which only outputs slow frames.
+///
+/// Record each frame's detail as usual, then call
+/// [`end_frame()`](Self::end_frame). Frames that finish within the budget are
+/// thrown away, and frames that blow it are flushed whole, so a 60 fps loop
+/// only logs the frames that dropped below 60 fps. Every frame starts a new
+/// timeline.
+///
+/// Frames are timed with the detailer's clock, so a detailer without timing
+/// never outputs a frame.
+///
+/// ```rust
+/// use std::{
+///     sync::atomic::{AtomicU64, Ordering},
+///     time::Duration,
+/// };
+///
+/// use detailer::{detail, Detailer, FrameDetailer, TimingSetting};
+///
+/// static NOW: AtomicU64 = AtomicU64::new(0);
+/// fn now() -> u64 {
+///     NOW.load(Ordering::Relaxed)
+/// }
+///
+/// let detailer = Detailer::new(log::LevelFilter::Info, TimingSetting::WithClock(now), 4096);
+/// let mut frames = FrameDetailer::new(detailer, Duration::from_micros(16_667));
+///
+/// detail!(frames, "physics");
+/// NOW.fetch_add(9_000, Ordering::Relaxed);
+/// assert!(!frames.end_frame());
+///
+/// detail!(frames, "physics");
+/// NOW.fetch_add(25_000, Ordering::Relaxed);
+/// detail!(frames, "render");
+/// assert!(frames.end_frame());
+/// ```
+pub struct FrameDetailer {
+    detailer: Detailer,
+    budget: Duration,
+}
+
+impl FrameDetailer {
+    /// Output only the frames that take longer than `budget`.
+    pub fn new(detailer: Detailer, budget: Duration) -> Self {
+        Self { detailer, budget }
+    }
+
+    /// Finish the current frame: flush it if it took longer than the budget
+    /// and discard it otherwise, then start the next frame. Returns whether
+    /// the frame was flushed.
+    pub fn end_frame(&mut self) -> bool {
+        let over_budget = self
+            .detailer
+            .elapsed_micros()
+            .is_some_and(|elapsed| self.budget < Duration::from_micros(elapsed));
+        if over_budget {
+            self.detailer.flush();
+        } else {
+            self.detailer.reset();
+        }
+        over_budget
+    }
+
+    /// Change the frame budget.
+    pub fn budget(&mut self, budget: Duration) {
+        self.budget = budget;
+    }
+}
+
+impl Deref for FrameDetailer {
+    type Target = Detailer;
+
+    fn deref(&self) -> &Detailer {
+        &self.detailer
+    }
+}
+
+impl DerefMut for FrameDetailer {
+    fn deref_mut(&mut self) -> &mut Detailer {
+        &mut self.detailer
+    }
+}
+
+/// An unfinished frame is not output.
+impl Drop for FrameDetailer {
+    fn drop(&mut self) {
+        self.detailer.reset();
+    }
+}
@@ -0,0 +1,105 @@
+//! Random trace and span IDs, without a dependency for them.
+//!
+//! IDs are lowercase hex, the lengths W3C trace context uses: 32 digits for a
+//! trace and 16 for a span. They come from a per-thread generator seeded from
+//! the standard library's hash seed, which it draws from system entropy.
+//! They are unique enough to correlate traces, but not for secrets.
+//!
+//! With the `uuid` feature, trace IDs are version 7 UUIDs instead, like
+//! `0190163d-8694-739b-aea5-966c26f8ad91`, for systems that expect them.
+//! These sort by the millisecond they were made in.
+//!
+//! ```rust
+//! use detailer::{id, new_detailer};
+//!
+//! let mut detailer = new_detailer!();
+//! detailer.trace_id(id::trace_id());
+//!
+//! let trace_id = id::trace_id();
+//! if cfg!(feature = "uuid") {
+//!     assert_eq!(36, trace_id.len());
+//!     assert_eq!(Some('7'), trace_id.chars().nth(14));
+//! } else {
+//!     assert_eq!(32, trace_id.len());
+//!     assert!(trace_id.chars().all(|c| c.is_ascii_hexdigit()));
+//! }
+//! assert_ne!(trace_id, id::trace_id());
+//! assert_eq!(16, id::span_id().len());
+//! ```
+
+use std::{
+    cell::Cell,
+    collections::hash_map::RandomState,
+    fmt::Write,
+    hash::{BuildHasher, Hasher},
+};
+
+thread_local! {
+    static STATE: Cell<u64> = Cell::new(seed());
+}
+
+/// A new random 128 bit trace ID, as 32 hex digits.
+#[cfg(not(feature = "uuid"))]
+pub fn trace_id() -> String {
+    hex(&[next(), next()])
+}
+
+/// A new version 7 UUID trace ID: 48 bits of unix milliseconds, then random
+/// bits around the version and variant.
+#[cfg(feature = "uuid")]
+pub fn trace_id() -> String {
+    let millis = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64;
+    let high = (millis << 16) | 0x7000 | (next() & 0x0fff);
+    let low = (next() & 0x3fff_ffff_ffff_ffff) | 0x8000_0000_0000_0000;
+    let mut id = hex(&[high, low]);
+    for dash in [8, 13, 18, 23] {
+        id.insert(dash, '-');
+    }
+    id
+}
+
+/// A new random 64 bit span ID, as 16 hex digits.
+pub fn span_id() -> String {
+    hex(&[next()])
+}
+
+fn hex(words: &[u64]) -> String {
+    let mut id = String::with_capacity(words.len() * 16);
+    for word in words {
+        let _ = write!(id, "{word:016x}");
+    }
+    id
+}
+
+fn seed() -> u64 {
+    // Each RandomState is keyed from system entropy, and the thread keeps the
+    // seeds of threads that start at the same moment apart.
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u64(
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos() as u64,
+    );
+    hasher.write(format!("{:?}", std::thread::current().id()).as_bytes());
+    hasher.finish()
+}
+
+/// The next number from this thread's splitmix64 generator. Zero is skipped,
+/// since an all zero ID is invalid in W3C trace context.
+fn next() -> u64 {
+    STATE.with(|state| loop {
+        let next = state.get().wrapping_add(0x9e37_79b9_7f4a_7c15);
+        state.set(next);
+        let mut z = next;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^= z >> 31;
+        if z != 0 {
+            return z;
+        }
+    })
+}
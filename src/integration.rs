@@ -0,0 +1,120 @@
+use sentry_core::{
+    protocol::{Breadcrumb, Event, Level, Value},
+    ClientOptions, Integration,
+};
+
+use crate::{log_bridge, parse};
+
+/// A Sentry [`Integration`] that adds what the current detailer has recorded
+/// to each error event, so that a crash report comes with the workflow that
+/// led up to it.
+///
+/// The current detailer is the one this thread is
+/// [capturing](log_bridge::capture) into. When an error is captured, each
+/// line it recorded so far becomes a breadcrumb, and the rendered trace is
+/// attached to the event as its `detailer_trace` extra value.
+///
+/// ```
+/// use detailer::{detail, log_bridge, new_detailer, DetailIntegration, SyncDetailer};
+/// use sentry_core::ClientOptions;
+///
+/// let detailer = SyncDetailer::new(new_detailer!(Info, WithoutTiming));
+/// let events = sentry_core::test::with_captured_events_options(
+///     || {
+///         let _capture = log_bridge::capture(&detailer);
+///         detail!(detailer, "loading profile");
+///         detail!(detailer, "cache miss");
+///         let error = std::io::Error::other("connection reset");
+///         sentry_core::capture_error(&error);
+///     },
+///     ClientOptions::new().add_integration(DetailIntegration::default()),
+/// );
+///
+/// let breadcrumbs: Vec<_> = events[0]
+///     .breadcrumbs
+///     .values
+///     .iter()
+///     .filter_map(|breadcrumb| breadcrumb.message.as_deref())
+///     .collect();
+/// assert_eq!(vec!["loading profile", "cache miss"], breadcrumbs);
+/// assert_eq!(
+///     Some(&"loading profile\ncache miss\n".into()),
+///     events[0].extra.get("detailer_trace"),
+/// );
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct DetailIntegration {
+    breadcrumbs: bool,
+    trace: bool,
+}
+
+impl Default for DetailIntegration {
+    fn default() -> Self {
+        Self {
+            breadcrumbs: true,
+            trace: true,
+        }
+    }
+}
+
+impl DetailIntegration {
+    /// Add a breadcrumb for each recorded line, or not. They are added by
+    /// default.
+    pub fn breadcrumbs(mut self, enabled: bool) -> Self {
+        self.breadcrumbs = enabled;
+        self
+    }
+
+    /// Attach the rendered trace as the `detailer_trace` extra value, or not.
+    /// It is attached by default.
+    pub fn trace(mut self, enabled: bool) -> Self {
+        self.trace = enabled;
+        self
+    }
+}
+
+impl Integration for DetailIntegration {
+    fn name(&self) -> &'static str {
+        "detailer"
+    }
+
+    fn process_event(
+        &self,
+        mut event: Event<'static>,
+        _: &ClientOptions,
+    ) -> Option<Event<'static>> {
+        if event.exception.values.is_empty() && event.level < Level::Error {
+            return Some(event);
+        }
+        let Some(detailer) = log_bridge::current() else {
+            return Some(event);
+        };
+        // An error captured while the detailer is locked, like from a flush
+        // callback, goes without the trace rather than deadlocking.
+        let Ok(snapshot) = detailer.try_lock().map(|detailer| detailer.snapshot()) else {
+            return Some(event);
+        };
+        if self.breadcrumbs {
+            for line in parse::events(snapshot.as_str()) {
+                event.breadcrumbs.values.push(Breadcrumb {
+                    category: Some("detail".into()),
+                    message: Some(format!("{}{}", "  ".repeat(line.indent), line.message)),
+                    data: line
+                        .elapsed
+                        .map(|elapsed| {
+                            ("elapsed_us".into(), Value::from(elapsed.as_micros() as u64))
+                        })
+                        .into_iter()
+                        .collect(),
+                    ..Default::default()
+                });
+            }
+        }
+        if self.trace {
+            event
+                .extra
+                .insert("detailer_trace".into(), snapshot.as_str().into());
+        }
+        Some(event)
+    }
+}
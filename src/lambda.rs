@@ -0,0 +1,124 @@
+//! A wrapper for [AWS Lambda](https://docs.rs/lambda_runtime) handlers that
+//! gives each invocation a detailer of its own.
+//!
+//! Lambda may freeze the execution environment as soon as an invocation
+//! returns, so the detailer is flushed before the result is handed back,
+//! instead of whenever it happens to be dropped.
+//!
+//! ```
+//! use std::{
+//!     future::Future,
+//!     pin::pin,
+//!     sync::Arc,
+//!     task::{Context, Poll, Wake, Waker},
+//!     time::{Duration, SystemTime, UNIX_EPOCH},
+//! };
+//!
+//! use detailer::{detail, new_detailer, SyncDetailer};
+//! use lambda_runtime::LambdaEvent;
+//!
+//! async fn handler(detailer: SyncDetailer, event: LambdaEvent<u32>) -> Result<u32, String> {
+//!     detail!(detailer, "doubling {}", event.payload);
+//!     Ok(2 * event.payload)
+//! }
+//!
+//! let handler = detailer::lambda::detailed(
+//!     || {
+//!         let mut detailer = new_detailer!(Info, WithoutTiming);
+//!         detailer.on_flush(|trace| {
+//!             assert!(trace.text.starts_with("request 8f5c1a with "));
+//!             assert!(trace.text.ends_with(" remaining\ndoubling 21\ncomplete"));
+//!         });
+//!         detailer
+//!     },
+//!     handler,
+//! );
+//! // A real function would run it with lambda_runtime::run(service_fn(handler)).
+//! let mut context = lambda_runtime::Context::default();
+//! context.request_id = "8f5c1a".into();
+//! let deadline = SystemTime::now() + Duration::from_secs(3);
+//! context.deadline = deadline.duration_since(UNIX_EPOCH).unwrap().as_millis() as u64;
+//! let invocation = handler(LambdaEvent::new(21, context));
+//!
+//! struct Noop;
+//! impl Wake for Noop {
+//!     fn wake(self: Arc<Self>) {}
+//! }
+//! let waker = Waker::from(Arc::new(Noop));
+//! let poll = pin!(invocation).poll(&mut Context::from_waker(&waker));
+//! assert_eq!(Poll::Ready(Ok(42)), poll);
+//! ```
+
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+    time::SystemTime,
+};
+
+use lambda_runtime::LambdaEvent;
+
+use crate::{detail, detail_at, Detailer, SyncDetailer};
+
+/// Wrap `handler` so that each invocation gets a detailer from `make`, with
+/// the invocation's request ID and remaining time recorded. The detailer is
+/// flushed before the invocation's result is returned.
+pub fn detailed<M, H, P, F, T, E>(
+    make: M,
+    handler: H,
+) -> impl Fn(LambdaEvent<P>) -> DetailedInvocation<F>
+where
+    M: Fn() -> Detailer,
+    H: Fn(SyncDetailer, LambdaEvent<P>) -> F,
+    F: Future<Output = Result<T, E>>,
+{
+    move |event| {
+        let detailer = SyncDetailer::new(make());
+        let remaining = event
+            .context
+            .deadline()
+            .duration_since(SystemTime::now())
+            .unwrap_or_default();
+        detail!(
+            detailer,
+            "request {} with {}ms remaining",
+            event.context.request_id,
+            remaining.as_millis()
+        );
+        DetailedInvocation {
+            future: Box::pin(handler(detailer.clone(), event)),
+            detailer,
+        }
+    }
+}
+
+/// An invocation of a handler wrapped with [`detailed()`], which flushes the
+/// invocation's detailer when it is ready.
+pub struct DetailedInvocation<F> {
+    future: Pin<Box<F>>,
+    detailer: SyncDetailer,
+}
+
+impl<F, T, E> Future for DetailedInvocation<F>
+where
+    F: Future<Output = Result<T, E>>,
+{
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<F::Output> {
+        let this = self.get_mut();
+        let poll = this.future.as_mut().poll(cx);
+        if let Poll::Ready(result) = &poll {
+            match result {
+                Ok(_) => {
+                    detail!(this.detailer, "complete");
+                }
+                Err(_) => {
+                    detail_at!(this.detailer, Error, "failed");
+                }
+            }
+            this.detailer.flush();
+        }
+        poll
+    }
+}
@@ -0,0 +1,144 @@
+use std::fmt::{Debug, Write};
+
+use tracing::{
+    field::{Field, Visit},
+    span, Event, Span, Subscriber,
+};
+use tracing_subscriber::{layer::Context, registry::LookupSpan, Layer};
+
+use crate::{ScopeId, SyncDetailer};
+
+/// A tracing-subscriber [`Layer`] that records the events and spans inside a
+/// request into that request's detailer.
+///
+/// [Attach](Self::attach) the request's detailer to the span the request
+/// runs in. Events recorded in that span, or in any span nested under it,
+/// become lines of the request's trace, and each nested span becomes a
+/// [scope](crate::Detailer::enter_scope) that its events are indented under.
+/// The scopes stay straight when spans on different tasks interleave.
+///
+/// ```
+/// use detailer::{new_detailer, DetailLayer, SyncDetailer};
+/// use tracing_subscriber::prelude::*;
+///
+/// let subscriber = tracing_subscriber::registry().with(DetailLayer);
+/// tracing::subscriber::with_default(subscriber, || {
+///     let mut detailer = new_detailer!(Info, WithoutTiming);
+///     detailer.on_flush(|trace| {
+///         assert_eq!(
+///             "authenticated user=7\nquery table=users\n  3 rows\nresponded",
+///             trace.text,
+///         )
+///     });
+///     let detailer = SyncDetailer::from(detailer);
+///
+///     let request = tracing::info_span!("request");
+///     DetailLayer::attach(&request, detailer.clone());
+///     request.in_scope(|| {
+///         tracing::info!(user = 7, "authenticated");
+///         tracing::info_span!("query", table = "users").in_scope(|| tracing::info!("3 rows"));
+///         tracing::debug!("below the detailer's level");
+///         tracing::info!("responded");
+///     });
+///     detailer.flush();
+/// });
+/// ```
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DetailLayer;
+
+impl DetailLayer {
+    /// Record what happens in `span`, and in the spans nested under it, into
+    /// `detailer`. This does nothing unless the span is recorded by a
+    /// registry with a `DetailLayer`.
+    pub fn attach(span: &Span, detailer: SyncDetailer) {
+        span.with_subscriber(|(id, dispatch)| {
+            if let Some(span) = dispatch
+                .downcast_ref::<tracing_subscriber::Registry>()
+                .and_then(|registry| registry.span(id))
+            {
+                span.extensions_mut().insert(detailer);
+            }
+        });
+    }
+}
+
+impl<S: Subscriber + for<'a> LookupSpan<'a>> Layer<S> for DetailLayer {
+    fn on_new_span(&self, attrs: &span::Attributes<'_>, id: &span::Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else {
+            return;
+        };
+        let Some(parent) = span.parent() else {
+            return;
+        };
+        let (detailer, parent_scope) = {
+            let extensions = parent.extensions();
+            let Some(detailer) = extensions.get::<SyncDetailer>().cloned() else {
+                return;
+            };
+            (detailer, extensions.get::<ScopeId>().copied())
+        };
+        let mut name = String::from(attrs.metadata().name());
+        attrs.record(&mut Fields(&mut name));
+        let name = format_args!("{name}");
+        let scope = match parent_scope {
+            Some(parent_scope) => detailer.enter_scope_in(parent_scope, name),
+            None => detailer.enter_scope(name),
+        };
+        let mut extensions = span.extensions_mut();
+        extensions.insert(detailer);
+        extensions.insert(scope);
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        let Some(span) = ctx.event_span(event) else {
+            return;
+        };
+        let extensions = span.extensions();
+        let Some(detailer) = extensions.get::<SyncDetailer>() else {
+            return;
+        };
+        let level = match *event.metadata().level() {
+            tracing::Level::ERROR => log::Level::Error,
+            tracing::Level::WARN => log::Level::Warn,
+            tracing::Level::INFO => log::Level::Info,
+            tracing::Level::DEBUG => log::Level::Debug,
+            tracing::Level::TRACE => log::Level::Trace,
+        };
+        let mut message = String::new();
+        event.record(&mut Fields(&mut message));
+        match extensions.get::<ScopeId>() {
+            Some(scope) => detailer.log_in(*scope, level, format_args!("{message}")),
+            None => detailer.log(level, format_args!("{message}")),
+        }
+    }
+}
+
+/// Writes an event's or span's fields after what's already written, the
+/// message first as it is and the others as `name=value`.
+struct Fields<'a>(&'a mut String);
+
+impl Fields<'_> {
+    fn separate(&mut self) {
+        if !self.0.is_empty() {
+            self.0.push(' ');
+        }
+    }
+}
+
+impl Visit for Fields<'_> {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.separate();
+        let _ = match field.name() {
+            "message" => write!(self.0, "{value}"),
+            name => write!(self.0, "{name}={value}"),
+        };
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        self.separate();
+        let _ = match field.name() {
+            "message" => write!(self.0, "{value:?}"),
+            name => write!(self.0, "{name}={value:?}"),
+        };
+    }
+}
@@ -1,6 +1,7 @@
 //! A dynamic, simple workflow trace logger.
 //!
-//! [`Detailer`] is a log recording tool, depending only on std and log.
+//! [`Detailer`] is a log recording tool, depending only on std and log by
+//! default.
 //! It prioritizes ease of use and low overhead, particularly when disabled.
 //!
 //! # Examples
@@ -47,7 +48,151 @@
 //! You might want to `detail!()` the backend client a little more in this example. It's masking the bulk of your wall
 //! clock query request time. If 813µs is good for your backend, however, maybe this is just a good trace result.
 
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+#[deny(missing_docs)]
+mod aggregate;
+#[deny(missing_docs)]
+pub mod binary;
+#[deny(missing_docs)]
+mod clock;
+#[cfg(feature = "compat")]
+#[deny(missing_docs)]
+pub mod compat;
+#[cfg(feature = "gzip")]
+#[deny(missing_docs)]
+mod compress;
+#[cfg(feature = "std")]
+#[deny(missing_docs)]
+pub mod debug;
 #[deny(missing_docs)]
 mod detailer;
+#[deny(missing_docs)]
+mod diff;
+#[cfg(feature = "slog")]
+#[deny(missing_docs)]
+mod drain;
+#[deny(missing_docs)]
+mod event;
+#[cfg(feature = "http")]
+#[deny(missing_docs)]
+mod extensions;
+#[cfg(feature = "rocket")]
+#[deny(missing_docs)]
+mod fairing;
+#[deny(missing_docs)]
+mod format;
+#[deny(missing_docs)]
+mod frame;
+#[cfg(feature = "std")]
+#[deny(missing_docs)]
+pub mod id;
+#[cfg(feature = "sentry")]
+#[deny(missing_docs)]
+mod integration;
+#[cfg(feature = "std")]
+#[deny(missing_docs)]
+mod json;
+#[cfg(feature = "lambda")]
+#[deny(missing_docs)]
+pub mod lambda;
+#[cfg(feature = "tracing")]
+#[deny(missing_docs)]
+mod layer;
+#[deny(missing_docs)]
+mod line;
+#[cfg(feature = "std")]
+#[deny(missing_docs)]
+pub mod log_bridge;
+#[cfg(feature = "rayon")]
+#[deny(missing_docs)]
+mod parallel;
+#[deny(missing_docs)]
+pub mod parse;
+#[cfg(feature = "std")]
+#[deny(missing_docs)]
+mod process;
+#[cfg(feature = "std")]
+#[deny(missing_docs)]
+pub mod registry;
+#[cfg(feature = "hyper")]
+#[deny(missing_docs)]
+mod service;
+#[deny(missing_docs)]
+mod sink;
+#[deny(missing_docs)]
+mod small;
+#[deny(missing_docs)]
+mod snapshot;
+#[cfg(feature = "std")]
+#[deny(missing_docs)]
+mod spawn;
+#[cfg(feature = "std")]
+#[deny(missing_docs)]
+mod subscribe;
+#[cfg(feature = "std")]
+#[deny(missing_docs)]
+mod sync_detailer;
+#[cfg(feature = "std")]
+#[deny(missing_docs)]
+mod task;
+#[deny(missing_docs)]
+mod values;
+#[cfg(feature = "warp")]
+#[deny(missing_docs)]
+pub mod warp;
 
-pub use detailer::{DetailScopeGuard, Detailer, TimingSetting};
+pub use aggregate::{Aggregator, Summary};
+pub use clock::Clock;
+pub use detailer::{
+    ContextGuard, ContinuationLines, DetailScopeGuard, Detailer, ElapsedFormat, Lane, LaneLayout,
+    LineFormat, ScopeId, SeverityMarkers, TimingSetting,
+};
+pub use diff::{diff, Change, Diff, MatchedLine};
+#[cfg(feature = "slog")]
+pub use drain::DetailDrain;
+pub use event::{DetailEvent, EventFields};
+#[cfg(feature = "http")]
+pub use extensions::DetailerExtensions;
+#[cfg(feature = "rocket")]
+pub use fairing::DetailFairing;
+pub use format::{LineFormatter, LineStart};
+pub use frame::FrameDetailer;
+#[cfg(feature = "sentry")]
+pub use integration::DetailIntegration;
+#[cfg(feature = "tracing")]
+pub use layer::DetailLayer;
+pub use line::{Attributes, RecordedLine};
+#[cfg(feature = "hyper")]
+pub use service::{DetailService, DetailedResponse};
+#[cfg(feature = "tokio")]
+pub use sink::AsyncFileSink;
+#[cfg(feature = "datadog")]
+pub use sink::DatadogSink;
+#[cfg(feature = "emf")]
+pub use sink::EmfSink;
+#[cfg(all(feature = "journald", unix))]
+pub use sink::JournaldSink;
+#[cfg(feature = "metrics")]
+pub use sink::MetricsSink;
+#[cfg(feature = "slog")]
+pub use sink::SlogSink;
+#[cfg(feature = "xray")]
+pub use sink::XRaySink;
+#[cfg(feature = "std")]
+pub use sink::{BackgroundSink, ChannelSink, FileSink, OwnedTrace, WriterFlush, WriterSink};
+#[cfg(feature = "syslog")]
+pub use sink::{Facility, SyslogSink};
+pub use sink::{FlushError, FlushErrorPolicy, FlushedTrace, LogSink, NullSink, RecordLimit, Sink};
+pub use small::SmallString;
+pub use snapshot::Snapshot;
+#[cfg(feature = "std")]
+pub use spawn::SpawnedScope;
+#[cfg(feature = "std")]
+pub use subscribe::{LineEvent, Subscription};
+#[cfg(feature = "std")]
+pub use sync_detailer::SyncDetailer;
+#[cfg(feature = "std")]
+pub use task::DetailedFuture;
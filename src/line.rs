@@ -0,0 +1,70 @@
+use core::time::Duration;
+
+use crate::values::AttributeValues;
+
+/// A line, as it is recorded.
+#[non_exhaustive]
+pub struct RecordedLine<'a> {
+    /// The level the line was logged at
+    pub level: log::Level,
+    /// Time since the trace started, if the detailer records timing
+    pub elapsed: Option<Duration>,
+    /// Time since the unix epoch, if the detailer
+    /// [stamps lines](crate::Detailer::wall_clock_timestamps) with it
+    pub since_epoch: Option<Duration>,
+    /// How many scopes deep the line was recorded
+    pub indent: usize,
+    /// The line's message
+    pub message: &'a str,
+    /// The line's [target](crate::Detailer::log_target), if it has its own
+    pub target: Option<&'static str>,
+    /// The [lane](crate::Detailer::lane) the line was recorded in, if any
+    pub lane: Option<&'static str>,
+    /// The attributes of a scope's line, or the fields of an
+    /// [event](crate::Detailer::event)
+    pub attributes: Attributes<'a>,
+}
+
+/// The keys and formatted values of a scope's attributes.
+///
+/// The values are kept in one buffer for the whole trace, or with the
+/// `arena` feature in a bump arena, which is reused after each flush, so
+/// attributes don't allocate once a detailer has warmed up.
+#[derive(Clone, Copy)]
+pub struct Attributes<'a> {
+    values: &'a AttributeValues,
+    start: usize,
+    end: usize,
+}
+
+impl<'a> Attributes<'a> {
+    pub(crate) fn new(values: &'a AttributeValues, entries: core::ops::Range<usize>) -> Self {
+        Self {
+            values,
+            start: entries.start,
+            end: entries.end,
+        }
+    }
+
+    /// The attributes, as keys and values, in the order they were given
+    pub fn iter(&self) -> impl Iterator<Item = (&'static str, &'a str)> + 'a {
+        self.values.iter(self.start..self.end)
+    }
+
+    /// The value of the first attribute named `key`
+    pub fn get(&self, key: &str) -> Option<&'a str> {
+        self.iter()
+            .find(|(name, _)| *name == key)
+            .map(|(_, value)| value)
+    }
+
+    /// How many attributes there are
+    pub fn len(&self) -> usize {
+        self.end - self.start
+    }
+
+    /// Whether there are no attributes
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+}
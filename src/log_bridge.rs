@@ -0,0 +1,133 @@
+//! Capture `log` records from dependencies into the trace they belong to.
+//!
+//! Install a [`LogBridge`] as the logger, wrapping the logger the
+//! application would otherwise use. While a thread is
+//! [capturing](capture) into a detailer, records logged on that thread are
+//! recorded into the detailer instead, indented under its current scope.
+//! Everything else goes to the wrapped logger as usual.
+//!
+//! ```rust
+//! use detailer::{log_bridge::{self, LogBridge}, new_detailer, scope, SyncDetailer};
+//!
+//! struct Stderr;
+//! impl log::Log for Stderr {
+//!     fn enabled(&self, _: &log::Metadata) -> bool {
+//!         true
+//!     }
+//!     fn log(&self, record: &log::Record) {
+//!         eprintln!("{}", record.args());
+//!     }
+//!     fn flush(&self) {}
+//! }
+//!
+//! log::set_boxed_logger(Box::new(LogBridge::new(Box::new(Stderr)))).unwrap();
+//! log::set_max_level(log::LevelFilter::Trace);
+//!
+//! let detailer = SyncDetailer::new(new_detailer!(Info, WithoutTiming));
+//! {
+//!     let _capture = log_bridge::capture(&detailer);
+//!     let _scope = scope!(detailer, "loading profile");
+//!     log::info!(target: "cache", "miss for user 7");
+//! }
+//! log::info!("not captured");
+//! assert_eq!("loading profile\n  cache: miss for user 7\n", detailer.lock().peek());
+//! ```
+
+use std::{cell::RefCell, sync::TryLockError};
+
+use crate::SyncDetailer;
+
+thread_local! {
+    static CAPTURING: RefCell<Option<SyncDetailer>> = const { RefCell::new(None) };
+}
+
+/// A logger that records into the detailer the current thread is capturing
+/// into, and otherwise logs to the logger it wraps.
+///
+/// Records a capturing detailer's level would leave out go to the wrapped
+/// logger, as do detailer's own records, so flushed traces are not captured
+/// into each other.
+pub struct LogBridge {
+    previous: Box<dyn log::Log>,
+}
+
+impl LogBridge {
+    /// Forward records that are not captured to `previous`.
+    pub fn new(previous: Box<dyn log::Log>) -> Self {
+        Self { previous }
+    }
+}
+
+impl log::Log for LogBridge {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        CAPTURING.with(|capturing| capturing.borrow().is_some()) || self.previous.enabled(metadata)
+    }
+
+    fn log(&self, record: &log::Record) {
+        let captured = !record.target().starts_with("detailer")
+            && CAPTURING.with(|capturing| {
+                let Some(detailer) = &*capturing.borrow() else {
+                    return false;
+                };
+                // A locked detailer is logging itself, like a truncation warning.
+                let mut detailer = match detailer.try_lock() {
+                    Ok(detailer) => detailer,
+                    Err(TryLockError::Poisoned(poisoned)) => poisoned.into_inner(),
+                    Err(TryLockError::WouldBlock) => return false,
+                };
+                if !detailer.records_level(record.level()) {
+                    return false;
+                }
+                detailer.log(
+                    record.level(),
+                    format_args!("{}: {}", record.target(), record.args()),
+                );
+                true
+            });
+        if !captured {
+            self.previous.log(record);
+        }
+    }
+
+    fn flush(&self) {
+        self.previous.flush()
+    }
+}
+
+/// Capture this thread's `log` records into `detailer` until the returned
+/// guard is dropped. Captures nest: dropping the guard goes back to the
+/// capture before it.
+pub fn capture(detailer: &SyncDetailer) -> Capture {
+    let previous = CAPTURING.with(|capturing| capturing.replace(Some(detailer.clone())));
+    Capture { previous }
+}
+
+/// The detailer this thread is capturing into, if any. Adapters for other
+/// logging frameworks can use this to record into the same detailer.
+///
+/// ```rust
+/// use detailer::{log_bridge, new_detailer, SyncDetailer};
+///
+/// let detailer = SyncDetailer::new(new_detailer!(Info, WithoutTiming));
+/// assert!(log_bridge::current().is_none());
+/// let _capture = log_bridge::capture(&detailer);
+/// if let Some(current) = log_bridge::current() {
+///     current.log(log::Level::Info, format_args!("from another framework"));
+/// }
+/// assert_eq!("from another framework\n", detailer.lock().peek());
+/// ```
+pub fn current() -> Option<SyncDetailer> {
+    CAPTURING.with(|capturing| capturing.borrow().clone())
+}
+
+/// Keeps capturing `log` records into a detailer, from [`capture()`].
+pub struct Capture {
+    previous: Option<SyncDetailer>,
+}
+
+impl Drop for Capture {
+    fn drop(&mut self) {
+        let previous = self.previous.take();
+        CAPTURING.with(|capturing| *capturing.borrow_mut() = previous);
+    }
+}
@@ -0,0 +1,53 @@
+use std::fmt::Arguments;
+
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
+
+use crate::Detailer;
+
+impl Detailer {
+    /// Map `items` in parallel with rayon, giving each task a child detailer
+    /// to record into.
+    ///
+    /// The scope's line is recorded first, and when every task is done their
+    /// lines are merged under it, one task after another in the order of
+    /// `items`. Results come back in the same order, like `collect()`.
+    ///
+    /// ```
+    /// use detailer::{detail, new_detailer};
+    ///
+    /// let mut detailer = new_detailer!(Info, WithoutTiming);
+    /// let sizes = detailer.par_map(format_args!("compressing"), vec![30, 10], |child, block| {
+    ///     detail!(child, "block of {block}");
+    ///     block / 10
+    /// });
+    ///
+    /// assert_eq!(vec![3, 1], sizes);
+    /// assert_eq!(
+    ///     "compressing\n  block of 30\n  block of 10\n",
+    ///     detailer.peek(),
+    /// );
+    /// ```
+    pub fn par_map<I, F, R>(&mut self, scope_name: Arguments, items: I, map: F) -> Vec<R>
+    where
+        I: IntoParallelIterator,
+        F: Fn(&mut Detailer, I::Item) -> R + Sync + Send,
+        R: Send,
+    {
+        let scope = self.enter_scope(scope_name);
+        let parent = &*self;
+        let done: Vec<(Detailer, R)> = items
+            .into_par_iter()
+            .map(|item| {
+                let mut child = parent.child();
+                let value = map(&mut child, item);
+                (child, value)
+            })
+            .collect();
+        done.into_iter()
+            .map(|(child, value)| {
+                self.append_in(scope, child);
+                value
+            })
+            .collect()
+    }
+}
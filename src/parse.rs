@@ -0,0 +1,270 @@
+//! Read rendered traces back into structured events.
+//!
+//! Log analysis tooling and tests can use this to consume traces that were
+//! already emitted, without matching the text format by hand.
+//!
+//! ```rust
+//! use std::time::Duration;
+//!
+//! use detailer::parse::{self, Event};
+//!
+//! let trace = "\
+//! 14     running query command
+//! 16     authenticating
+//! 18       authorization header parsed
+//! 837    request complete";
+//!
+//! let events = parse::events(trace);
+//! assert_eq!(4, events.len());
+//! assert_eq!(
+//!     Event {
+//!         elapsed: Some(Duration::from_micros(18)),
+//!         indent: 1,
+//!         message: "authorization header parsed".into(),
+//!     },
+//!     events[2],
+//! );
+//! ```
+
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::time::Duration;
+
+use crate::{diff::durations, SmallString};
+
+/// What starts a continuation line that repeats its record's prefix.
+pub(crate) const CONTINUATION_MARKER: &str = "… ";
+
+/// The default width of the elapsed column, not counting the separating
+/// space.
+pub(crate) const ELAPSED_WIDTH: usize = 6;
+
+/// One recorded line of a trace.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Event {
+    /// Time since the trace started, if the trace was recorded with timing
+    pub elapsed: Option<Duration>,
+    /// How many scopes deep the line was recorded
+    pub indent: usize,
+    /// The message, with the continuation lines of multi-line messages
+    /// joined by `\n`
+    pub message: SmallString,
+}
+
+/// Parse a trace, as it was flushed, into its events.
+///
+/// When the trace was recorded with timing, lines without an elapsed prefix
+/// continue the message of the line before them. Without timing, there is no
+/// way to tell a new line from a continuation, so every line is an event.
+///
+/// ```rust
+/// use detailer::{detail, new_detailer, parse, scope};
+///
+/// let mut detailer = new_detailer!(Info, WithoutTiming);
+/// detail!(detailer, "start");
+/// {
+///     let _scope = scope!(detailer, "work");
+///     detail!(detailer, "step");
+/// }
+///
+/// let events = parse::events(detailer.peek());
+/// let summary: Vec<_> = events
+///     .iter()
+///     .map(|event| (event.indent, event.message.as_str()))
+///     .collect();
+/// assert_eq!(vec![(0, "start"), (0, "work"), (1, "step")], summary);
+/// ```
+pub fn events(trace: &str) -> Vec<Event> {
+    events_with_elapsed_width(trace, ELAPSED_WIDTH)
+}
+
+/// Parse a trace whose detailer set a custom
+/// [`elapsed_width()`](crate::Detailer::elapsed_width).
+///
+/// ```rust
+/// use detailer::parse;
+///
+/// let events = parse::events_with_elapsed_width("0         start\n12500000    step", 9);
+/// assert_eq!(1, events[1].indent);
+/// assert_eq!("step", events[1].message);
+/// ```
+pub fn events_with_elapsed_width(trace: &str, width: usize) -> Vec<Event> {
+    let timed = trace
+        .lines()
+        .find(|line| !line.is_empty())
+        .map(|line| split_elapsed(line, width).is_some())
+        .unwrap_or_default();
+
+    let mut events: Vec<Event> = Vec::new();
+    for line in trace.lines() {
+        let (elapsed, rest) = match split_elapsed(line, width) {
+            Some((elapsed, rest)) if timed => (Some(elapsed), rest),
+            _ => match events.last_mut() {
+                Some(event) if timed => {
+                    let indentation = "  ".repeat(event.indent);
+                    event.message.push('\n');
+                    event
+                        .message
+                        .push_str(line.strip_prefix(indentation.as_str()).unwrap_or(line));
+                    continue;
+                }
+                _ => (None, line),
+            },
+        };
+        let message = rest.trim_start_matches(' ');
+        let indent = (rest.len() - message.len()) / 2;
+        if let (Some(continued), Some(event)) =
+            (message.strip_prefix(CONTINUATION_MARKER), events.last_mut())
+        {
+            let indentation = "  ".repeat(event.indent);
+            event.message.push('\n');
+            event.message.push_str(
+                rest.strip_prefix(indentation.as_str())
+                    .and_then(|rest| rest.strip_prefix(CONTINUATION_MARKER))
+                    .unwrap_or(continued),
+            );
+            continue;
+        }
+        events.push(Event {
+            elapsed,
+            indent,
+            message: SmallString::from(message),
+        });
+    }
+    events
+}
+
+/// A scope of a trace: a line with lines nested under it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Scope {
+    /// The scope's line, with its attributes
+    pub name: String,
+    /// Time since the trace started when the scope began
+    pub start: Duration,
+    /// How long the scope lasted, until the next line not nested under it,
+    /// or the end of the trace
+    pub duration: Duration,
+    /// The index, among the scopes, of the scope this one is nested under
+    pub parent: Option<usize>,
+}
+
+/// Parse a trace, as it was flushed, into its scopes, in the order they
+/// began. Traces recorded without timing have no scopes to measure.
+///
+/// Exporters use this to turn a trace into spans for tracing systems.
+///
+/// ```rust
+/// use std::time::Duration;
+///
+/// use detailer::parse;
+///
+/// let trace = "\
+/// 0      request
+/// 2        authenticating
+/// 5          token checked
+/// 9        query
+/// 30         rows read
+/// 31     done";
+///
+/// let scopes = parse::scopes(trace);
+/// let names: Vec<&str> = scopes.iter().map(|scope| scope.name.as_str()).collect();
+/// assert_eq!(vec!["request", "authenticating", "query"], names);
+/// assert_eq!(Duration::from_micros(7), scopes[1].duration);
+/// assert_eq!(Some(0), scopes[2].parent);
+/// assert_eq!(Duration::from_micros(22), scopes[2].duration);
+/// ```
+pub fn scopes(trace: &str) -> Vec<Scope> {
+    scopes_in(&events(trace))
+}
+
+/// The scopes of a trace's events, like the
+/// [events](crate::FlushedTrace::events) a sink can ask a detailer for.
+/// See [`scopes()`].
+pub fn scopes_in(events: &[Event]) -> Vec<Scope> {
+    let durations = durations(events);
+    let mut scopes: Vec<Scope> = Vec::new();
+    // The indentation and index of each open scope
+    let mut open: Vec<(usize, usize)> = Vec::new();
+    for (index, (event, duration)) in events.iter().zip(&durations).enumerate() {
+        while open
+            .last()
+            .is_some_and(|(indent, _)| event.indent <= *indent)
+        {
+            open.pop();
+        }
+        let nests = events
+            .get(index + 1)
+            .is_some_and(|next| event.indent < next.indent);
+        let (Some(start), Some(duration), true) = (event.elapsed, duration, nests) else {
+            continue;
+        };
+        scopes.push(Scope {
+            name: event.message.to_string(),
+            start,
+            duration: *duration,
+            parent: open.last().map(|(_, parent)| *parent),
+        });
+        open.push((event.indent, scopes.len() - 1));
+    }
+    scopes
+}
+
+/// The text of traces a [`WriterSink`](crate::WriterSink) or
+/// [`FileSink`](crate::FileSink) wrote, with the ones it
+/// [compressed](crate::WriterSink::compress_over) decompressed in place.
+///
+/// Compressed traces are told apart from plain ones by the magic bytes
+/// they start with, so `bytes` can mix both. Text without compressed
+/// traces is borrowed as it is.
+///
+/// ```rust
+/// use detailer::{binary::DecodeError, parse};
+///
+/// assert_eq!("authenticating\n", parse::decode(b"authenticating\n").unwrap());
+/// assert_eq!(Err(DecodeError::Malformed), parse::decode(b"DTLZ\x40\0\0\0\x1f\x8b"));
+/// ```
+#[cfg(feature = "gzip")]
+pub fn decode(bytes: &[u8]) -> Result<alloc::borrow::Cow<'_, str>, crate::binary::DecodeError> {
+    use crate::{binary::DecodeError, compress};
+
+    if !bytes
+        .split(|byte| *byte == b'\n')
+        .any(|line| line.starts_with(compress::MAGIC))
+    {
+        return core::str::from_utf8(bytes)
+            .map(alloc::borrow::Cow::Borrowed)
+            .map_err(|_| DecodeError::Malformed);
+    }
+
+    let mut text = String::with_capacity(bytes.len() * 4);
+    let mut rest = bytes;
+    while !rest.is_empty() {
+        if let Some(after) = compress::unframe(rest, &mut text) {
+            rest = after.map_err(|_| DecodeError::Malformed)?;
+            continue;
+        }
+        let end = rest
+            .iter()
+            .position(|byte| *byte == b'\n')
+            .map_or(rest.len(), |newline| newline + 1);
+        let (line, after) = rest.split_at(end);
+        text.push_str(core::str::from_utf8(line).map_err(|_| DecodeError::Malformed)?);
+        rest = after;
+    }
+    Ok(alloc::borrow::Cow::Owned(text))
+}
+
+/// Split a line into its elapsed prefix and the indented message after it.
+fn split_elapsed(line: &str, width: usize) -> Option<(Duration, &str)> {
+    let digits = line.bytes().take_while(u8::is_ascii_digit).count();
+    if digits == 0 || line.as_bytes().get(digits) != Some(&b' ') {
+        return None;
+    }
+    let micros = line[..digits].parse().ok()?;
+    let rest = &line[digits..];
+    let padding = width.saturating_sub(digits) + 1;
+    let padded = rest.len() - rest.trim_start_matches(' ').len();
+    Some((Duration::from_micros(micros), &rest[padding.min(padded)..]))
+}
@@ -0,0 +1,56 @@
+use std::{rc::Rc, thread};
+
+/// The name of the host this process runs on, if it can be found.
+pub(crate) fn hostname() -> Option<String> {
+    #[cfg(unix)]
+    for file in ["/proc/sys/kernel/hostname", "/etc/hostname"] {
+        if let Ok(hostname) = std::fs::read_to_string(file) {
+            let hostname = hostname.trim();
+            if !hostname.is_empty() {
+                return Some(hostname.to_string());
+            }
+        }
+    }
+    ["HOSTNAME", "COMPUTERNAME"]
+        .into_iter()
+        .find_map(|variable| std::env::var(variable).ok())
+        .filter(|hostname| !hostname.is_empty())
+}
+
+thread_local! {
+    static THREAD_LABEL: Rc<str> = label(&thread::current()).into();
+}
+
+/// The current thread's name, or its ID when it has no name.
+pub(crate) fn thread_label() -> Rc<str> {
+    THREAD_LABEL.with(Rc::clone)
+}
+
+fn label(thread: &thread::Thread) -> String {
+    match thread.name() {
+        Some(name) => name.to_string(),
+        None => {
+            // Thread IDs only print as `ThreadId(7)` on stable.
+            let id = format!("{:?}", thread.id());
+            id.strip_prefix("ThreadId(")
+                .and_then(|id| id.strip_suffix(')'))
+                .map_or_else(|| id.clone(), str::to_string)
+        }
+    }
+}
+
+/// The CPU time the current thread has used, in microseconds.
+#[cfg(all(feature = "cpu-time", unix))]
+pub(crate) fn thread_cpu_micros() -> Option<u64> {
+    let mut time = libc::timespec {
+        tv_sec: 0,
+        tv_nsec: 0,
+    };
+    // SAFETY: clock_gettime only writes to the timespec it is given, which
+    // lives for the whole call.
+    let result = unsafe { libc::clock_gettime(libc::CLOCK_THREAD_CPUTIME_ID, &mut time) };
+    if result != 0 {
+        return None;
+    }
+    Some(time.tv_sec as u64 * 1_000_000 + time.tv_nsec as u64 / 1_000)
+}
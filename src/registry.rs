@@ -0,0 +1,124 @@
+//! An opt-in registry of live detailers.
+//!
+//! Detailers registered with [`Detailer::register()`](crate::Detailer::register)
+//! can be listed from anywhere in the process, which is handy for admin
+//! endpoints like "show me all in-flight operations and how long they've been
+//! running". A detailer leaves the registry when it is dropped.
+//!
+//! ```rust
+//! use detailer::{detail, new_detailer, registry};
+//!
+//! let mut detailer = new_detailer!();
+//! detailer.register("nightly compaction");
+//! detail!(detailer, "compacting shard 1");
+//!
+//! let live = registry::live();
+//! let compaction = live.iter().find(|live| live.name() == "nightly compaction");
+//! assert_eq!(Some(1), compaction.map(|compaction| compaction.lines()));
+//! ```
+
+use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, Mutex, PoisonError, Weak,
+    },
+    time::Duration,
+};
+
+use crate::clock;
+
+static REGISTRY: Mutex<Vec<Weak<Registration>>> = Mutex::new(Vec::new());
+
+/// A registered detailer's shared status.
+pub(crate) struct Registration {
+    name: String,
+    registered: Option<(clock::Clock, u64)>,
+    lines: AtomicUsize,
+    flush_requested: AtomicBool,
+}
+
+impl Registration {
+    pub(crate) fn register(name: String) -> Arc<Self> {
+        let registration = Arc::new(Self {
+            name,
+            registered: clock::platform().map(|clock| (clock, clock())),
+            lines: AtomicUsize::new(0),
+            flush_requested: AtomicBool::new(false),
+        });
+        let mut registry = REGISTRY.lock().unwrap_or_else(PoisonError::into_inner);
+        registry.retain(|live| live.strong_count() != 0);
+        registry.push(Arc::downgrade(&registration));
+        registration
+    }
+
+    pub(crate) fn set_lines(&self, lines: usize) {
+        self.lines.store(lines, Ordering::Relaxed);
+    }
+
+    /// Whether a flush was requested since the last time this was called
+    pub(crate) fn take_flush_request(&self) -> bool {
+        self.flush_requested.load(Ordering::Relaxed)
+            && self.flush_requested.swap(false, Ordering::Relaxed)
+    }
+}
+
+/// A registered detailer, as it was when it was listed.
+#[derive(Clone)]
+pub struct LiveDetailer {
+    name: String,
+    elapsed: Option<Duration>,
+    lines: usize,
+    registration: Weak<Registration>,
+}
+
+impl LiveDetailer {
+    /// The name the detailer was registered with
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// How long ago the detailer was registered, if this platform has a clock
+    pub fn elapsed(&self) -> Option<Duration> {
+        self.elapsed
+    }
+
+    /// How many lines the detailer is holding
+    pub fn lines(&self) -> usize {
+        self.lines
+    }
+
+    /// Ask the detailer to flush.
+    ///
+    /// Detailers are not shared between threads, so the flush happens the
+    /// next time the detailer records a line.
+    pub fn request_flush(&self) {
+        if let Some(registration) = self.registration.upgrade() {
+            registration.flush_requested.store(true, Ordering::Relaxed);
+        }
+    }
+}
+
+/// List the registered detailers that are still alive, oldest first.
+pub fn live() -> Vec<LiveDetailer> {
+    let mut registry = REGISTRY.lock().unwrap_or_else(PoisonError::into_inner);
+    registry.retain(|live| live.strong_count() != 0);
+    registry
+        .iter()
+        .filter_map(Weak::upgrade)
+        .map(|registration| LiveDetailer {
+            name: registration.name.clone(),
+            elapsed: registration.registered.map(|(clock, registered)| {
+                Duration::from_micros(clock().saturating_sub(registered))
+            }),
+            lines: registration.lines.load(Ordering::Relaxed),
+            registration: Arc::downgrade(&registration),
+        })
+        .collect()
+}
+
+/// Ask every registered detailer to flush the next time it records a line.
+pub fn request_flush_all() {
+    for live in live() {
+        live.request_flush();
+    }
+}
@@ -0,0 +1,134 @@
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+use http::{Request, Response};
+use hyper::service::Service;
+
+use crate::{detail, detail_at, Detailer, DetailerExtensions, SyncDetailer};
+
+/// A hyper [`Service`] that gives each request a detailer of its own.
+///
+/// Each call makes a detailer, records the request's method and URI, and
+/// hands the detailer to the inner service in the request's extensions,
+/// where [`DetailerExtensions::get_detailer()`] finds it. When the inner
+/// service produces its response, the status is recorded and the detailer
+/// flushes.
+///
+/// ```
+/// use std::{
+///     convert::Infallible,
+///     future::Future,
+///     pin::pin,
+///     sync::Arc,
+///     task::{Context, Poll, Wake, Waker},
+/// };
+///
+/// use detailer::{detail, new_detailer, DetailService, DetailerExtensions};
+/// use hyper::{
+///     service::{service_fn, Service},
+///     Request, Response,
+/// };
+///
+/// let service = DetailService::new(
+///     service_fn(|request: Request<String>| async move {
+///         if let Some(detailer) = request.extensions().get_detailer() {
+///             detail!(detailer, "looking up user");
+///         }
+///         Ok::<_, Infallible>(Response::new(String::from("ok")))
+///     }),
+///     || {
+///         let mut detailer = new_detailer!(Info, WithoutTiming);
+///         detailer.on_flush(|trace| {
+///             assert_eq!("GET /users/7\nlooking up user\n200 OK", trace.text)
+///         });
+///         detailer
+///     },
+/// );
+/// let response = service.call(Request::get("/users/7").body(String::new()).unwrap());
+///
+/// // A real server would run the service on its executor.
+/// struct Noop;
+/// impl Wake for Noop {
+///     fn wake(self: Arc<Self>) {}
+/// }
+/// let waker = Waker::from(Arc::new(Noop));
+/// let poll = pin!(response).poll(&mut Context::from_waker(&waker));
+/// assert!(matches!(poll, Poll::Ready(Ok(_))));
+/// ```
+pub struct DetailService<S> {
+    inner: S,
+    make: Arc<dyn Fn() -> Detailer + Send + Sync>,
+}
+
+impl<S> DetailService<S> {
+    /// Wrap `inner`, giving each request a detailer from `make`.
+    pub fn new(inner: S, make: impl Fn() -> Detailer + Send + Sync + 'static) -> Self {
+        Self {
+            inner,
+            make: Arc::new(make),
+        }
+    }
+}
+
+impl<S: Clone> Clone for DetailService<S> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            make: self.make.clone(),
+        }
+    }
+}
+
+impl<S, B, R> Service<Request<B>> for DetailService<S>
+where
+    S: Service<Request<B>, Response = Response<R>>,
+{
+    type Response = Response<R>;
+    type Error = S::Error;
+    type Future = DetailedResponse<S::Future>;
+
+    fn call(&self, mut request: Request<B>) -> Self::Future {
+        let detailer = SyncDetailer::new((self.make)());
+        detail!(detailer, "{} {}", request.method(), request.uri());
+        request.extensions_mut().insert_detailer(detailer.clone());
+        DetailedResponse {
+            future: Box::pin(self.inner.call(request)),
+            detailer,
+        }
+    }
+}
+
+/// The response of a [`DetailService`], which flushes the request's detailer
+/// when it is ready.
+pub struct DetailedResponse<F> {
+    future: Pin<Box<F>>,
+    detailer: SyncDetailer,
+}
+
+impl<F, R, E> Future for DetailedResponse<F>
+where
+    F: Future<Output = Result<Response<R>, E>>,
+{
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<F::Output> {
+        let this = self.get_mut();
+        let poll = this.future.as_mut().poll(cx);
+        if let Poll::Ready(result) = &poll {
+            match result {
+                Ok(response) => {
+                    detail!(this.detailer, "{}", response.status());
+                }
+                Err(_) => {
+                    detail_at!(this.detailer, Error, "failed");
+                }
+            }
+            this.detailer.flush();
+        }
+        poll
+    }
+}
@@ -0,0 +1,483 @@
+use alloc::{boxed::Box, string::String, vec::Vec};
+#[cfg(feature = "std")]
+use core::ops::Range;
+use core::{fmt::Display, time::Duration};
+
+use crate::parse::Event;
+
+#[cfg(feature = "tokio")]
+mod async_file;
+#[cfg(feature = "std")]
+mod background;
+#[cfg(feature = "std")]
+mod channel;
+#[cfg(feature = "datadog")]
+mod datadog;
+#[cfg(feature = "emf")]
+mod emf;
+#[cfg(feature = "std")]
+mod file;
+#[cfg(all(feature = "journald", unix))]
+mod journald;
+#[cfg(feature = "metrics")]
+mod metrics;
+
+#[cfg(feature = "slog")]
+mod slog;
+#[cfg(feature = "syslog")]
+mod syslog;
+#[cfg(feature = "std")]
+mod writer;
+#[cfg(feature = "xray")]
+mod xray;
+
+#[cfg(feature = "metrics")]
+pub use self::metrics::MetricsSink;
+#[cfg(feature = "slog")]
+pub use self::slog::SlogSink;
+#[cfg(feature = "tokio")]
+pub use async_file::AsyncFileSink;
+#[cfg(feature = "std")]
+pub use background::BackgroundSink;
+#[cfg(feature = "std")]
+pub use channel::ChannelSink;
+#[cfg(feature = "datadog")]
+pub use datadog::DatadogSink;
+#[cfg(feature = "emf")]
+pub use emf::EmfSink;
+#[cfg(feature = "std")]
+pub use file::FileSink;
+#[cfg(all(feature = "journald", unix))]
+pub use journald::JournaldSink;
+#[cfg(feature = "syslog")]
+pub use syslog::{Facility, SyslogSink};
+#[cfg(feature = "std")]
+pub use writer::{WriterFlush, WriterSink};
+#[cfg(feature = "xray")]
+pub use xray::XRaySink;
+
+/// Where a detailer's trace goes when it is flushed.
+///
+/// By default traces are logged through the `log` crate. Implement this to
+/// send them somewhere else, like a serial port in firmware or a dedicated
+/// trace file.
+///
+/// ```rust
+/// use std::sync::{Arc, Mutex};
+///
+/// use detailer::{detail, new_detailer, FlushedTrace, Sink};
+///
+/// #[derive(Default)]
+/// struct Collect(Mutex<Vec<String>>);
+///
+/// impl Sink for Collect {
+///     fn flush(&self, trace: &FlushedTrace) {
+///         if let Ok(mut traces) = self.0.lock() {
+///             traces.push(trace.text.to_string());
+///         }
+///     }
+/// }
+///
+/// let collected = Arc::new(Collect::default());
+/// let mut detailer = new_detailer!(Info, WithoutTiming);
+/// detailer.sink(collected.clone());
+///
+/// detail!(detailer, "to the sink");
+/// detailer.flush();
+/// assert_eq!(vec!["to the sink".to_string()], *collected.0.lock().unwrap());
+/// ```
+pub trait Sink: Send + Sync {
+    /// Write out one flushed trace
+    fn flush(&self, trace: &FlushedTrace);
+
+    /// Write out one flushed trace, reporting if it could not be written.
+    ///
+    /// Detailers flush through this, and apply their
+    /// [`FlushErrorPolicy`] when it fails. Sinks that can fail should
+    /// implement it; by default it calls [`flush()`](Self::flush) and
+    /// succeeds.
+    ///
+    /// ```rust
+    /// use std::sync::Arc;
+    ///
+    /// use detailer::{detail, new_detailer, FlushError, FlushErrorPolicy, FlushedTrace, Sink};
+    ///
+    /// struct Unplugged;
+    ///
+    /// impl Sink for Unplugged {
+    ///     fn flush(&self, trace: &FlushedTrace) {
+    ///         let _ = self.try_flush(trace);
+    ///     }
+    ///
+    ///     fn try_flush(&self, _trace: &FlushedTrace) -> Result<(), FlushError> {
+    ///         Err(FlushError::new("cable unplugged"))
+    ///     }
+    /// }
+    ///
+    /// let mut detailer = new_detailer!(Info, WithoutTiming);
+    /// detailer.sink(Arc::new(Unplugged));
+    /// detailer.on_flush_error(FlushErrorPolicy::Discard);
+    /// detail!(detailer, "lost");
+    /// assert_eq!("cable unplugged", detailer.try_flush().unwrap_err().to_string());
+    /// ```
+    fn try_flush(&self, trace: &FlushedTrace) -> Result<(), FlushError> {
+        self.flush(trace);
+        Ok(())
+    }
+
+    /// The largest record this sink can write, and what a detailer does with
+    /// traces that are larger. By default there is no limit, and a detailer
+    /// uses its own [`chunk_traces_over()`](crate::Detailer::chunk_traces_over)
+    /// size if it has one.
+    ///
+    /// ```rust
+    /// use std::sync::{Arc, Mutex};
+    ///
+    /// use detailer::{detail, new_detailer, FlushedTrace, RecordLimit, Sink};
+    ///
+    /// #[derive(Default)]
+    /// struct Narrow(Mutex<Vec<String>>);
+    ///
+    /// impl Sink for Narrow {
+    ///     fn flush(&self, trace: &FlushedTrace) {
+    ///         self.0.lock().unwrap().push(trace.text.to_string());
+    ///     }
+    ///
+    ///     fn record_limit(&self) -> Option<RecordLimit> {
+    ///         Some(RecordLimit::Truncate(80))
+    ///     }
+    /// }
+    ///
+    /// let narrow = Arc::new(Narrow::default());
+    /// let mut detailer = new_detailer!(Info, WithoutTiming);
+    /// detailer.sink(narrow.clone());
+    /// for page in 1..=6 {
+    ///     detail!(detailer, "loaded page {page}");
+    /// }
+    /// detailer.flush();
+    /// assert_eq!(
+    ///     vec!["loaded page 1\nloaded page 2\n(+56 bytes truncated over the record limit)"],
+    ///     *narrow.0.lock().unwrap(),
+    /// );
+    /// ```
+    fn record_limit(&self) -> Option<RecordLimit> {
+        None
+    }
+
+    /// Whether this sink wants each trace's lines as structured
+    /// [events](FlushedTrace::events). By default it doesn't, and they
+    /// aren't collected.
+    ///
+    /// Sinks that export a trace's structure, like its scopes as spans, want
+    /// them: the events come from the lines as they were recorded, so they
+    /// don't depend on how the text is laid out.
+    ///
+    /// ```rust
+    /// use std::sync::{
+    ///     atomic::{AtomicU64, Ordering},
+    ///     Arc, Mutex,
+    /// };
+    ///
+    /// use detailer::{detail, parse, scope, Detailer, ElapsedFormat, FlushedTrace, Sink, TimingSetting};
+    ///
+    /// static NOW: AtomicU64 = AtomicU64::new(0);
+    /// fn now() -> u64 {
+    ///     NOW.fetch_add(250, Ordering::Relaxed)
+    /// }
+    ///
+    /// #[derive(Default)]
+    /// struct Spans(Mutex<Vec<String>>);
+    ///
+    /// impl Sink for Spans {
+    ///     fn flush(&self, trace: &FlushedTrace) {
+    ///         for scope in parse::scopes_in(trace.events) {
+    ///             self.0.lock().unwrap().push(scope.name);
+    ///         }
+    ///     }
+    ///
+    ///     fn wants_events(&self) -> bool {
+    ///         true
+    ///     }
+    /// }
+    ///
+    /// let spans = Arc::new(Spans::default());
+    /// let mut detailer = Detailer::new(log::LevelFilter::Info, TimingSetting::WithClock(now), 4096);
+    /// detailer.sink(spans.clone());
+    /// detailer.elapsed_format(ElapsedFormat::Scaled);
+    /// {
+    ///     let _scope = scope!(detailer, "query");
+    ///     detail!(detailer, "rows read");
+    /// }
+    /// detailer.flush();
+    /// assert_eq!(vec!["query".to_string()], *spans.0.lock().unwrap());
+    /// ```
+    fn wants_events(&self) -> bool {
+        false
+    }
+}
+
+/// The largest record, in bytes, a [`Sink`] can write, and what to do with a
+/// trace that is larger.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum RecordLimit {
+    /// Flush the trace in numbered parts, like
+    /// [`chunk_traces_over()`](crate::Detailer::chunk_traces_over) does
+    Chunk(usize),
+    /// Flush as much of the trace as fits, ending with a line that says how
+    /// much was cut
+    Truncate(usize),
+    /// Don't flush the trace to the sink, and handle it like any other
+    /// [`FlushError`]
+    Reject(usize),
+}
+
+impl RecordLimit {
+    /// The largest record the sink can write
+    pub fn bytes(self) -> usize {
+        match self {
+            Self::Chunk(bytes) | Self::Truncate(bytes) | Self::Reject(bytes) => bytes,
+        }
+    }
+}
+
+/// Why a sink could not write a trace.
+#[derive(Debug)]
+pub struct FlushError {
+    source: Box<dyn core::error::Error + Send + Sync>,
+}
+
+impl FlushError {
+    /// Wrap the error that kept a sink from writing a trace
+    pub fn new(source: impl Into<Box<dyn core::error::Error + Send + Sync>>) -> Self {
+        Self {
+            source: source.into(),
+        }
+    }
+}
+
+impl Display for FlushError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        self.source.fmt(f)
+    }
+}
+
+impl core::error::Error for FlushError {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        Some(&*self.source)
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<std::io::Error> for FlushError {
+    fn from(error: std::io::Error) -> Self {
+        Self::new(error)
+    }
+}
+
+/// What a detailer does with a trace its sink could not write.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum FlushErrorPolicy {
+    /// Lose the trace
+    Discard,
+    /// Try the sink up to this many more times, then log the trace through
+    /// the `log` crate
+    Retry(usize),
+    /// Log the trace through the `log` crate instead
+    #[default]
+    FallBackToLog,
+}
+
+/// A trace that is being flushed.
+#[non_exhaustive]
+pub struct FlushedTrace<'a> {
+    /// The level the trace is flushed at
+    pub level: log::Level,
+    /// The rendered trace
+    pub text: &'a str,
+    /// The detailer's trace ID, if it has one
+    pub trace_id: Option<&'a str>,
+    /// The detailer's [context](crate::Detailer::set_context), as keys and
+    /// values
+    pub context: &'a [(&'static str, String)],
+    /// How long the trace ran for, if it was recorded with timing
+    pub elapsed: Option<Duration>,
+    /// How many lines the trace recorded
+    pub lines: usize,
+    /// The trace's lines in this text, without the headers and notes around
+    /// them: the `part` header of a trace flushed in parts, the context
+    /// header, and decorations like [scope bars](crate::Detailer::scope_bars)
+    /// or the note about truncation. Sinks that write those on their own, or
+    /// [parse](crate::parse) the lines, want this.
+    ///
+    /// ```rust
+    /// use detailer::{detail, new_detailer, parse};
+    ///
+    /// let mut detailer = new_detailer!(Info, WithoutTiming);
+    /// detailer.set_context("request_id", "7");
+    /// detailer.chunk_traces_over(Some(40));
+    /// detailer.on_flush(|trace| {
+    ///     if trace.part == Some((1, 2)) {
+    ///         assert!(trace.text.starts_with("part 1/2\nrequest_id=7\n"));
+    ///         assert_eq!(Some("line number 0"), parse::events(trace.body).first().map(|event| event.message.as_str()));
+    ///     }
+    /// });
+    /// for n in 0..3 {
+    ///     detail!(detailer, "line number {n}");
+    /// }
+    /// detailer.flush();
+    /// ```
+    pub body: &'a str,
+    /// Which part of the trace this is, counting from 1, and how many parts
+    /// there are, when the trace is flushed in parts over a
+    /// [record limit](RecordLimit::Chunk)
+    pub part: Option<(usize, usize)>,
+    /// Every line the trace recorded, with its indentation and elapsed time,
+    /// when the sink [wants them](Sink::wants_events). Otherwise, and for
+    /// [`on_flush()`](crate::Detailer::on_flush) callbacks without such a
+    /// sink, this is empty.
+    ///
+    /// These are the lines as they were recorded, before any
+    /// [filters](crate::Detailer::tag_filter) left some out of the text.
+    /// Every part of a trace flushed in parts has all of them.
+    pub events: &'a [Event],
+}
+
+/// A copy of a [`FlushedTrace`] that outlives the flush, for traces that are
+/// written later or somewhere else, like by a [`ChannelSink`].
+#[cfg(feature = "std")]
+#[non_exhaustive]
+pub struct OwnedTrace {
+    /// The level the trace is flushed at
+    pub level: log::Level,
+    /// The rendered trace
+    pub text: String,
+    /// The detailer's trace ID, if it has one
+    pub trace_id: Option<String>,
+    /// The detailer's [context](crate::Detailer::set_context), as keys and
+    /// values
+    pub context: Vec<(&'static str, String)>,
+    /// How long the trace ran for, if it was recorded with timing
+    pub elapsed: Option<Duration>,
+    /// How many lines the trace recorded
+    pub lines: usize,
+    /// Which part of the trace this is, and how many parts there are, when
+    /// it was flushed in parts. See [`FlushedTrace::part`].
+    pub part: Option<(usize, usize)>,
+    /// The trace's lines as structured events, if the sink
+    /// [wanted them](Sink::wants_events). See [`FlushedTrace::events`].
+    pub events: Vec<Event>,
+    /// Where the [body](FlushedTrace::body) is in the text
+    body: Range<usize>,
+}
+
+#[cfg(feature = "std")]
+impl OwnedTrace {
+    /// Borrow the trace as it was flushed, like to hand it on to another
+    /// [`Sink`]
+    pub fn as_flushed(&self) -> FlushedTrace<'_> {
+        FlushedTrace {
+            level: self.level,
+            text: &self.text,
+            trace_id: self.trace_id.as_deref(),
+            context: &self.context,
+            elapsed: self.elapsed,
+            lines: self.lines,
+            body: self.body(),
+            part: self.part,
+            events: &self.events,
+        }
+    }
+
+    /// The trace's lines, without headers and notes. See
+    /// [`FlushedTrace::body`].
+    pub fn body(&self) -> &str {
+        self.text.get(self.body.clone()).unwrap_or(&self.text)
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<&FlushedTrace<'_>> for OwnedTrace {
+    fn from(trace: &FlushedTrace) -> Self {
+        Self {
+            level: trace.level,
+            text: trace.text.into(),
+            trace_id: trace.trace_id.map(String::from),
+            context: trace.context.to_vec(),
+            elapsed: trace.elapsed,
+            lines: trace.lines,
+            part: trace.part,
+            events: trace.events.to_vec(),
+            body: (trace.body.as_ptr() as usize)
+                .checked_sub(trace.text.as_ptr() as usize)
+                .map_or(0..trace.text.len(), |start| start..start + trace.body.len()),
+        }
+    }
+}
+
+/// The default sink, which logs each trace as one `log` record.
+pub struct LogSink;
+
+impl Sink for LogSink {
+    fn flush(&self, trace: &FlushedTrace) {
+        // Keep the target detailer has always logged with, for log filters.
+        log::log!(target: "detailer::detailer", trace.level, "{}", trace.text);
+    }
+}
+
+/// A sink that drops every trace.
+///
+/// Instrumentation keeps recording, so a trace can still be inspected or
+/// sampled before it is flushed, but flushing skips the `log` crate's global
+/// dispatch. Benchmarks can use it to measure a detailer's own cost.
+///
+/// ```rust
+/// use std::sync::Arc;
+///
+/// use detailer::{detail, new_detailer, NullSink};
+///
+/// let mut detailer = new_detailer!(Info, WithoutTiming);
+/// detailer.sink(Arc::new(NullSink));
+/// detail!(detailer, "recorded, then dropped");
+/// detailer.flush();
+/// ```
+pub struct NullSink;
+
+impl Sink for NullSink {
+    fn flush(&self, _trace: &FlushedTrace) {}
+}
+
+/// The syslog severity of a log level.
+#[cfg(any(all(feature = "journald", unix), feature = "syslog"))]
+pub(crate) fn severity(level: log::Level) -> u8 {
+    match level {
+        log::Level::Error => 3,
+        log::Level::Warn => 4,
+        log::Level::Info => 6,
+        log::Level::Debug | log::Level::Trace => 7,
+    }
+}
+
+/// Split text into pieces of at most `budget` bytes, between lines where
+/// possible.
+pub(crate) fn split(text: &str, budget: usize) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut rest = text;
+    while budget < rest.len() {
+        let mut end = budget;
+        while !rest.is_char_boundary(end) {
+            end -= 1;
+        }
+        let end = match rest[..end].rfind('\n') {
+            Some(newline) if 0 < newline => newline,
+            _ if 0 < end => end,
+            // A character wider than the budget still has to go somewhere.
+            _ => rest.chars().next().map_or(rest.len(), char::len_utf8),
+        };
+        parts.push(&rest[..end]);
+        rest = rest[end..].strip_prefix('\n').unwrap_or(&rest[end..]);
+    }
+    parts.push(rest);
+    parts
+}
@@ -0,0 +1,123 @@
+use std::{io, path::Path};
+
+use tokio::{
+    fs::{File, OpenOptions},
+    io::AsyncWriteExt,
+    sync::{
+        mpsc::{self, error::TrySendError, Sender},
+        oneshot,
+    },
+};
+
+use crate::{FlushError, FlushedTrace, Sink};
+
+/// What the writer task is asked to do
+enum Message {
+    /// Append a trace, with its newline
+    Trace(String),
+    /// Flush the file, then say so
+    Flush(oneshot::Sender<()>),
+}
+
+/// Appends flushed traces to a file from a tokio task, so the threads that
+/// flush never block on the disk.
+///
+/// Flushing only copies the trace into a bounded queue, and a task on the
+/// runtime the sink was made on writes them with [`tokio::fs`]. When the
+/// queue is full, the trace is not written, and the detailer handles that
+/// like any other sink failure, following its
+/// [`FlushErrorPolicy`](crate::FlushErrorPolicy). Write errors happen on the
+/// task, so they are logged with a warning.
+///
+/// ```rust
+/// use std::sync::Arc;
+///
+/// use detailer::{detail, new_detailer, AsyncFileSink};
+///
+/// # #[tokio::main(flavor = "current_thread")]
+/// # async fn main() {
+/// # let path = std::env::temp_dir().join(format!("detailer-async-{}.log", std::process::id()));
+/// # let _ = std::fs::remove_file(&path);
+/// let sink = Arc::new(AsyncFileSink::append(&path, 1024).await.unwrap());
+/// let mut detailer = new_detailer!(Info, WithoutTiming);
+/// detailer.sink(sink.clone());
+/// detail!(detailer, "authenticating");
+/// detailer.flush();
+///
+/// sink.written().await;
+/// assert_eq!("authenticating\n", std::fs::read_to_string(&path).unwrap());
+/// # }
+/// ```
+pub struct AsyncFileSink {
+    queue: Sender<Message>,
+}
+
+impl AsyncFileSink {
+    /// Append traces to the file at `path`, creating it if it doesn't exist,
+    /// queueing up to `capacity` of them while the file catches up.
+    pub async fn append(path: impl AsRef<Path>, capacity: usize) -> io::Result<Self> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .await?;
+        Ok(Self::new(file, capacity))
+    }
+
+    /// Append traces to an open file, queueing up to `capacity` of them.
+    ///
+    /// The writer task is spawned with [`tokio::spawn()`], so this has to be
+    /// called from a runtime. It runs until the sink is dropped and what was
+    /// queued is written.
+    pub fn new(mut file: File, capacity: usize) -> Self {
+        let (queue, mut messages) = mpsc::channel(capacity.max(1));
+        tokio::spawn(async move {
+            while let Some(message) = messages.recv().await {
+                let result = match message {
+                    Message::Trace(text) => file.write_all(text.as_bytes()).await,
+                    Message::Flush(done) => {
+                        let result = file.flush().await;
+                        let _ = done.send(());
+                        result
+                    }
+                };
+                if let Err(e) = result {
+                    log::warn!("could not write trace to file: {e}");
+                }
+            }
+            if let Err(e) = file.flush().await {
+                log::warn!("could not write trace to file: {e}");
+            }
+        });
+        Self { queue }
+    }
+
+    /// Wait until the traces queued so far are written to the file.
+    pub async fn written(&self) {
+        let (done, written) = oneshot::channel();
+        if self.queue.send(Message::Flush(done)).await.is_ok() {
+            let _ = written.await;
+        }
+    }
+}
+
+impl Sink for AsyncFileSink {
+    fn flush(&self, trace: &FlushedTrace) {
+        if let Err(e) = self.try_flush(trace) {
+            log::warn!("could not queue trace: {e}");
+        }
+    }
+
+    fn try_flush(&self, trace: &FlushedTrace) -> Result<(), FlushError> {
+        let mut text = String::with_capacity(trace.text.len() + 1);
+        text.push_str(trace.text);
+        text.push('\n');
+        match self.queue.try_send(Message::Trace(text)) {
+            Ok(()) => Ok(()),
+            Err(TrySendError::Full(_)) => Err(FlushError::new("the sink's queue is full")),
+            Err(TrySendError::Closed(_)) => {
+                Err(FlushError::new("the sink's writer task has stopped"))
+            }
+        }
+    }
+}
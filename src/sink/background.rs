@@ -0,0 +1,107 @@
+use std::{
+    sync::mpsc::{self, SyncSender, TrySendError},
+    thread::{self, JoinHandle},
+};
+
+use crate::{FlushError, FlushedTrace, RecordLimit, Sink};
+
+use super::OwnedTrace;
+
+/// Hands flushed traces to another sink on a thread of its own, so the
+/// threads that flush never wait on that sink's I/O.
+///
+/// Use this to write traces from async code, where a slow disk or network
+/// would otherwise stall the runtime's worker threads: flushing only copies
+/// the trace into a bounded queue. When the queue is full, the trace is not
+/// written, and the detailer handles that like any other sink failure,
+/// following its [`FlushErrorPolicy`](crate::FlushErrorPolicy). Errors from
+/// the inner sink happen on the writer thread, so they are reported the way
+/// its [`flush()`](Sink::flush) reports them, usually with a warning.
+///
+/// Dropping the sink waits for the traces already queued to be written.
+///
+/// ```rust
+/// use std::sync::Arc;
+///
+/// use detailer::{detail, new_detailer, BackgroundSink, FileSink};
+///
+/// # let path = std::env::temp_dir().join(format!("detailer-background-{}.log", std::process::id()));
+/// # let _ = std::fs::remove_file(&path);
+/// let sink = Arc::new(BackgroundSink::new(FileSink::append(&path).unwrap(), 1024).unwrap());
+/// let mut detailer = new_detailer!(Info, WithoutTiming);
+/// detailer.sink(sink.clone());
+/// detail!(detailer, "authenticating");
+/// detailer.flush();
+///
+/// drop(detailer);
+/// drop(sink);
+/// assert_eq!("authenticating\n", std::fs::read_to_string(&path).unwrap());
+/// ```
+pub struct BackgroundSink {
+    queue: Option<SyncSender<OwnedTrace>>,
+    writer: Option<JoinHandle<()>>,
+    record_limit: Option<RecordLimit>,
+    wants_events: bool,
+}
+
+impl BackgroundSink {
+    /// Write traces to `sink` on a new thread, queueing up to `capacity` of
+    /// them while it catches up.
+    pub fn new(sink: impl Sink + 'static, capacity: usize) -> std::io::Result<Self> {
+        let (queue, traces) = mpsc::sync_channel::<OwnedTrace>(capacity);
+        let record_limit = sink.record_limit();
+        let wants_events = sink.wants_events();
+        let writer = thread::Builder::new()
+            .name("detailer-sink".to_string())
+            .spawn(move || {
+                for trace in traces {
+                    sink.flush(&trace.as_flushed());
+                }
+            })?;
+        Ok(Self {
+            queue: Some(queue),
+            writer: Some(writer),
+            record_limit,
+            wants_events,
+        })
+    }
+}
+
+impl Sink for BackgroundSink {
+    fn flush(&self, trace: &FlushedTrace) {
+        if let Err(e) = self.try_flush(trace) {
+            log::warn!("could not queue trace: {e}");
+        }
+    }
+
+    fn try_flush(&self, trace: &FlushedTrace) -> Result<(), FlushError> {
+        let Some(queue) = &self.queue else {
+            return Err(FlushError::new("the sink is shutting down"));
+        };
+        match queue.try_send(OwnedTrace::from(trace)) {
+            Ok(()) => Ok(()),
+            Err(TrySendError::Full(_)) => Err(FlushError::new("the sink's queue is full")),
+            Err(TrySendError::Disconnected(_)) => {
+                Err(FlushError::new("the sink's writer thread has stopped"))
+            }
+        }
+    }
+
+    fn record_limit(&self) -> Option<RecordLimit> {
+        self.record_limit
+    }
+
+    fn wants_events(&self) -> bool {
+        self.wants_events
+    }
+}
+
+impl Drop for BackgroundSink {
+    fn drop(&mut self) {
+        // Closing the queue lets the writer finish what is left and exit.
+        self.queue = None;
+        if let Some(writer) = self.writer.take() {
+            let _ = writer.join();
+        }
+    }
+}
@@ -0,0 +1,99 @@
+use std::sync::mpsc::{self, Receiver, Sender, SyncSender, TrySendError};
+
+use crate::{FlushError, FlushedTrace, OwnedTrace, Sink};
+
+/// Sends flushed traces over a channel, so one thread can collect the traces
+/// of many detailers, like to persist or analyze them in one place.
+///
+/// Each trace arrives as an [`OwnedTrace`], with its rendered text and the
+/// rest of what it was flushed with.
+///
+/// ```rust
+/// use std::sync::Arc;
+///
+/// use detailer::{detail, new_detailer, ChannelSink};
+///
+/// let (sink, traces) = ChannelSink::new();
+/// let sink = Arc::new(sink);
+/// let workers: Vec<_> = (0..2)
+///     .map(|worker| {
+///         let sink = sink.clone();
+///         std::thread::spawn(move || {
+///             let mut detailer = new_detailer!(Info, WithoutTiming);
+///             detailer.sink(sink);
+///             detailer.set_context("worker", worker);
+///             detail!(detailer, "finished batch");
+///         })
+///     })
+///     .collect();
+/// for worker in workers {
+///     worker.join().unwrap();
+/// }
+/// drop(sink);
+///
+/// let mut workers: Vec<_> = traces.iter().map(|trace| trace.context[0].1.clone()).collect();
+/// workers.sort();
+/// assert_eq!(vec!["0", "1"], workers);
+/// ```
+pub struct ChannelSink {
+    sender: ChannelSender,
+}
+
+enum ChannelSender {
+    Unbounded(Sender<OwnedTrace>),
+    Bounded(SyncSender<OwnedTrace>),
+}
+
+impl ChannelSink {
+    /// A sink and the receiver its traces go to. The channel holds as many
+    /// traces as the receiver falls behind by.
+    pub fn new() -> (Self, Receiver<OwnedTrace>) {
+        let (sender, receiver) = mpsc::channel();
+        (
+            Self {
+                sender: ChannelSender::Unbounded(sender),
+            },
+            receiver,
+        )
+    }
+
+    /// A sink and the receiver its traces go to, through a channel that
+    /// holds at most `capacity` traces. Traces flushed while it is full are
+    /// not sent, and the detailer handles that like any other sink failure.
+    pub fn bounded(capacity: usize) -> (Self, Receiver<OwnedTrace>) {
+        let (sender, receiver) = mpsc::sync_channel(capacity);
+        (
+            Self {
+                sender: ChannelSender::Bounded(sender),
+            },
+            receiver,
+        )
+    }
+}
+
+impl Sink for ChannelSink {
+    fn flush(&self, trace: &FlushedTrace) {
+        if let Err(e) = self.try_flush(trace) {
+            log::warn!("could not send trace: {e}");
+        }
+    }
+
+    fn try_flush(&self, trace: &FlushedTrace) -> Result<(), FlushError> {
+        let trace = OwnedTrace::from(trace);
+        let sent = match &self.sender {
+            ChannelSender::Unbounded(sender) => sender.send(trace).is_ok(),
+            ChannelSender::Bounded(sender) => match sender.try_send(trace) {
+                Ok(()) => true,
+                Err(TrySendError::Full(_)) => {
+                    return Err(FlushError::new("the channel is full"));
+                }
+                Err(TrySendError::Disconnected(_)) => false,
+            },
+        };
+        if sent {
+            Ok(())
+        } else {
+            Err(FlushError::new("the receiver was dropped"))
+        }
+    }
+}
@@ -0,0 +1,256 @@
+use std::{
+    fmt::Write as _,
+    io::{self, BufRead, BufReader, Write},
+    net::TcpStream,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use crate::{id, json::push_string, parse, FlushError, FlushedTrace, Sink};
+
+/// Where the Datadog agent listens for traces by default.
+const DEFAULT_AGENT: &str = "127.0.0.1:8126";
+
+/// How long to wait on the agent before giving up on a trace.
+const AGENT_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Submits flushed traces to a Datadog agent as APM traces.
+///
+/// Each trace is a root span covering the whole trace, with a span for each
+/// of its scopes, nested the way the scopes are. The root span's service
+/// and resource come from the detailer's `service` and `resource`
+/// [context](crate::Detailer::set_context) fields when it has them, and
+/// every context field is a tag on it. Scope spans are named after their
+/// scope's line. The trace ID is the low 64 bits of the detailer's
+/// [trace ID](crate::Detailer::trace_id) when it is hex, as Datadog reads
+/// W3C trace IDs, and random otherwise.
+///
+/// Spans come from the lines as they were recorded, so the trace's
+/// [line format](crate::Detailer::line_format) and
+/// [elapsed format](crate::Detailer::elapsed_format) don't change them. A
+/// trace flushed in [parts](crate::RecordLimit::Chunk) is submitted once,
+/// with its first part. Traces recorded without timing become a root span
+/// with no scopes.
+///
+/// ```rust
+/// use std::{
+///     io::{BufRead, BufReader, Read, Write},
+///     net::TcpListener,
+///     sync::Arc,
+/// };
+///
+/// use detailer::{detail, new_detailer, scope, DatadogSink, ElapsedFormat};
+///
+/// let agent = TcpListener::bind("127.0.0.1:0").unwrap();
+/// let sink = DatadogSink::new("checkout").agent(agent.local_addr().unwrap().to_string());
+/// let received = std::thread::spawn(move || {
+///     let (connection, _) = agent.accept().unwrap();
+///     let mut request = BufReader::new(connection);
+///     let mut length = 0;
+///     let mut header = String::new();
+///     while request.read_line(&mut header).unwrap() > 2 {
+///         if let Some(value) = header.to_lowercase().strip_prefix("content-length:") {
+///             length = value.trim().parse().unwrap();
+///         }
+///         header.clear();
+///     }
+///     let mut body = vec![0; length];
+///     request.read_exact(&mut body).unwrap();
+///     request.get_mut().write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nOK").unwrap();
+///     String::from_utf8(body).unwrap()
+/// });
+///
+/// let mut detailer = new_detailer!();
+/// detailer.sink(Arc::new(sink));
+/// detailer.elapsed_format(ElapsedFormat::Millis);
+/// detailer.set_context("resource", "POST /cart");
+/// {
+///     let _scope = scope!(detailer, "query");
+///     detail!(detailer, "rows read");
+/// }
+/// detailer.flush();
+///
+/// let spans = received.join().unwrap();
+/// assert!(spans.contains(r#""service":"checkout","resource":"POST /cart""#));
+/// assert!(spans.contains(r#""resource":"query""#));
+/// ```
+pub struct DatadogSink {
+    agent: String,
+    service: String,
+    service_field: &'static str,
+    resource_field: &'static str,
+    operation: String,
+}
+
+impl DatadogSink {
+    /// Submit traces for `service` to the agent on this host.
+    pub fn new(service: impl Into<String>) -> Self {
+        Self {
+            agent: DEFAULT_AGENT.into(),
+            service: service.into(),
+            service_field: "service",
+            resource_field: "resource",
+            operation: "detailer.trace".into(),
+        }
+    }
+
+    /// Submit traces to an agent at `address`, like `datadog-agent:8126`,
+    /// instead of the one on this host
+    pub fn agent(mut self, address: impl Into<String>) -> Self {
+        self.agent = address.into();
+        self
+    }
+
+    /// Use the context field `key` for each trace's service, when it has
+    /// one, instead of `service`
+    pub fn service_field(mut self, key: &'static str) -> Self {
+        self.service_field = key;
+        self
+    }
+
+    /// Use the context field `key` for each trace's resource, when it has
+    /// one, instead of `resource`. Traces without one are named after the
+    /// operation.
+    pub fn resource_field(mut self, key: &'static str) -> Self {
+        self.resource_field = key;
+        self
+    }
+
+    /// Name the operation of each trace's root span, which is
+    /// `detailer.trace` by default. Scope spans are named `detailer.scope`.
+    pub fn operation(mut self, name: impl Into<String>) -> Self {
+        self.operation = name.into();
+        self
+    }
+
+    /// The trace as a v0.4 JSON payload of one trace
+    fn payload(&self, trace: &FlushedTrace) -> String {
+        let field = |key: &str| {
+            trace
+                .context
+                .iter()
+                .find(|(field, _)| *field == key)
+                .map(|(_, value)| value.as_str())
+        };
+        let service = field(self.service_field).unwrap_or(&self.service);
+        let resource = field(self.resource_field).unwrap_or(&self.operation);
+        let trace_id = trace
+            .trace_id
+            .and_then(low_64_bits)
+            .unwrap_or_else(random_id);
+        let elapsed = trace.elapsed.unwrap_or_default();
+        let start = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .saturating_sub(elapsed);
+        let error = u8::from(trace.level == log::Level::Error);
+
+        let root = random_id();
+        let mut json = String::from("[[");
+        let _ = write!(
+            json,
+            r#"{{"trace_id":{trace_id},"span_id":{root},"parent_id":0,"name":"#
+        );
+        push_string(&mut json, &self.operation);
+        json.push_str(r#","service":"#);
+        push_string(&mut json, service);
+        json.push_str(r#","resource":"#);
+        push_string(&mut json, resource);
+        let _ = write!(
+            json,
+            r#","type":"custom","start":{},"duration":{},"error":{error},"meta":{{"#,
+            start.as_nanos(),
+            elapsed.as_nanos(),
+        );
+        let tags = trace
+            .trace_id
+            .map(|trace_id| ("detailer.trace_id", trace_id))
+            .into_iter()
+            .chain(
+                trace
+                    .context
+                    .iter()
+                    .map(|(key, value)| (*key, value.as_str())),
+            );
+        for (index, (key, value)) in tags.enumerate() {
+            if 0 < index {
+                json.push(',');
+            }
+            push_string(&mut json, key);
+            json.push(':');
+            push_string(&mut json, value);
+        }
+        json.push_str("}}");
+
+        let scopes = parse::scopes_in(trace.events);
+        let span_ids: Vec<u64> = scopes.iter().map(|_| random_id()).collect();
+        for (scope, span_id) in scopes.iter().zip(&span_ids) {
+            let parent = scope.parent.map_or(root, |parent| span_ids[parent]);
+            let _ = write!(
+                json,
+                r#",{{"trace_id":{trace_id},"span_id":{span_id},"parent_id":{parent},"name":"detailer.scope","service":"#
+            );
+            push_string(&mut json, service);
+            json.push_str(r#","resource":"#);
+            push_string(&mut json, &scope.name);
+            let _ = write!(
+                json,
+                r#","type":"custom","start":{},"duration":{},"error":0}}"#,
+                (start + scope.start).as_nanos(),
+                scope.duration.as_nanos(),
+            );
+        }
+        json.push_str("]]");
+        json
+    }
+}
+
+impl Sink for DatadogSink {
+    fn flush(&self, trace: &FlushedTrace) {
+        if let Err(e) = self.try_flush(trace) {
+            log::warn!("could not submit trace to the Datadog agent: {e}");
+        }
+    }
+
+    fn try_flush(&self, trace: &FlushedTrace) -> Result<(), FlushError> {
+        if trace.part.is_some_and(|(part, _)| 1 < part) {
+            return Ok(());
+        }
+        let payload = self.payload(trace);
+        let mut connection = TcpStream::connect(&self.agent)?;
+        connection.set_read_timeout(Some(AGENT_TIMEOUT))?;
+        connection.set_write_timeout(Some(AGENT_TIMEOUT))?;
+        write!(
+            connection,
+            "PUT /v0.4/traces HTTP/1.1\r\nHost: {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nX-Datadog-Trace-Count: 1\r\nConnection: close\r\n\r\n",
+            self.agent,
+            payload.len(),
+        )?;
+        connection.write_all(payload.as_bytes())?;
+
+        let mut status = String::new();
+        BufReader::new(connection).read_line(&mut status)?;
+        match status.split(' ').nth(1) {
+            Some(code) if code.starts_with('2') => Ok(()),
+            _ => Err(io::Error::other(format!("the agent answered {}", status.trim_end())).into()),
+        }
+    }
+
+    fn wants_events(&self) -> bool {
+        true
+    }
+}
+
+/// The low 64 bits of a hex ID, like the trace IDs of W3C trace context
+fn low_64_bits(trace_id: &str) -> Option<u64> {
+    if !trace_id.chars().all(|c| c.is_ascii_hexdigit() || c == '-') {
+        return None;
+    }
+    let digits: String = trace_id.chars().filter(|c| *c != '-').collect();
+    let low = &digits[digits.len().saturating_sub(16)..];
+    u64::from_str_radix(low, 16).ok().filter(|id| *id != 0)
+}
+
+/// A random, nonzero span ID
+fn random_id() -> u64 {
+    u64::from_str_radix(&id::span_id(), 16).unwrap_or(1).max(1)
+}
@@ -0,0 +1,288 @@
+use std::{
+    fmt::Write as _,
+    io::Write,
+    sync::{Mutex, PoisonError},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use crate::{diff::durations, json::push_string, FlushError, FlushedTrace, RecordLimit, Sink};
+
+/// The largest log event CloudWatch accepts.
+const MAX_EVENT: usize = 256 * 1024;
+
+/// How much of a trace's text goes in one log event, leaving room for it to
+/// grow as it's escaped, and for the members around it.
+const MAX_TRACE: usize = 128 * 1024;
+
+/// The members each object is written with, which context fields and
+/// metrics can't be named.
+const RESERVED: &[&str] = &["_aws", "duration", "level", "part", "trace", "trace_id"];
+
+/// Writes flushed traces in CloudWatch Embedded Metric Format, one JSON
+/// object per line, so one log line feeds both CloudWatch Logs and Metrics.
+///
+/// The rendered trace is the object's `trace` member, next to its `level`,
+/// `trace_id` and context fields. Its metrics are its total `duration`, and
+/// the duration of each line [named](Self::metric) as a metric: how long
+/// its scope took, or until the next line for other lines. Durations are in
+/// microseconds. Context fields chosen as [dimensions](Self::dimensions)
+/// split the metrics. A context field named like one of the other members
+/// is written as `context.<key>`.
+///
+/// Traces longer than a CloudWatch log event can hold are written in
+/// [parts](RecordLimit::Chunk), each with a `part` member like `"2/3"`. Only
+/// the first part carries the metrics.
+///
+/// In Lambda, write to stdout and the runtime forwards each line to
+/// CloudWatch. Elsewhere, the CloudWatch agent can read them from a file.
+///
+/// ```rust
+/// use std::sync::Arc;
+///
+/// use detailer::{detail, new_detailer, scope, EmfSink};
+///
+/// let sink = Arc::new(
+///     EmfSink::new("Checkout", Vec::new())
+///         .dimensions(&["service"])
+///         .metric("query"),
+/// );
+/// let mut detailer = new_detailer!();
+/// detailer.sink(sink.clone());
+/// detailer.set_context("service", "cart");
+/// detailer.set_context("level", "gold");
+/// {
+///     let _scope = scope!(detailer, "query");
+///     detail!(detailer, "rows read");
+/// }
+/// detailer.flush();
+///
+/// let line = String::from_utf8(sink.lock().clone()).unwrap();
+/// assert!(line.starts_with(r#"{"_aws":{"Timestamp":"#));
+/// assert!(line.contains(r#""Namespace":"Checkout","Dimensions":[["service"]]"#));
+/// assert!(line.contains(r#"{"Name":"query","Unit":"Microseconds"}"#));
+/// assert!(line.contains(r#""service":"cart""#));
+/// assert!(line.contains(r#""context.level":"gold","level":"INFO""#));
+/// assert!(line.contains(r#""query":"#));
+/// assert!(line.ends_with("}\n"));
+/// ```
+///
+/// A long trace is written in parts:
+///
+/// ```rust
+/// use std::sync::Arc;
+///
+/// use detailer::{detail, new_detailer, EmfSink};
+///
+/// let sink = Arc::new(EmfSink::new("Checkout", Vec::new()));
+/// let mut detailer = new_detailer!(Info, WithTiming, 1024 * 1024);
+/// detailer.sink(sink.clone());
+/// for item in 0..4096 {
+///     detail!(detailer, "checked the stock of item number {item}");
+/// }
+/// detailer.flush();
+///
+/// let lines = String::from_utf8(sink.lock().clone()).unwrap();
+/// let parts: Vec<&str> = lines.lines().collect();
+/// assert!(parts[0].starts_with(r#"{"_aws":"#) && parts[0].contains(r#""part":"1/"#));
+/// assert!(parts[1..].iter().all(|part| !part.contains(r#""duration":"#)));
+/// ```
+pub struct EmfSink<W> {
+    writer: Mutex<W>,
+    namespace: String,
+    dimensions: Vec<&'static str>,
+    metrics: Vec<&'static str>,
+}
+
+impl<W: Write> EmfSink<W> {
+    /// Write traces with metrics in `namespace` to `writer`.
+    pub fn new(namespace: impl Into<String>, writer: W) -> Self {
+        Self {
+            writer: Mutex::new(writer),
+            namespace: namespace.into(),
+            dimensions: Vec::new(),
+            metrics: Vec::new(),
+        }
+    }
+
+    /// Split the metrics by these context fields. Traces without one of
+    /// them are split by the others.
+    pub fn dimensions(mut self, keys: &[&'static str]) -> Self {
+        self.dimensions = keys.to_vec();
+        self
+    }
+
+    /// Publish the duration of lines that read `line`, like a scope's name,
+    /// as a metric of that name. A line that is recorded more than once in a
+    /// trace has a value for each time.
+    ///
+    /// Lines named like one of the members the trace is written with, like
+    /// `duration` or `level`, are published as `metric.<line>`.
+    pub fn metric(mut self, line: &'static str) -> Self {
+        if !self.metrics.contains(&line) {
+            self.metrics.push(line);
+        }
+        self
+    }
+
+    /// Lock the writer for direct access, like reading back an in-memory
+    /// buffer.
+    pub fn lock(&self) -> std::sync::MutexGuard<'_, W> {
+        self.writer.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Take the writer back.
+    pub fn into_inner(self) -> W {
+        self.writer
+            .into_inner()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// The trace as one line of EMF
+    fn document(&self, trace: &FlushedTrace) -> String {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        let mut samples: Vec<(String, Vec<Duration>)> = self
+            .metrics
+            .iter()
+            .map(|metric| match RESERVED.contains(metric) {
+                true => (format!("metric.{metric}"), Vec::new()),
+                false => (metric.to_string(), Vec::new()),
+            })
+            .collect();
+        for (event, duration) in trace.events.iter().zip(durations(trace.events)) {
+            let (Some(duration), Some(index)) = (
+                duration,
+                self.metrics
+                    .iter()
+                    .position(|metric| *metric == event.message),
+            ) else {
+                continue;
+            };
+            samples[index].1.push(duration);
+        }
+        let first_part = trace.part.is_none_or(|(part, _)| part == 1);
+        let mut metrics: Vec<(String, Vec<Duration>)> = trace
+            .elapsed
+            .filter(|_| first_part)
+            .map(|elapsed| ("duration".to_string(), vec![elapsed]))
+            .into_iter()
+            .collect();
+        if first_part {
+            metrics.extend(samples.into_iter().filter(|(_, values)| !values.is_empty()));
+        }
+        // Context fields take the names that are left.
+        let mut taken: Vec<String> = RESERVED.iter().map(|name| name.to_string()).collect();
+        taken.extend(metrics.iter().map(|(name, _)| name.clone()));
+        let mut fields: Vec<(&str, String)> = Vec::with_capacity(trace.context.len());
+        for (key, _) in trace.context {
+            let mut name = key.to_string();
+            while taken.contains(&name) {
+                name.insert_str(0, "context.");
+            }
+            taken.push(name.clone());
+            fields.push((key, name));
+        }
+
+        let mut json = String::with_capacity(trace.text.len() + 256);
+        json.push('{');
+        if !metrics.is_empty() {
+            let _ = write!(
+                json,
+                r#""_aws":{{"Timestamp":{},"CloudWatchMetrics":[{{"Namespace":"#,
+                timestamp.as_millis()
+            );
+            push_string(&mut json, &self.namespace);
+            json.push_str(r#","Dimensions":[["#);
+            let dimensions = self
+                .dimensions
+                .iter()
+                .filter_map(|key| fields.iter().find(|(field, _)| field == key));
+            for (index, (_, name)) in dimensions.enumerate() {
+                if 0 < index {
+                    json.push(',');
+                }
+                push_string(&mut json, name);
+            }
+            json.push_str(r#"]],"Metrics":["#);
+            for (index, (name, _)) in metrics.iter().enumerate() {
+                if 0 < index {
+                    json.push(',');
+                }
+                json.push_str(r#"{"Name":"#);
+                push_string(&mut json, name);
+                json.push_str(r#","Unit":"Microseconds"}"#);
+            }
+            json.push_str("]}]},");
+        }
+
+        for (name, values) in &metrics {
+            push_string(&mut json, name);
+            json.push(':');
+            match &values[..] {
+                [value] => {
+                    let _ = write!(json, "{}", value.as_micros());
+                }
+                values => {
+                    json.push('[');
+                    for (index, value) in values.iter().enumerate() {
+                        if 0 < index {
+                            json.push(',');
+                        }
+                        let _ = write!(json, "{}", value.as_micros());
+                    }
+                    json.push(']');
+                }
+            }
+            json.push(',');
+        }
+        for ((_, value), (_, name)) in trace.context.iter().zip(&fields) {
+            push_string(&mut json, name);
+            json.push(':');
+            push_string(&mut json, value);
+            json.push(',');
+        }
+        let _ = write!(json, r#""level":"{}""#, trace.level);
+        if let Some((part, parts)) = trace.part {
+            let _ = write!(json, r#","part":"{part}/{parts}""#);
+        }
+        if let Some(trace_id) = trace.trace_id {
+            json.push_str(r#","trace_id":"#);
+            push_string(&mut json, trace_id);
+        }
+        json.push_str(r#","trace":"#);
+        push_string(&mut json, trace.body);
+        json.push_str("}\n");
+        json
+    }
+}
+
+impl<W: Write + Send> Sink for EmfSink<W> {
+    fn flush(&self, trace: &FlushedTrace) {
+        if let Err(e) = self.try_flush(trace) {
+            log::warn!("could not write trace as EMF: {e}");
+        }
+    }
+
+    fn try_flush(&self, trace: &FlushedTrace) -> Result<(), FlushError> {
+        let document = self.document(trace);
+        if MAX_EVENT < document.len() {
+            return Err(FlushError::new(format!(
+                "the trace's {} bytes of EMF are over CloudWatch's event limit",
+                document.len()
+            )));
+        }
+        let mut writer = self.lock();
+        writer.write_all(document.as_bytes())?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    fn record_limit(&self) -> Option<RecordLimit> {
+        Some(RecordLimit::Chunk(MAX_TRACE))
+    }
+
+    fn wants_events(&self) -> bool {
+        true
+    }
+}
@@ -0,0 +1,116 @@
+use std::{
+    fs::{File, OpenOptions},
+    io::{self, IoSlice, Write},
+    path::Path,
+    sync::{Mutex, PoisonError},
+};
+
+use crate::{FlushError, FlushedTrace, Sink};
+
+/// Appends flushed traces to a file, each followed by a newline.
+///
+/// Each trace goes to the file in one vectored write of the trace and its
+/// newline, so big traces aren't copied or split into many small writes.
+///
+/// ```rust
+/// use std::sync::Arc;
+///
+/// use detailer::{detail, new_detailer, FileSink};
+///
+/// # let path = std::env::temp_dir().join(format!("detailer-file-{}.log", std::process::id()));
+/// # let _ = std::fs::remove_file(&path);
+/// let mut detailer = new_detailer!(Info, WithoutTiming);
+/// detailer.sink(Arc::new(FileSink::append(&path).unwrap()));
+/// detail!(detailer, "authenticating");
+/// detail!(detailer, "authenticated");
+/// detailer.flush();
+///
+/// assert_eq!("authenticating\nauthenticated\n", std::fs::read_to_string(&path).unwrap());
+/// ```
+pub struct FileSink {
+    file: Mutex<File>,
+    #[cfg(feature = "gzip")]
+    compress_over: Option<usize>,
+}
+
+impl FileSink {
+    /// Append traces to the file at `path`, creating it if it doesn't exist.
+    pub fn append(path: impl AsRef<Path>) -> io::Result<Self> {
+        Ok(Self::new(
+            OpenOptions::new().create(true).append(true).open(path)?,
+        ))
+    }
+
+    /// Append traces to an open file.
+    pub fn new(file: File) -> Self {
+        Self {
+            file: Mutex::new(file),
+            #[cfg(feature = "gzip")]
+            compress_over: None,
+        }
+    }
+
+    /// Write traces longer than `threshold` bytes gzip compressed, like
+    /// [`WriterSink::compress_over()`](crate::WriterSink::compress_over).
+    /// [`parse::decode()`](crate::parse::decode) reads the file back.
+    ///
+    /// ```rust
+    /// use std::sync::Arc;
+    ///
+    /// use detailer::{detail, new_detailer, parse, FileSink};
+    ///
+    /// # let path = std::env::temp_dir().join(format!("detailer-gzip-{}.log", std::process::id()));
+    /// # let _ = std::fs::remove_file(&path);
+    /// let mut detailer = new_detailer!(Info, WithoutTiming);
+    /// detailer.sink(Arc::new(FileSink::append(&path).unwrap().compress_over(0)));
+    /// detail!(detailer, "authenticating");
+    /// detailer.flush();
+    ///
+    /// let bytes = std::fs::read(&path).unwrap();
+    /// assert!(bytes.starts_with(b"DTLZ"));
+    /// assert_eq!("authenticating\n", parse::decode(&bytes).unwrap());
+    /// ```
+    #[cfg(feature = "gzip")]
+    pub fn compress_over(mut self, threshold: usize) -> Self {
+        self.compress_over = Some(threshold);
+        self
+    }
+}
+
+impl Sink for FileSink {
+    fn flush(&self, trace: &FlushedTrace) {
+        if let Err(e) = self.try_flush(trace) {
+            log::warn!("could not write trace to file: {e}");
+        }
+    }
+
+    fn try_flush(&self, trace: &FlushedTrace) -> Result<(), FlushError> {
+        let mut file = self.file.lock().unwrap_or_else(PoisonError::into_inner);
+        #[cfg(feature = "gzip")]
+        if let Some(frame) = crate::compress::frame_over(self.compress_over, trace.text)? {
+            write_all_vectored(&mut *file, &mut [IoSlice::new(&frame), IoSlice::new(b"\n")])?;
+            return Ok(());
+        }
+        write_all_vectored(
+            &mut *file,
+            &mut [IoSlice::new(trace.text.as_bytes()), IoSlice::new(b"\n")],
+        )?;
+        Ok(())
+    }
+}
+
+/// Write every segment, in as few vectored writes as the writer takes.
+pub(super) fn write_all_vectored(
+    writer: &mut impl Write,
+    mut segments: &mut [IoSlice<'_>],
+) -> io::Result<()> {
+    while !segments.is_empty() {
+        match writer.write_vectored(segments) {
+            Ok(0) => return Err(io::ErrorKind::WriteZero.into()),
+            Ok(written) => IoSlice::advance_slices(&mut segments, written),
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(())
+}
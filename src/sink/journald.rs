@@ -0,0 +1,148 @@
+use std::{io, os::unix::net::UnixDatagram, path::Path};
+
+use crate::{FlushError, FlushedTrace, Sink};
+
+use super::severity;
+
+/// The longest field name journald accepts.
+const MAX_FIELD_NAME: usize = 64;
+
+/// The socket journald listens on for its native protocol.
+const JOURNAL_SOCKET: &str = "/run/systemd/journal/socket";
+
+/// Writes flushed traces to systemd-journald.
+///
+/// Besides the trace as `MESSAGE`, each entry carries `TRACE_ID`,
+/// `DURATION_US` and `LINE_COUNT` fields when the trace has them, so traces
+/// can be found with field filters like `journalctl TRACE_ID=4bf92f35`. Each
+/// context key is a field too, upper cased: `request_id` is `REQUEST_ID`.
+/// Keys that don't start with a letter, which journald would reject or
+/// take for one of its own fields, are prefixed: `_shard` is
+/// `CONTEXT__SHARD`.
+///
+/// ```rust
+/// use std::{os::unix::net::UnixDatagram, sync::Arc};
+///
+/// use detailer::{detail, new_detailer, JournaldSink};
+///
+/// # let directory = std::env::temp_dir().join(format!("detailer-journald-{}", std::process::id()));
+/// # std::fs::create_dir_all(&directory).unwrap();
+/// # let socket_path = directory.join("socket");
+/// # let _ = std::fs::remove_file(&socket_path);
+/// let journal = UnixDatagram::bind(&socket_path).unwrap();
+///
+/// let mut detailer = new_detailer!(Info, WithoutTiming);
+/// detailer.sink(Arc::new(JournaldSink::with_socket(&socket_path).unwrap()));
+/// detailer.trace_id("4bf92f35");
+/// detail!(detailer, "authenticating");
+/// detailer.flush();
+///
+/// let mut entry = [0; 1024];
+/// let length = journal.recv(&mut entry).unwrap();
+/// let entry = String::from_utf8_lossy(&entry[..length]);
+/// assert!(entry.contains("MESSAGE=authenticating\n"));
+/// assert!(entry.contains("PRIORITY=6\n"));
+/// assert!(entry.contains("TRACE_ID=4bf92f35\n"));
+/// assert!(entry.contains("LINE_COUNT=1\n"));
+///
+/// detailer.set_context("2fa", "totp");
+/// detail!(detailer, "verified");
+/// detailer.flush();
+/// let mut entry = [0; 1024];
+/// let length = journal.recv(&mut entry).unwrap();
+/// assert!(String::from_utf8_lossy(&entry[..length]).contains("\nCONTEXT_2FA=totp\n"));
+/// ```
+pub struct JournaldSink {
+    socket: UnixDatagram,
+    identifier: Option<String>,
+}
+
+impl JournaldSink {
+    /// Connect to the system journal.
+    pub fn new() -> io::Result<Self> {
+        Self::with_socket(JOURNAL_SOCKET)
+    }
+
+    /// Connect to a journal listening somewhere other than the usual socket.
+    pub fn with_socket(path: impl AsRef<Path>) -> io::Result<Self> {
+        let socket = UnixDatagram::unbound()?;
+        socket.connect(path)?;
+        Ok(Self {
+            socket,
+            identifier: None,
+        })
+    }
+
+    /// Tag entries with a `SYSLOG_IDENTIFIER`, which `journalctl -t` filters on.
+    pub fn identifier(mut self, identifier: impl Into<String>) -> Self {
+        self.identifier = Some(identifier.into());
+        self
+    }
+}
+
+impl Sink for JournaldSink {
+    fn flush(&self, trace: &FlushedTrace) {
+        if let Err(e) = self.try_flush(trace) {
+            log::warn!("could not write trace to journald: {e}");
+        }
+    }
+
+    fn try_flush(&self, trace: &FlushedTrace) -> Result<(), FlushError> {
+        let mut entry = Vec::with_capacity(trace.text.len() + 128);
+        field(&mut entry, "MESSAGE", trace.text.as_bytes());
+        field(
+            &mut entry,
+            "PRIORITY",
+            severity(trace.level).to_string().as_bytes(),
+        );
+        if let Some(identifier) = &self.identifier {
+            field(&mut entry, "SYSLOG_IDENTIFIER", identifier.as_bytes());
+        }
+        if let Some(trace_id) = trace.trace_id {
+            field(&mut entry, "TRACE_ID", trace_id.as_bytes());
+        }
+        for (key, value) in trace.context {
+            field(&mut entry, &field_name(key), value.as_bytes());
+        }
+        if let Some(elapsed) = trace.elapsed {
+            field(
+                &mut entry,
+                "DURATION_US",
+                elapsed.as_micros().to_string().as_bytes(),
+            );
+        }
+        field(&mut entry, "LINE_COUNT", trace.lines.to_string().as_bytes());
+        self.socket.send(&entry)?;
+        Ok(())
+    }
+}
+
+/// The journald field for a context key: upper case letters, digits and
+/// underscores, starting with a letter.
+fn field_name(key: &str) -> String {
+    let mut name = String::with_capacity(key.len() + "CONTEXT_".len());
+    if !key.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        name.push_str("CONTEXT_");
+    }
+    name.extend(key.chars().map(|c| match c {
+        'a'..='z' => c.to_ascii_uppercase(),
+        'A'..='Z' | '0'..='9' => c,
+        _ => '_',
+    }));
+    name.truncate(MAX_FIELD_NAME);
+    name
+}
+
+/// Append one field in journald's native format. Values with newlines, like
+/// most traces, are written length-prefixed.
+fn field(entry: &mut Vec<u8>, name: &str, value: &[u8]) {
+    entry.extend_from_slice(name.as_bytes());
+    if value.contains(&b'\n') {
+        entry.push(b'\n');
+        entry.extend_from_slice(&(value.len() as u64).to_le_bytes());
+    } else {
+        entry.push(b'=');
+    }
+    entry.extend_from_slice(value);
+    entry.push(b'\n');
+}
@@ -0,0 +1,91 @@
+use metrics::{describe_histogram, histogram, Histogram, Unit};
+
+use crate::{diff::durations, FlushedTrace, Sink};
+
+/// Records how long the scopes of each flushed trace took as histograms
+/// with the [`metrics`] crate, for the recorder the program installed.
+///
+/// Only [registered](Self::scope) scopes are recorded, each in a histogram
+/// of its own named after the sink's prefix and the scope's line, in
+/// microseconds. A line that isn't a scope is recorded as the time until the
+/// next line, and a line recorded more than once in a trace is recorded each
+/// time.
+///
+/// ```rust
+/// use std::sync::Arc;
+///
+/// use detailer::{detail, new_detailer, scope, MetricsSink};
+/// use metrics::Unit;
+/// use metrics_util::debugging::{DebugValue, DebuggingRecorder};
+///
+/// let recorder = DebuggingRecorder::new();
+/// let snapshotter = recorder.snapshotter();
+/// metrics::with_local_recorder(&recorder, || {
+///     let sink = MetricsSink::new("checkout")
+///         .scope("query", "Time spent waiting on the database");
+///     let mut detailer = new_detailer!();
+///     detailer.sink(Arc::new(sink));
+///     for _ in 0..2 {
+///         let _scope = scope!(detailer, "query");
+///         detail!(detailer, "rows read");
+///     }
+///     detail!(detailer, "rendered");
+///     detailer.flush();
+/// });
+///
+/// let metrics = snapshotter.snapshot().into_vec();
+/// assert_eq!(1, metrics.len());
+/// let (key, unit, description, value) = &metrics[0];
+/// assert_eq!("checkout.query", key.key().name());
+/// assert_eq!(Some(Unit::Microseconds), *unit);
+/// assert_eq!("Time spent waiting on the database", &**description.as_ref().unwrap());
+/// assert!(matches!(value, DebugValue::Histogram(values) if values.len() == 2));
+/// ```
+pub struct MetricsSink {
+    prefix: String,
+    scopes: Vec<(&'static str, Histogram)>,
+}
+
+impl MetricsSink {
+    /// Record histograms named `<prefix>.<scope>`.
+    pub fn new(prefix: impl Into<String>) -> Self {
+        Self {
+            prefix: prefix.into(),
+            scopes: Vec::new(),
+        }
+    }
+
+    /// Record how long the lines that read `line`, like a scope's name,
+    /// took. The histogram is described with `description` and registered
+    /// now, so it reaches dashboards with its unit and description before
+    /// the first trace is flushed. Register scopes after the recorder is
+    /// installed.
+    pub fn scope(mut self, line: &'static str, description: &'static str) -> Self {
+        let name = format!("{}.{line}", self.prefix);
+        describe_histogram!(name.clone(), Unit::Microseconds, description);
+        if !self.scopes.iter().any(|(scope, _)| *scope == line) {
+            self.scopes.push((line, histogram!(name)));
+        }
+        self
+    }
+}
+
+impl Sink for MetricsSink {
+    fn flush(&self, trace: &FlushedTrace) {
+        for (event, duration) in trace.events.iter().zip(durations(trace.events)) {
+            let (Some(duration), Some((_, histogram))) = (
+                duration,
+                self.scopes
+                    .iter()
+                    .find(|(scope, _)| *scope == event.message),
+            ) else {
+                continue;
+            };
+            histogram.record(duration.as_micros() as f64);
+        }
+    }
+
+    fn wants_events(&self) -> bool {
+        true
+    }
+}
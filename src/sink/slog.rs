@@ -0,0 +1,68 @@
+use slog::Logger;
+
+use crate::{FlushedTrace, Sink};
+
+/// Flushes traces through a slog [`Logger`], each as one record at the
+/// trace's level, with its `trace_id` and `lines` as key-value pairs.
+///
+/// ```rust
+/// use std::sync::{Arc, Mutex};
+///
+/// use detailer::{detail, new_detailer, SlogSink};
+/// use slog::{Drain, OwnedKVList, Record};
+///
+/// #[derive(Clone, Default)]
+/// struct Messages(Arc<Mutex<Vec<String>>>);
+/// impl Drain for Messages {
+///     type Ok = ();
+///     type Err = slog::Never;
+///     fn log(&self, record: &Record, _: &OwnedKVList) -> Result<(), slog::Never> {
+///         self.0.lock().unwrap().push(record.msg().to_string());
+///         Ok(())
+///     }
+/// }
+///
+/// let messages = Messages::default();
+/// let logger = slog::Logger::root(messages.clone(), slog::o!("service" => "checkout"));
+///
+/// let mut detailer = new_detailer!(Info, WithoutTiming);
+/// detailer.sink(Arc::new(SlogSink::new(logger)));
+/// detail!(detailer, "authenticated");
+/// detail!(detailer, "request complete");
+/// detailer.flush();
+/// assert_eq!(["authenticated\nrequest complete"], messages.0.lock().unwrap()[..]);
+/// ```
+pub struct SlogSink {
+    logger: Logger,
+}
+
+impl SlogSink {
+    /// Flush traces through `logger`.
+    pub fn new(logger: Logger) -> Self {
+        Self { logger }
+    }
+}
+
+impl Sink for SlogSink {
+    fn flush(&self, trace: &FlushedTrace) {
+        let logger = &self.logger;
+        let (text, trace_id, lines) = (trace.text, trace.trace_id, trace.lines);
+        match trace.level {
+            log::Level::Error => {
+                slog::error!(logger, "{}", text; "trace_id" => trace_id, "lines" => lines)
+            }
+            log::Level::Warn => {
+                slog::warn!(logger, "{}", text; "trace_id" => trace_id, "lines" => lines)
+            }
+            log::Level::Info => {
+                slog::info!(logger, "{}", text; "trace_id" => trace_id, "lines" => lines)
+            }
+            log::Level::Debug => {
+                slog::debug!(logger, "{}", text; "trace_id" => trace_id, "lines" => lines)
+            }
+            log::Level::Trace => {
+                slog::trace!(logger, "{}", text; "trace_id" => trace_id, "lines" => lines)
+            }
+        }
+    }
+}
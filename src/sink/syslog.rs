@@ -0,0 +1,276 @@
+use std::{
+    fmt::Write,
+    io,
+    net::{Ipv4Addr, Ipv6Addr, SocketAddr, ToSocketAddrs, UdpSocket},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+#[cfg(unix)]
+use std::{os::unix::net::UnixDatagram, path::Path};
+
+use crate::{clock::write_rfc3339, FlushError, FlushedTrace, Sink};
+
+use super::{severity, split};
+
+/// The largest message every RFC 5424 receiver should accept over UDP.
+const DEFAULT_MAX_DATAGRAM: usize = 2048;
+
+/// Writes flushed traces as RFC 5424 syslog messages.
+///
+/// Traces that don't fit in one datagram are split across several messages,
+/// preferably between lines. Each part carries a `part="2/5"` parameter, and
+/// the trace ID when the trace has one and the detailer's context, in
+/// `[detailer@32473 ...]` structured data. 32473 is the private enterprise
+/// number reserved for examples.
+///
+/// ```rust
+/// use std::{net::UdpSocket, sync::Arc};
+///
+/// use detailer::{detail, new_detailer, Facility, SyslogSink};
+///
+/// let server = UdpSocket::bind("127.0.0.1:0").unwrap();
+/// let sink = SyslogSink::udp(server.local_addr().unwrap())
+///     .unwrap()
+///     .facility(Facility::Local0)
+///     .app_name("checkout");
+///
+/// let mut detailer = new_detailer!(Info, WithoutTiming);
+/// detailer.sink(Arc::new(sink));
+/// detail!(detailer, "authenticating");
+/// detailer.flush();
+///
+/// let mut message = [0; 2048];
+/// let length = server.recv(&mut message).unwrap();
+/// let message = String::from_utf8_lossy(&message[..length]);
+/// assert!(message.starts_with("<134>1 "));
+/// assert!(message.contains(" checkout "));
+/// assert!(message.ends_with("] authenticating"));
+/// ```
+pub struct SyslogSink {
+    transport: Transport,
+    facility: Facility,
+    hostname: String,
+    app_name: String,
+    max_datagram: usize,
+}
+
+enum Transport {
+    Udp(UdpSocket),
+    #[cfg(unix)]
+    Unix(UnixDatagram),
+}
+
+/// The syslog facility messages are logged under.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[allow(missing_docs)]
+pub enum Facility {
+    Kern = 0,
+    User = 1,
+    Mail = 2,
+    Daemon = 3,
+    Auth = 4,
+    Syslog = 5,
+    Lpr = 6,
+    News = 7,
+    Uucp = 8,
+    Cron = 9,
+    Authpriv = 10,
+    Ftp = 11,
+    Local0 = 16,
+    Local1 = 17,
+    Local2 = 18,
+    Local3 = 19,
+    Local4 = 20,
+    Local5 = 21,
+    Local6 = 22,
+    Local7 = 23,
+}
+
+impl SyslogSink {
+    /// Send messages to a syslog server over UDP.
+    pub fn udp(server: impl ToSocketAddrs) -> io::Result<Self> {
+        let server = server
+            .to_socket_addrs()?
+            .next()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "no server address"))?;
+        let local: SocketAddr = match server {
+            SocketAddr::V4(_) => (Ipv4Addr::UNSPECIFIED, 0).into(),
+            SocketAddr::V6(_) => (Ipv6Addr::UNSPECIFIED, 0).into(),
+        };
+        let socket = UdpSocket::bind(local)?;
+        socket.connect(server)?;
+        Ok(Self::new(Transport::Udp(socket)))
+    }
+
+    /// Send messages to a local syslog daemon's socket, usually `/dev/log`.
+    #[cfg(unix)]
+    pub fn unix(path: impl AsRef<Path>) -> io::Result<Self> {
+        let socket = UnixDatagram::unbound()?;
+        socket.connect(path)?;
+        Ok(Self::new(Transport::Unix(socket)))
+    }
+
+    fn new(transport: Transport) -> Self {
+        Self {
+            transport,
+            facility: Facility::User,
+            hostname: "-".to_string(),
+            app_name: header_field(
+                std::env::current_exe()
+                    .ok()
+                    .and_then(|exe| Some(exe.file_name()?.to_string_lossy().into_owned()))
+                    .unwrap_or_default(),
+            ),
+            max_datagram: DEFAULT_MAX_DATAGRAM,
+        }
+    }
+
+    /// Log under `facility` instead of `User`.
+    pub fn facility(mut self, facility: Facility) -> Self {
+        self.facility = facility;
+        self
+    }
+
+    /// Report `hostname` as the sending host. By default no host is reported.
+    pub fn hostname(mut self, hostname: impl Into<String>) -> Self {
+        self.hostname = header_field(hostname.into());
+        self
+    }
+
+    /// Report `app_name` as the sending application, instead of the name of
+    /// the executable.
+    pub fn app_name(mut self, app_name: impl Into<String>) -> Self {
+        self.app_name = header_field(app_name.into());
+        self
+    }
+
+    /// Split traces into messages of at most `max_datagram` bytes instead of
+    /// 2048.
+    ///
+    /// ```rust
+    /// use std::{net::UdpSocket, sync::Arc};
+    ///
+    /// use detailer::{detail, new_detailer, SyslogSink};
+    ///
+    /// let server = UdpSocket::bind("127.0.0.1:0").unwrap();
+    /// let sink = SyslogSink::udp(server.local_addr().unwrap())
+    ///     .unwrap()
+    ///     .app_name("app")
+    ///     .max_datagram(95);
+    ///
+    /// let mut detailer = new_detailer!(Info, WithoutTiming);
+    /// detailer.sink(Arc::new(sink));
+    /// detail!(detailer, "first line");
+    /// detail!(detailer, "second line");
+    /// detailer.flush();
+    ///
+    /// let mut message = [0; 2048];
+    /// let length = server.recv(&mut message).unwrap();
+    /// assert!(String::from_utf8_lossy(&message[..length]).ends_with("part=\"1/2\"] first line"));
+    /// let length = server.recv(&mut message).unwrap();
+    /// assert!(String::from_utf8_lossy(&message[..length]).ends_with("part=\"2/2\"] second line"));
+    /// ```
+    pub fn max_datagram(mut self, max_datagram: usize) -> Self {
+        self.max_datagram = max_datagram;
+        self
+    }
+
+    fn header(&self, trace: &FlushedTrace, timestamp: &str, part: usize, parts: usize) -> String {
+        let mut header = String::with_capacity(128);
+        let _ = write!(
+            header,
+            "<{}>1 {timestamp} {} {} {} - [detailer@32473",
+            self.facility as u8 * 8 + severity(trace.level),
+            self.hostname,
+            self.app_name,
+            std::process::id(),
+        );
+        if let Some(trace_id) = trace.trace_id {
+            param(&mut header, "trace_id", trace_id);
+        }
+        for (key, value) in trace.context {
+            let name: String = key
+                .chars()
+                .filter(|c| c.is_ascii_graphic() && !matches!(c, '=' | ']' | '"'))
+                .take(32)
+                .collect();
+            if !name.is_empty() {
+                param(&mut header, &name, value);
+            }
+        }
+        let _ = write!(header, " part=\"{part}/{parts}\"] ");
+        header
+    }
+
+    fn send(&self, message: &[u8]) -> io::Result<usize> {
+        match &self.transport {
+            Transport::Udp(socket) => socket.send(message),
+            #[cfg(unix)]
+            Transport::Unix(socket) => socket.send(message),
+        }
+    }
+}
+
+impl Sink for SyslogSink {
+    fn flush(&self, trace: &FlushedTrace) {
+        if let Err(e) = self.try_flush(trace) {
+            log::warn!("could not write trace to syslog: {e}");
+        }
+    }
+
+    fn try_flush(&self, trace: &FlushedTrace) -> Result<(), FlushError> {
+        let mut timestamp = String::with_capacity(32);
+        let _ = write_rfc3339(
+            &mut timestamp,
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default(),
+        );
+
+        // Size the parts with the longest header any of them could have: there
+        // can't be more parts than bytes.
+        let parts = trace.text.len();
+        let longest_header = self.header(trace, &timestamp, parts, parts).len();
+        let budget = self.max_datagram.saturating_sub(longest_header).max(1);
+        let parts = split(trace.text, budget);
+
+        let mut message = Vec::with_capacity(self.max_datagram);
+        for (index, part) in parts.iter().enumerate() {
+            message.clear();
+            message.extend_from_slice(
+                self.header(trace, &timestamp, index + 1, parts.len())
+                    .as_bytes(),
+            );
+            message.extend_from_slice(part.as_bytes());
+            self.send(&message)?;
+        }
+        Ok(())
+    }
+}
+
+/// Append a structured data parameter, escaping its value.
+fn param(header: &mut String, name: &str, value: &str) {
+    let _ = write!(header, " {name}=\"");
+    for c in value.chars() {
+        if matches!(c, '"' | '\\' | ']') {
+            header.push('\\');
+        }
+        header.push(c);
+    }
+    header.push('"');
+}
+
+/// Header fields are up to 48 printable ASCII characters without spaces, or
+/// `-` when empty.
+fn header_field(value: String) -> String {
+    let value: String = value
+        .chars()
+        .filter(|c| c.is_ascii_graphic())
+        .take(48)
+        .collect();
+    if value.is_empty() {
+        "-".to_string()
+    } else {
+        value
+    }
+}
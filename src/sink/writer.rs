@@ -0,0 +1,156 @@
+use std::{
+    io::{IoSlice, Write},
+    sync::{Mutex, PoisonError},
+};
+
+use crate::{FlushError, FlushedTrace, Sink};
+
+use super::file::write_all_vectored;
+
+/// Writes flushed traces to any [`Write`], like a TCP stream, a pipe or a
+/// compressing encoder, each followed by a newline.
+///
+/// Writes go through a lock, so one trace is never interleaved with another.
+/// How often the writer itself is flushed is up to its [`WriterFlush`]
+/// policy.
+///
+/// ```rust
+/// use std::sync::Arc;
+///
+/// use detailer::{detail, new_detailer, WriterSink};
+///
+/// let sink = Arc::new(WriterSink::new(Vec::new()));
+/// let mut detailer = new_detailer!(Info, WithoutTiming);
+/// detailer.sink(sink.clone());
+/// detail!(detailer, "authenticating");
+/// detailer.flush();
+///
+/// assert_eq!(b"authenticating\n", &sink.lock()[..]);
+/// ```
+pub struct WriterSink<W> {
+    writer: Mutex<W>,
+    policy: WriterFlush,
+    #[cfg(feature = "gzip")]
+    compress_over: Option<usize>,
+}
+
+/// When a [`WriterSink`] flushes its writer
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum WriterFlush {
+    /// After each trace. This is the default.
+    #[default]
+    EachTrace,
+    /// After each line of each trace, for readers that follow the output
+    /// line by line, like a terminal or `tail -f` on a pipe
+    EachLine,
+    /// Only when the writer decides to, like a `BufWriter` when it fills
+    /// up, or when it is dropped
+    Never,
+}
+
+impl<W: Write> WriterSink<W> {
+    /// Write traces to `writer`.
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer: Mutex::new(writer),
+            policy: WriterFlush::default(),
+            #[cfg(feature = "gzip")]
+            compress_over: None,
+        }
+    }
+
+    /// Choose when the writer is flushed.
+    pub fn flush_policy(mut self, policy: WriterFlush) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Write traces longer than `threshold` bytes gzip compressed, in a frame
+    /// that [`parse::decode()`](crate::parse::decode) finds and decompresses
+    /// among the plain traces.
+    ///
+    /// ```rust
+    /// use std::sync::Arc;
+    ///
+    /// use detailer::{detail, new_detailer, parse, WriterSink};
+    ///
+    /// let sink = Arc::new(WriterSink::new(Vec::new()).compress_over(64));
+    /// let mut detailer = new_detailer!(Info, WithoutTiming);
+    /// detailer.sink(sink.clone());
+    /// detail!(detailer, "authenticating");
+    /// detailer.flush();
+    /// for batch in 0..20 {
+    ///     detail!(detailer, "loaded batch {batch}");
+    /// }
+    /// detailer.flush();
+    ///
+    /// let written = sink.lock().clone();
+    /// assert!(written.len() < 200);
+    /// let text = parse::decode(&written).unwrap();
+    /// assert!(text.starts_with("authenticating\nloaded batch 0\nloaded batch 1\n"));
+    /// assert!(text.ends_with("loaded batch 19\n"));
+    /// ```
+    #[cfg(feature = "gzip")]
+    pub fn compress_over(mut self, threshold: usize) -> Self {
+        self.compress_over = Some(threshold);
+        self
+    }
+
+    /// Lock the writer for direct access, like reading back an in-memory
+    /// buffer.
+    pub fn lock(&self) -> std::sync::MutexGuard<'_, W> {
+        self.writer.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Take the writer back, like to finish a compressed stream.
+    pub fn into_inner(self) -> W {
+        self.writer
+            .into_inner()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl<W: Write + Send> Sink for WriterSink<W> {
+    fn flush(&self, trace: &FlushedTrace) {
+        if let Err(e) = self.try_flush(trace) {
+            log::warn!("could not write trace: {e}");
+        }
+    }
+
+    fn try_flush(&self, trace: &FlushedTrace) -> Result<(), FlushError> {
+        let mut writer = self.lock();
+        #[cfg(feature = "gzip")]
+        if let Some(frame) = crate::compress::frame_over(self.compress_over, trace.text)? {
+            write_all_vectored(
+                &mut *writer,
+                &mut [IoSlice::new(&frame), IoSlice::new(b"\n")],
+            )?;
+            if self.policy != WriterFlush::Never {
+                writer.flush()?;
+            }
+            return Ok(());
+        }
+        match self.policy {
+            WriterFlush::EachLine => {
+                for line in trace.text.split('\n') {
+                    write_all_vectored(
+                        &mut *writer,
+                        &mut [IoSlice::new(line.as_bytes()), IoSlice::new(b"\n")],
+                    )?;
+                    writer.flush()?;
+                }
+            }
+            WriterFlush::EachTrace | WriterFlush::Never => {
+                write_all_vectored(
+                    &mut *writer,
+                    &mut [IoSlice::new(trace.text.as_bytes()), IoSlice::new(b"\n")],
+                )?;
+                if self.policy == WriterFlush::EachTrace {
+                    writer.flush()?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
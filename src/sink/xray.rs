@@ -0,0 +1,265 @@
+use std::{
+    fmt::Write,
+    io,
+    net::{Ipv4Addr, Ipv6Addr, SocketAddr, ToSocketAddrs, UdpSocket},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    id,
+    json::push_string,
+    parse::{self, Scope},
+    FlushError, FlushedTrace, Sink,
+};
+
+/// Where the X-Ray daemon listens for segments by default.
+const DEFAULT_DAEMON: &str = "127.0.0.1:2000";
+
+/// The header each segment document is sent after.
+const DAEMON_HEADER: &str = "{\"format\":\"json\",\"version\":1}\n";
+
+/// The longest segment name X-Ray accepts.
+const MAX_NAME: usize = 200;
+
+/// Sends flushed traces to the AWS X-Ray daemon as segments.
+///
+/// Each trace is a segment, with a subsegment for each of its scopes, nested
+/// the way the scopes are. When the detailer's
+/// [trace ID](crate::Detailer::trace_id) is an `X-Amzn-Trace-Id` header,
+/// like `Root=1-5759e988-bd862e3fe1be46a994272793;Parent=53995c3f42cd8ad8;Sampled=1`,
+/// or just its root, the segment joins that trace under its parent, and
+/// traces the header doesn't sample aren't sent. Other traces get a new
+/// X-Ray trace ID, and keep the detailer's as an annotation. Context fields
+/// are annotations too, and traces flushed at error are marked as faults.
+///
+/// Subsegments come from the lines as they were recorded, so the trace's
+/// [line format](crate::Detailer::line_format) and
+/// [elapsed format](crate::Detailer::elapsed_format) don't change them. A
+/// trace flushed in [parts](crate::RecordLimit::Chunk) is sent once, with
+/// its first part. Traces recorded without timing become a segment with no
+/// subsegments.
+///
+/// ```rust
+/// use std::{net::UdpSocket, sync::Arc};
+///
+/// use detailer::{detail, new_detailer, scope, ElapsedFormat, XRaySink};
+///
+/// let daemon = UdpSocket::bind("127.0.0.1:0").unwrap();
+/// let sink = XRaySink::with_daemon("checkout", daemon.local_addr().unwrap()).unwrap();
+///
+/// let mut detailer = new_detailer!();
+/// detailer.sink(Arc::new(sink));
+/// detailer.elapsed_format(ElapsedFormat::Scaled);
+/// detailer.trace_id("Root=1-5759e988-bd862e3fe1be46a994272793;Parent=53995c3f42cd8ad8;Sampled=1");
+/// {
+///     let _scope = scope!(detailer, "query");
+///     detail!(detailer, "rows read");
+/// }
+/// detailer.flush();
+///
+/// let mut segment = [0; 4096];
+/// let length = daemon.recv(&mut segment).unwrap();
+/// let segment = String::from_utf8_lossy(&segment[..length]);
+/// assert!(segment.starts_with("{\"format\":\"json\",\"version\":1}\n{\"name\":\"checkout\""));
+/// assert!(segment.contains(r#""trace_id":"1-5759e988-bd862e3fe1be46a994272793""#));
+/// assert!(segment.contains(r#""parent_id":"53995c3f42cd8ad8""#));
+/// assert!(segment.contains(r#""subsegments":[{"name":"query""#));
+/// ```
+pub struct XRaySink {
+    socket: UdpSocket,
+    name: String,
+}
+
+impl XRaySink {
+    /// Send segments named `name` to the daemon at `AWS_XRAY_DAEMON_ADDRESS`,
+    /// as Lambda sets it, or on this host.
+    pub fn new(name: impl Into<String>) -> io::Result<Self> {
+        let daemon =
+            std::env::var("AWS_XRAY_DAEMON_ADDRESS").unwrap_or_else(|_| DEFAULT_DAEMON.into());
+        Self::with_daemon(name, daemon.as_str())
+    }
+
+    /// Send segments named `name` to a daemon somewhere else.
+    pub fn with_daemon(name: impl Into<String>, daemon: impl ToSocketAddrs) -> io::Result<Self> {
+        let daemon = daemon
+            .to_socket_addrs()?
+            .next()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "no daemon address"))?;
+        let local: SocketAddr = match daemon {
+            SocketAddr::V4(_) => (Ipv4Addr::UNSPECIFIED, 0).into(),
+            SocketAddr::V6(_) => (Ipv6Addr::UNSPECIFIED, 0).into(),
+        };
+        let socket = UdpSocket::bind(local)?;
+        socket.connect(daemon)?;
+        Ok(Self {
+            socket,
+            name: name.into(),
+        })
+    }
+
+    /// The trace as a segment document, unless its header doesn't sample it
+    fn segment(&self, trace: &FlushedTrace) -> Option<String> {
+        let header = trace.trace_id.and_then(TraceHeader::parse);
+        if header.as_ref().and_then(|header| header.sampled) == Some(false) {
+            return None;
+        }
+        let elapsed = trace.elapsed.unwrap_or_default();
+        let start = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .saturating_sub(elapsed);
+
+        let mut json = String::from(DAEMON_HEADER);
+        json.push_str(r#"{"name":"#);
+        push_string(&mut json, truncated(&self.name));
+        json.push_str(r#","id":"#);
+        push_string(&mut json, &id::span_id());
+        json.push_str(r#","trace_id":"#);
+        match &header {
+            Some(header) => push_string(&mut json, header.root),
+            None => {
+                let random = format!("{}{}", id::span_id(), id::span_id());
+                let trace_id = format!("1-{:08x}-{}", start.as_secs(), &random[..24]);
+                push_string(&mut json, &trace_id);
+            }
+        }
+        if let Some(parent) = header.as_ref().and_then(|header| header.parent) {
+            json.push_str(r#","parent_id":"#);
+            push_string(&mut json, parent);
+        }
+        push_times(&mut json, start, elapsed);
+        if trace.level == log::Level::Error {
+            json.push_str(r#","fault":true"#);
+        }
+        json.push_str(r#","annotations":{"#);
+        let detailer_id = match header {
+            Some(_) => None,
+            None => trace
+                .trace_id
+                .map(|trace_id| ("detailer_trace_id", trace_id)),
+        };
+        let annotations = detailer_id.into_iter().chain(
+            trace
+                .context
+                .iter()
+                .map(|(key, value)| (*key, value.as_str())),
+        );
+        for (index, (key, value)) in annotations.enumerate() {
+            if 0 < index {
+                json.push(',');
+            }
+            let key: String = key
+                .chars()
+                .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+                .collect();
+            push_string(&mut json, &key);
+            json.push(':');
+            push_string(&mut json, value);
+        }
+        json.push('}');
+        push_subsegments(&mut json, &parse::scopes_in(trace.events), None, start);
+        json.push('}');
+        Some(json)
+    }
+}
+
+impl Sink for XRaySink {
+    fn flush(&self, trace: &FlushedTrace) {
+        if let Err(e) = self.try_flush(trace) {
+            log::warn!("could not send trace to the X-Ray daemon: {e}");
+        }
+    }
+
+    fn try_flush(&self, trace: &FlushedTrace) -> Result<(), FlushError> {
+        if trace.part.is_some_and(|(part, _)| 1 < part) {
+            return Ok(());
+        }
+        if let Some(segment) = self.segment(trace) {
+            self.socket.send(segment.as_bytes())?;
+        }
+        Ok(())
+    }
+
+    fn wants_events(&self) -> bool {
+        true
+    }
+}
+
+/// The parts of an `X-Amzn-Trace-Id` header a segment is sent with
+struct TraceHeader<'a> {
+    root: &'a str,
+    parent: Option<&'a str>,
+    sampled: Option<bool>,
+}
+
+impl<'a> TraceHeader<'a> {
+    fn parse(header: &'a str) -> Option<Self> {
+        let mut root = None;
+        let mut parent = None;
+        let mut sampled = None;
+        for part in header.split(';').map(str::trim) {
+            if let Some(value) = part.strip_prefix("Root=") {
+                root = Some(value);
+            } else if let Some(value) = part.strip_prefix("Parent=") {
+                parent = Some(value);
+            } else if let Some(value) = part.strip_prefix("Sampled=") {
+                sampled = match value {
+                    "0" => Some(false),
+                    "1" => Some(true),
+                    _ => None,
+                };
+            } else if part.starts_with("1-") {
+                root = Some(part);
+            }
+        }
+        Some(Self {
+            root: root.filter(|root| root.starts_with("1-"))?,
+            parent,
+            sampled,
+        })
+    }
+}
+
+/// Append the `subsegments` of a segment, or of the scope at `parent`
+fn push_subsegments(json: &mut String, scopes: &[Scope], parent: Option<usize>, start: Duration) {
+    let mut children = scopes
+        .iter()
+        .enumerate()
+        .filter(|(_, scope)| scope.parent == parent)
+        .peekable();
+    if children.peek().is_none() {
+        return;
+    }
+    json.push_str(r#","subsegments":["#);
+    for (index, (scope_index, scope)) in children.enumerate() {
+        if 0 < index {
+            json.push(',');
+        }
+        json.push_str(r#"{"name":"#);
+        push_string(json, truncated(&scope.name));
+        json.push_str(r#","id":"#);
+        push_string(json, &id::span_id());
+        push_times(json, start + scope.start, scope.duration);
+        push_subsegments(json, scopes, Some(scope_index), start);
+        json.push('}');
+    }
+    json.push(']');
+}
+
+/// Append the `start_time` and `end_time` of a segment, in seconds
+fn push_times(json: &mut String, start: Duration, duration: Duration) {
+    let _ = write!(
+        json,
+        r#","start_time":{:.6},"end_time":{:.6}"#,
+        start.as_secs_f64(),
+        (start + duration).as_secs_f64(),
+    );
+}
+
+/// A name, cut short to the length X-Ray accepts
+fn truncated(name: &str) -> &str {
+    match name.char_indices().nth(MAX_NAME) {
+        Some((end, _)) => &name[..end],
+        None => name,
+    }
+}
@@ -0,0 +1,214 @@
+use alloc::string::String;
+use core::{
+    borrow::Borrow,
+    cmp::Ordering,
+    fmt::{self, Debug, Display},
+    hash::{Hash, Hasher},
+    ops::Deref,
+};
+
+/// The longest message, in bytes, that is kept inline
+const INLINE: usize = 23;
+
+/// A message that is kept inline, without a heap allocation, when it is
+/// shorter than 24 bytes, the way most lines of a trace are.
+///
+/// This is what the structured forms of a line, like [`parse::Event`](crate::parse::Event)
+/// and [`LineEvent`](crate::LineEvent), keep their message in. It derefs to
+/// [`str`] and compares like one.
+///
+/// ```rust
+/// use detailer::SmallString;
+///
+/// let mut message = SmallString::from("connected");
+/// assert!(message.is_inline());
+/// assert_eq!("connected", message);
+///
+/// message.push_str(" to the primary replica");
+/// assert!(!message.is_inline());
+/// assert_eq!("connected to the primary replica", message.as_str());
+/// ```
+#[derive(Clone)]
+pub struct SmallString(Repr);
+
+#[derive(Clone)]
+enum Repr {
+    Inline { len: u8, bytes: [u8; INLINE] },
+    Heap(String),
+}
+
+impl SmallString {
+    /// An empty message
+    pub const fn new() -> Self {
+        Self(Repr::Inline {
+            len: 0,
+            bytes: [0; INLINE],
+        })
+    }
+
+    /// The message
+    pub fn as_str(&self) -> &str {
+        match &self.0 {
+            // Inline bytes are only ever copied from whole strings.
+            Repr::Inline { len, bytes } => {
+                core::str::from_utf8(&bytes[..*len as usize]).unwrap_or_default()
+            }
+            Repr::Heap(text) => text,
+        }
+    }
+
+    /// Whether the message is kept inline, rather than on the heap
+    pub fn is_inline(&self) -> bool {
+        matches!(self.0, Repr::Inline { .. })
+    }
+
+    /// Add `text` to the end, moving the message to the heap if it no
+    /// longer fits inline.
+    pub fn push_str(&mut self, text: &str) {
+        match &mut self.0 {
+            Repr::Inline { len, bytes } if *len as usize + text.len() <= INLINE => {
+                let start = *len as usize;
+                bytes[start..start + text.len()].copy_from_slice(text.as_bytes());
+                *len += text.len() as u8;
+            }
+            Repr::Inline { .. } => {
+                let mut heap = String::with_capacity(self.len() + text.len());
+                heap.push_str(self.as_str());
+                heap.push_str(text);
+                self.0 = Repr::Heap(heap);
+            }
+            Repr::Heap(heap) => heap.push_str(text),
+        }
+    }
+
+    /// Add `c` to the end.
+    pub fn push(&mut self, c: char) {
+        self.push_str(c.encode_utf8(&mut [0; 4]));
+    }
+}
+
+impl Default for SmallString {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<&str> for SmallString {
+    fn from(text: &str) -> Self {
+        let mut small = Self::new();
+        small.push_str(text);
+        small
+    }
+}
+
+impl From<String> for SmallString {
+    fn from(text: String) -> Self {
+        match text.len() <= INLINE {
+            true => Self::from(text.as_str()),
+            false => Self(Repr::Heap(text)),
+        }
+    }
+}
+
+impl From<SmallString> for String {
+    fn from(small: SmallString) -> Self {
+        match small.0 {
+            Repr::Inline { .. } => String::from(small.as_str()),
+            Repr::Heap(text) => text,
+        }
+    }
+}
+
+impl Deref for SmallString {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl AsRef<str> for SmallString {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl Borrow<str> for SmallString {
+    fn borrow(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl Display for SmallString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Display::fmt(self.as_str(), f)
+    }
+}
+
+impl Debug for SmallString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl PartialEq for SmallString {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+
+impl Eq for SmallString {}
+
+impl PartialOrd for SmallString {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for SmallString {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.as_str().cmp(other.as_str())
+    }
+}
+
+impl Hash for SmallString {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_str().hash(state);
+    }
+}
+
+impl PartialEq<str> for SmallString {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl PartialEq<&str> for SmallString {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+impl PartialEq<String> for SmallString {
+    fn eq(&self, other: &String) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl PartialEq<SmallString> for str {
+    fn eq(&self, other: &SmallString) -> bool {
+        self == other.as_str()
+    }
+}
+
+impl PartialEq<SmallString> for &str {
+    fn eq(&self, other: &SmallString) -> bool {
+        *self == other.as_str()
+    }
+}
+
+impl PartialEq<SmallString> for String {
+    fn eq(&self, other: &SmallString) -> bool {
+        self == other.as_str()
+    }
+}
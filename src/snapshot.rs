@@ -0,0 +1,51 @@
+use alloc::sync::Arc;
+use core::{fmt::Display, time::Duration};
+
+/// An owned, immutable copy of a detailer's trace at some point in time.
+///
+/// Taking a snapshot does not clear the detailer, so a workflow can attach
+/// its trace so far to an error and keep going. Snapshots are cheap to clone.
+///
+/// ```rust
+/// use detailer::{detail, new_detailer};
+///
+/// let mut detailer = new_detailer!(Info, WithoutTiming);
+/// detail!(detailer, "connecting");
+///
+/// let snapshot = detailer.snapshot();
+/// detail!(detailer, "connected");
+///
+/// assert_eq!("connecting\n", snapshot.as_str());
+/// assert_eq!("connecting\nconnected\n", detailer.peek());
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Snapshot {
+    text: Arc<str>,
+    elapsed: Option<Duration>,
+}
+
+impl Snapshot {
+    pub(crate) fn new(text: &str, elapsed: Option<Duration>) -> Self {
+        Self {
+            text: text.into(),
+            elapsed,
+        }
+    }
+
+    /// The trace as it was rendered when the snapshot was taken
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+
+    /// How long the detailer had been running when the snapshot was taken,
+    /// if it was recording timings
+    pub fn elapsed(&self) -> Option<Duration> {
+        self.elapsed
+    }
+}
+
+impl Display for Snapshot {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str(self.text.trim_end())
+    }
+}
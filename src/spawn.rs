@@ -0,0 +1,108 @@
+use std::{
+    any::Any,
+    thread::{self, JoinHandle},
+};
+
+use crate::{Detailer, ScopeId};
+
+/// Work running on another thread with a detailer of its own, from
+/// [`Detailer::spawn_scope()`].
+///
+/// Join it to merge the thread's lines back into the detailer that spawned
+/// it, under the scope it was spawned with.
+pub struct SpawnedScope<T> {
+    scope: ScopeId,
+    handle: JoinHandle<(Detailer, T)>,
+}
+
+impl Detailer {
+    /// Run `work` on a new thread, with a child detailer for it to record
+    /// into.
+    ///
+    /// The scope's line is recorded now. When the work is
+    /// [joined](SpawnedScope::join), the child's lines are merged under it,
+    /// on this detailer's timeline, so a multi-threaded workflow still
+    /// produces one trace. If the thread panics, the child flushes what it
+    /// had on its own.
+    ///
+    /// ```
+    /// use detailer::{detail, new_detailer};
+    ///
+    /// let mut detailer = new_detailer!(Info, WithoutTiming);
+    /// let compression = detailer.spawn_scope(format_args!("background compression"), |child| {
+    ///     detail!(child, "compressed 3 blocks");
+    ///     3
+    /// });
+    /// detail!(detailer, "serving");
+    ///
+    /// assert_eq!(3, compression.join(&mut detailer).unwrap());
+    /// detailer.on_flush(|trace| {
+    ///     assert_eq!("background compression\n  compressed 3 blocks\nserving", trace.text)
+    /// });
+    /// detailer.flush();
+    /// ```
+    ///
+    /// Lines the child records go under the scope's line even when this
+    /// detailer recorded into the scope between spawning and joining:
+    ///
+    /// ```
+    /// use detailer::{detail, detail_in, new_detailer};
+    ///
+    /// let mut detailer = new_detailer!(Info, WithoutTiming);
+    /// let compression = detailer.spawn_scope(format_args!("compression"), |child| {
+    ///     detail!(child, "compressed 3 blocks");
+    /// });
+    /// detail_in!(detailer, compression.scope(), "queued");
+    /// detail!(detailer, "serving");
+    /// compression.join(&mut detailer).unwrap();
+    /// detail!(detailer, "done");
+    /// detailer.on_flush(|trace| {
+    ///     assert_eq!(
+    ///         "compression\n  queued\n  compressed 3 blocks\nserving\ndone",
+    ///         trace.text,
+    ///     )
+    /// });
+    /// detailer.flush();
+    /// ```
+    pub fn spawn_scope<T, F>(&mut self, scope_name: std::fmt::Arguments, work: F) -> SpawnedScope<T>
+    where
+        F: FnOnce(&mut Detailer) -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        let scope = self.enter_scope(scope_name);
+        let mut child = self.child();
+        let handle = thread::spawn(move || {
+            let value = work(&mut child);
+            (child, value)
+        });
+        SpawnedScope { scope, handle }
+    }
+}
+
+impl<T> SpawnedScope<T> {
+    /// Wait for the thread to finish, and merge its lines into `detailer`.
+    /// Returns what the work returned, or the panic if it panicked.
+    pub fn join(self, detailer: &mut Detailer) -> Result<T, Box<dyn Any + Send + 'static>> {
+        match self.handle.join() {
+            Ok((child, value)) => {
+                detailer.append_in(self.scope, child);
+                Ok(value)
+            }
+            Err(panic) => {
+                detailer.log_in(self.scope, log::Level::Error, format_args!("panicked"));
+                Err(panic)
+            }
+        }
+    }
+
+    /// The scope the work was spawned with, for recording into it with
+    /// [`Detailer::log_in()`] while the work runs
+    pub fn scope(&self) -> ScopeId {
+        self.scope
+    }
+
+    /// Whether the thread has finished
+    pub fn is_finished(&self) -> bool {
+        self.handle.is_finished()
+    }
+}
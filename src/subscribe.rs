@@ -0,0 +1,198 @@
+use std::{
+    collections::VecDeque,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, Condvar, Mutex, PoisonError,
+    },
+    time::{Duration, Instant},
+};
+
+use crate::{RecordedLine, SmallString};
+
+/// A line a detailer recorded, as received by a [`Subscription`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct LineEvent {
+    /// The level the line was logged at
+    pub level: log::Level,
+    /// Time since the trace started, if the detailer records timing
+    pub elapsed: Option<Duration>,
+    /// Time since the unix epoch, if the detailer
+    /// [stamps lines](crate::Detailer::wall_clock_timestamps) with it
+    pub since_epoch: Option<Duration>,
+    /// How many scopes deep the line was recorded
+    pub indent: usize,
+    /// The line's message
+    pub message: SmallString,
+    /// The line's [target](crate::Detailer::log_target), if it has its own
+    pub target: Option<&'static str>,
+    /// The [lane](crate::Detailer::lane) the line was recorded in, if any
+    pub lane: Option<&'static str>,
+    /// The attributes of a scope's line, or the fields of an
+    /// [event](crate::Detailer::event), as keys and values
+    pub attributes: Vec<(&'static str, String)>,
+}
+
+/// Receives the lines a detailer records, from
+/// [`Detailer::subscribe()`](crate::Detailer::subscribe).
+///
+/// The subscription holds at most its capacity. When a slow reader falls
+/// behind, the oldest lines are dropped so the detailer never waits on it.
+pub struct Subscription {
+    channel: Arc<Channel>,
+}
+
+/// The detailer's end of a subscription.
+pub(crate) struct Publisher {
+    channel: Arc<Channel>,
+}
+
+struct Channel {
+    lines: Mutex<VecDeque<LineEvent>>,
+    ready: Condvar,
+    capacity: usize,
+    dropped: AtomicU64,
+    closed: AtomicBool,
+}
+
+pub(crate) fn channel(capacity: usize) -> (Publisher, Subscription) {
+    let channel = Arc::new(Channel {
+        lines: Mutex::new(VecDeque::with_capacity(capacity.min(1024))),
+        ready: Condvar::new(),
+        capacity: capacity.max(1),
+        dropped: AtomicU64::new(0),
+        closed: AtomicBool::new(false),
+    });
+    (
+        Publisher {
+            channel: channel.clone(),
+        },
+        Subscription { channel },
+    )
+}
+
+impl Publisher {
+    /// Whether the subscription was dropped
+    pub(crate) fn is_abandoned(&self) -> bool {
+        Arc::strong_count(&self.channel) == 1
+    }
+
+    pub(crate) fn publish(&self, line: &RecordedLine) {
+        let mut lines = self
+            .channel
+            .lines
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        if self.channel.capacity <= lines.len() {
+            lines.pop_front();
+            self.channel.dropped.fetch_add(1, Ordering::Relaxed);
+        }
+        lines.push_back(LineEvent {
+            level: line.level,
+            elapsed: line.elapsed,
+            since_epoch: line.since_epoch,
+            indent: line.indent,
+            message: SmallString::from(line.message),
+            target: line.target,
+            lane: line.lane,
+            attributes: line
+                .attributes
+                .iter()
+                .map(|(key, value)| (key, value.to_string()))
+                .collect(),
+        });
+        drop(lines);
+        self.channel.ready.notify_one();
+    }
+}
+
+impl Drop for Publisher {
+    fn drop(&mut self) {
+        // Held while closing so a waiting reader can't miss the wakeup.
+        let _lines = self
+            .channel
+            .lines
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        self.channel.closed.store(true, Ordering::Relaxed);
+        self.channel.ready.notify_all();
+    }
+}
+
+impl Subscription {
+    /// The next line, waiting for one to be recorded. Returns `None` once the
+    /// detailer is dropped and every line it recorded has been received.
+    pub fn recv(&self) -> Option<LineEvent> {
+        let mut lines = self
+            .channel
+            .lines
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        loop {
+            if let Some(line) = lines.pop_front() {
+                return Some(line);
+            }
+            if self.channel.closed.load(Ordering::Relaxed) {
+                return None;
+            }
+            lines = self
+                .channel
+                .ready
+                .wait(lines)
+                .unwrap_or_else(PoisonError::into_inner);
+        }
+    }
+
+    /// The next line, waiting at most `timeout` for one to be recorded.
+    pub fn recv_timeout(&self, timeout: Duration) -> Option<LineEvent> {
+        let deadline = Instant::now() + timeout;
+        let mut lines = self
+            .channel
+            .lines
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        loop {
+            if let Some(line) = lines.pop_front() {
+                return Some(line);
+            }
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if self.channel.closed.load(Ordering::Relaxed) || remaining.is_zero() {
+                return None;
+            }
+            lines = self
+                .channel
+                .ready
+                .wait_timeout(lines, remaining)
+                .unwrap_or_else(PoisonError::into_inner)
+                .0;
+        }
+    }
+
+    /// The next line, if one is waiting.
+    pub fn try_recv(&self) -> Option<LineEvent> {
+        self.channel
+            .lines
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .pop_front()
+    }
+
+    /// How many lines were dropped because the subscription was full
+    pub fn dropped(&self) -> u64 {
+        self.channel.dropped.load(Ordering::Relaxed)
+    }
+
+    /// Whether the detailer was dropped. Lines it recorded may still be
+    /// waiting.
+    pub fn is_closed(&self) -> bool {
+        self.channel.closed.load(Ordering::Relaxed)
+    }
+}
+
+impl Iterator for Subscription {
+    type Item = LineEvent;
+
+    fn next(&mut self) -> Option<LineEvent> {
+        self.recv()
+    }
+}
@@ -0,0 +1,140 @@
+use std::{
+    fmt::Arguments,
+    sync::{Arc, Mutex, MutexGuard, PoisonError, TryLockResult},
+};
+
+use crate::{ContextGuard, DetailScopeGuard, Detailer, ScopeId, Snapshot};
+
+/// A cheaply cloneable, thread-safe handle to a [`Detailer`].
+///
+/// Use this when one workflow's detail needs to be recorded from several
+/// places that can't share a `&mut Detailer`, like middleware layers that pass
+/// a request's detailer along through `http::Extensions`, which requires
+/// `Clone + Send + Sync + 'static` values.
+///
+/// The detailer is flushed when the last handle is dropped.
+///
+/// ```rust
+/// use detailer::{detail, new_detailer, scope, SyncDetailer};
+///
+/// let detailer = SyncDetailer::from(new_detailer!(Info, WithoutTiming));
+/// let for_middleware = detailer.clone();
+///
+/// detail!(for_middleware, "authenticated");
+/// {
+///     let _scope = scope!(detailer, "handling");
+///     detail!(detailer, "handled");
+/// }
+/// assert_eq!("authenticated\nhandling\n  handled\n", detailer.lock().peek());
+/// ```
+#[derive(Clone, Default)]
+pub struct SyncDetailer {
+    inner: Arc<Mutex<Detailer>>,
+}
+
+impl SyncDetailer {
+    /// Share a detailer.
+    pub fn new(detailer: Detailer) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(detailer)),
+        }
+    }
+
+    /// Lock the detailer for direct access.
+    ///
+    /// A panic on another thread while it held the lock does not make the
+    /// detailer unusable; whatever it recorded is kept.
+    pub fn lock(&self) -> MutexGuard<'_, Detailer> {
+        self.inner.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Lock the detailer, unless it is already locked.
+    pub(crate) fn try_lock(&self) -> TryLockResult<MutexGuard<'_, Detailer>> {
+        self.inner.try_lock()
+    }
+
+    /// log a line, if the level is enabled.
+    ///
+    /// ```
+    /// use detailer::{new_detailer, SyncDetailer};
+    ///
+    /// let detailer = SyncDetailer::new(new_detailer!());
+    ///
+    /// detailer.log(log::Level::Warn, format_args!("yikes {}", 42));
+    /// ```
+    pub fn log(&self, level: log::Level, message: Arguments) {
+        self.lock().log(level, message)
+    }
+
+    /// log a constant line, if the level is enabled.
+    pub fn log_static(&self, level: log::Level, message: &'static str) {
+        self.lock().log_static(level, message)
+    }
+
+    /// Indent output one more level as long as the scope guard exists
+    pub fn scope(&self, scope_name: Arguments) -> DetailScopeGuard {
+        self.lock().scope(scope_name)
+    }
+
+    /// Indent output one more level, and record lines at `level` instead of
+    /// the detailer's level, as long as the scope guard exists
+    pub fn scope_at_level(
+        &self,
+        level: log::LevelFilter,
+        scope_name: Arguments,
+    ) -> DetailScopeGuard {
+        self.lock().scope_at_level(level, scope_name)
+    }
+
+    /// Begin a scope that is addressed by its handle, for steps that run
+    /// concurrently. See [`Detailer::enter_scope()`].
+    ///
+    /// ```
+    /// use detailer::{detail_in, new_detailer, SyncDetailer};
+    ///
+    /// let detailer = SyncDetailer::new(new_detailer!(Info, WithoutTiming));
+    /// let fetch = detailer.enter_scope(format_args!("fetch"));
+    /// let render = detailer.enter_scope(format_args!("render"));
+    /// detail_in!(detailer, render, "layout");
+    /// detail_in!(detailer, fetch, "connected");
+    /// detailer.lock().on_flush(|trace| {
+    ///     assert_eq!("fetch\n  connected\nrender\n  layout", trace.text)
+    /// });
+    /// detailer.flush();
+    /// ```
+    pub fn enter_scope(&self, scope_name: Arguments) -> ScopeId {
+        self.lock().enter_scope(scope_name)
+    }
+
+    /// Begin a scope nested under `parent`.
+    pub fn enter_scope_in(&self, parent: ScopeId, scope_name: Arguments) -> ScopeId {
+        self.lock().enter_scope_in(parent, scope_name)
+    }
+
+    /// log a line under `scope`, if the level is enabled.
+    pub fn log_in(&self, scope: ScopeId, level: log::Level, message: Arguments) {
+        self.lock().log_in(scope, level, message)
+    }
+
+    /// Mark the lines recorded while the guard exists with `key=value`. See
+    /// [`Detailer::push_context()`].
+    pub fn push_context(&self, key: &'static str, value: impl std::fmt::Display) -> ContextGuard {
+        self.lock().push_context(key, value)
+    }
+
+    /// Copy what's currently accumulated, without clearing it
+    pub fn snapshot(&self) -> Snapshot {
+        self.lock().snapshot()
+    }
+
+    /// Output and clear the contents
+    pub fn flush(&self) {
+        self.lock().flush()
+    }
+}
+
+impl From<Detailer> for SyncDetailer {
+    fn from(detailer: Detailer) -> Self {
+        Self::new(detailer)
+    }
+}
@@ -0,0 +1,99 @@
+use std::{
+    future::Future,
+    panic::{self, AssertUnwindSafe},
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use crate::{Detailer, SyncDetailer};
+
+/// A future with a detailer of its own, from [`Detailer::detailed()`].
+///
+/// It works with any executor. When the future completes or panics, its
+/// detailer records that along with how long the task ran and how much of
+/// that it spent being polled, then flushes.
+pub struct DetailedFuture<F> {
+    future: Pin<Box<F>>,
+    detailer: SyncDetailer,
+    busy_micros: u64,
+}
+
+impl Detailer {
+    /// Wrap an async task with a detailer of its own, for spawning on an
+    /// executor.
+    ///
+    /// `task` is given a handle to a detached detailer (see
+    /// [`detach()`](Self::detach)) that shares this detailer's trace ID and
+    /// can be recorded into across `.await`s.
+    ///
+    /// ```
+    /// use std::{
+    ///     future::Future,
+    ///     pin::pin,
+    ///     sync::Arc,
+    ///     task::{Context, Poll, Wake, Waker},
+    /// };
+    ///
+    /// use detailer::{detail, new_detailer};
+    ///
+    /// let mut detailer = new_detailer!(Info, WithoutTiming);
+    /// let task = detailer.detailed("refresh cache", |detailer| async move {
+    ///     detail!(detailer, "fetched 3 keys");
+    ///     3
+    /// });
+    ///
+    /// // A real program would hand the task to its executor, like tokio::spawn(task).
+    /// struct Noop;
+    /// impl Wake for Noop {
+    ///     fn wake(self: Arc<Self>) {}
+    /// }
+    /// let waker = Waker::from(Arc::new(Noop));
+    /// let poll = pin!(task).poll(&mut Context::from_waker(&waker));
+    /// assert_eq!(Poll::Ready(3), poll);
+    /// ```
+    pub fn detailed<T, F>(&mut self, name: &str, task: T) -> DetailedFuture<F>
+    where
+        T: FnOnce(SyncDetailer) -> F,
+        F: Future,
+    {
+        let detailer = SyncDetailer::new(self.detach(name));
+        DetailedFuture {
+            future: Box::pin(task(detailer.clone())),
+            detailer,
+            busy_micros: 0,
+        }
+    }
+}
+
+impl<F: Future> Future for DetailedFuture<F> {
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<F::Output> {
+        let this = self.get_mut();
+        let entered = this.detailer.lock().elapsed_micros();
+        let poll = panic::catch_unwind(AssertUnwindSafe(|| this.future.as_mut().poll(cx)));
+        let mut detailer = this.detailer.lock();
+        let now = detailer.elapsed_micros();
+        if let (Some(entered), Some(now)) = (entered, now) {
+            this.busy_micros += now.saturating_sub(entered);
+        }
+        let (level, outcome) = match &poll {
+            Ok(Poll::Pending) => return Poll::Pending,
+            Ok(Poll::Ready(_)) => (log::Level::Info, "complete"),
+            Err(_) => (log::Level::Error, "panicked"),
+        };
+        match now {
+            Some(now) => detailer.log(
+                level,
+                format_args!("{outcome} after {now}µs, {}µs busy", this.busy_micros),
+            ),
+            None => detailer.log(level, format_args!("{outcome}")),
+        }
+        detailer.flush();
+        drop(detailer);
+        match poll {
+            Ok(ready) => ready,
+            Err(panic) => panic::resume_unwind(panic),
+        }
+    }
+}
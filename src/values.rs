@@ -0,0 +1,180 @@
+#[cfg(not(feature = "arena"))]
+use alloc::string::String;
+use alloc::vec::Vec;
+#[cfg(feature = "arena")]
+use core::ptr::NonNull;
+use core::{
+    fmt::{Arguments, Write},
+    ops::Range,
+};
+
+#[cfg(feature = "arena")]
+use bumpalo::Bump;
+
+use crate::Attributes;
+
+/// The keys and values of the attributes of a trace's lines, and of the
+/// fields of its events, kept until the trace is reset.
+///
+/// By default the values are written one after another into one buffer.
+/// With the `arena` feature they are allocated in a bump arena instead,
+/// which grows by adding chunks rather than by moving what it holds, and
+/// which [`clear()`](Self::clear) rewinds to its start.
+pub(crate) struct AttributeValues {
+    entries: Vec<(&'static str, Value)>,
+    #[cfg(not(feature = "arena"))]
+    text: String,
+    #[cfg(feature = "arena")]
+    bump: Bump,
+}
+
+/// Where one value is kept
+#[cfg(not(feature = "arena"))]
+type Value = Range<usize>;
+
+/// Where one value is kept: a string allocated in the arena
+#[cfg(feature = "arena")]
+#[derive(Clone, Copy)]
+struct Value {
+    start: NonNull<u8>,
+    len: usize,
+}
+
+// SAFETY: values are only allocated in the bump through `&mut self`, so
+// sharing `&AttributeValues` between threads only reads strings that are
+// already allocated, and never touches the bump's own cells. The values
+// point into the bump, which moves with the struct.
+#[cfg(feature = "arena")]
+unsafe impl Send for AttributeValues {}
+#[cfg(feature = "arena")]
+unsafe impl Sync for AttributeValues {}
+
+impl AttributeValues {
+    pub(crate) fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+            #[cfg(not(feature = "arena"))]
+            text: String::new(),
+            #[cfg(feature = "arena")]
+            bump: Bump::new(),
+        }
+    }
+
+    /// How many values there are, which is where the next one goes
+    pub(crate) fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Add a value, formatted from `value`
+    pub(crate) fn push(&mut self, key: &'static str, value: Arguments) {
+        #[cfg(not(feature = "arena"))]
+        let value = {
+            let start = self.text.len();
+            let _ = self.text.write_fmt(value);
+            start..self.text.len()
+        };
+        #[cfg(feature = "arena")]
+        let value = {
+            let mut text = bumpalo::collections::String::new_in(&self.bump);
+            let _ = text.write_fmt(value);
+            Value::new(text.into_bump_str())
+        };
+        self.entries.push((key, value));
+    }
+
+    /// Add a value that is already formatted
+    pub(crate) fn push_str(&mut self, key: &'static str, value: &str) {
+        #[cfg(not(feature = "arena"))]
+        let value = {
+            let start = self.text.len();
+            self.text.push_str(value);
+            start..self.text.len()
+        };
+        #[cfg(feature = "arena")]
+        let value = Value::new(self.bump.alloc_str(value));
+        self.entries.push((key, value));
+    }
+
+    /// Remove the values from `len` on. With the arena, their space is
+    /// only given back by [`clear()`](Self::clear).
+    pub(crate) fn truncate(&mut self, len: usize) {
+        #[cfg(not(feature = "arena"))]
+        if let Some((_, value)) = self.entries.get(len) {
+            self.text.truncate(value.start);
+        }
+        self.entries.truncate(len);
+    }
+
+    /// Remove every value, keeping the memory they took for the next trace.
+    pub(crate) fn clear(&mut self) {
+        self.entries.clear();
+        #[cfg(not(feature = "arena"))]
+        self.text.clear();
+        #[cfg(feature = "arena")]
+        self.bump.reset();
+    }
+
+    /// Free the memory that [`clear()`](Self::clear) keeps.
+    pub(crate) fn shrink(&mut self) {
+        self.clear();
+        self.entries.shrink_to_fit();
+        #[cfg(not(feature = "arena"))]
+        {
+            self.text = String::new();
+        }
+        #[cfg(feature = "arena")]
+        {
+            self.bump = Bump::new();
+        }
+    }
+
+    /// The bytes allocated for values
+    pub(crate) fn capacity(&self) -> usize {
+        let entries = self.entries.capacity() * core::mem::size_of::<(&str, Value)>();
+        #[cfg(not(feature = "arena"))]
+        let values = self.text.capacity();
+        #[cfg(feature = "arena")]
+        let values = self.bump.allocated_bytes();
+        entries + values
+    }
+
+    /// The attributes in `entries`
+    pub(crate) fn attributes(&self, entries: Range<usize>) -> Attributes<'_> {
+        Attributes::new(self, entries)
+    }
+
+    /// The keys and values in `entries`
+    pub(crate) fn iter(
+        &self,
+        entries: Range<usize>,
+    ) -> impl Iterator<Item = (&'static str, &str)> + '_ {
+        self.entries[entries]
+            .iter()
+            .map(move |(key, value)| (*key, self.value(value)))
+    }
+
+    fn value(&self, value: &Value) -> &str {
+        #[cfg(not(feature = "arena"))]
+        return &self.text[value.clone()];
+        // SAFETY: the value was allocated in the bump, which is only reset
+        // by `clear()` and replaced by `shrink()` after the values in it
+        // are removed, both through `&mut self`.
+        #[cfg(feature = "arena")]
+        unsafe {
+            core::str::from_utf8_unchecked(core::slice::from_raw_parts(
+                value.start.as_ptr(),
+                value.len,
+            ))
+        }
+    }
+}
+
+#[cfg(feature = "arena")]
+impl Value {
+    fn new(value: &str) -> Self {
+        Self {
+            start: NonNull::from(value).cast(),
+            len: value.len(),
+        }
+    }
+}
@@ -0,0 +1,73 @@
+//! [warp](https://docs.rs/warp) filters that give each request a detailer of
+//! its own.
+//!
+//! [`detailer()`] extracts a new detailer for each request, and
+//! [`detailed()`] flushes it with the reply's status once the reply is
+//! produced. Routes in between hand the detailer along with their reply:
+//!
+//! ```
+//! use detailer::{detail, new_detailer, SyncDetailer};
+//! use warp::Filter;
+//!
+//! # #[tokio::main(flavor = "current_thread")]
+//! # async fn main() {
+//! let users = detailer::warp::detailed(
+//!     detailer::warp::detailer(|| {
+//!         let mut detailer = new_detailer!(Info, WithoutTiming);
+//!         detailer.on_flush(|trace| {
+//!             assert_eq!("GET /users/7\nlooking up user 7\n200 OK", trace.text)
+//!         });
+//!         detailer
+//!     })
+//!     .and(warp::path!("users" / u32))
+//!     .map(|detailer: SyncDetailer, id: u32| {
+//!         detail!(detailer, "looking up user {id}");
+//!         (detailer, warp::reply())
+//!     }),
+//! );
+//!
+//! let response = warp::test::request().path("/users/7").reply(&users).await;
+//! assert_eq!(200, response.status());
+//! # }
+//! ```
+
+use std::convert::Infallible;
+
+use warp::{
+    filters::path::FullPath,
+    http::Method,
+    reply::{Reply, Response},
+    Filter, Rejection,
+};
+
+use crate::{detail, Detailer, SyncDetailer};
+
+/// A filter that extracts a detailer from `make` for each request, with the
+/// request's method and path recorded.
+pub fn detailer<M>(make: M) -> impl Filter<Extract = (SyncDetailer,), Error = Infallible> + Clone
+where
+    M: Fn() -> Detailer + Clone + Send + Sync + 'static,
+{
+    warp::method()
+        .and(warp::path::full())
+        .map(move |method: Method, path: FullPath| {
+            let detailer = SyncDetailer::new(make());
+            detail!(detailer, "{method} {}", path.as_str());
+            detailer
+        })
+}
+
+/// Flush the detailer that `routes` extracts along with its reply, once the
+/// reply is produced, with the reply's status recorded.
+pub fn detailed<F, R>(routes: F) -> impl Filter<Extract = (Response,), Error = Rejection> + Clone
+where
+    F: Filter<Extract = ((SyncDetailer, R),), Error = Rejection> + Clone,
+    R: Reply,
+{
+    routes.map(|(detailer, reply): (SyncDetailer, R)| {
+        let response = reply.into_response();
+        detail!(detailer, "{}", response.status());
+        detailer.flush();
+        response
+    })
+}